@@ -0,0 +1,351 @@
+use super::render;
+use crate::fleet::{self, FleetResult, Progress};
+use crate::ops::{Operation, Outcome};
+use crate::VMConnection;
+use anyhow::{anyhow, Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Subcomando não interativo já interpretado
+pub struct CommandArgs {
+    pub operation: Operation,
+    pub json: bool,           // Saída em JSON em vez de texto
+    pub all: bool,            // Todas as VMs do inventário
+    pub parallelism: usize,   // VMs atendidas ao mesmo tempo
+    pub targets: Vec<String>, // Nomes ou tags de VMs
+}
+
+/// Relatório JSON de um subcomando
+#[derive(Serialize)]
+struct Report<'a> {
+    command: &'static str,
+    ok: bool, // Todas as VMs tiveram sucesso
+    results: &'a [&'a Outcome],
+}
+
+pub fn print_usage() {
+    println!("Uso: central_monitor [--inventory <arquivo>] [<comando> [opções] <alvos>...]");
+    println!();
+    println!("Sem comando, abre o menu interativo.");
+    println!();
+    println!("Comandos:");
+    println!("  test        Testa a conexão SSH e detecta o sistema");
+    println!("  deploy      Instala/atualiza e inicia o agente");
+    println!("  status      Verifica se o agente está em execução");
+    println!("  logs        Mostra os últimos registros do log");
+    println!("  restart     Reinicia o agente");
+    println!("  stop        Para o agente");
+    println!("  remove      Remove o agente e seus arquivos");
+    println!("  clear-logs  Apaga os logs do agente");
+    println!();
+    println!("Alvos: nomes de VMs ou tags do inventário (ou --all para todas)");
+    println!();
+    println!("Opções:");
+    println!("  --all       Todas as VMs do inventário");
+    println!("  --json      Saída em JSON");
+    println!(
+        "  --parallel <n>  VMs atendidas ao mesmo tempo (padrão: {})",
+        fleet::DEFAULT_PARALLELISM
+    );
+    println!();
+    println!(
+        "O código de saída é 0 se a operação teve sucesso em todas as VMs e 1 caso contrário."
+    );
+}
+
+/// Interpreta os argumentos de um subcomando
+/// Retorna None para `help`, `--help` e `-h`
+pub fn parse_command(args: &[String]) -> Result<Option<CommandArgs>> {
+    let (name, rest) = args
+        .split_first()
+        .ok_or_else(|| anyhow!("Nenhum comando informado"))?;
+    if matches!(name.as_str(), "help" | "--help" | "-h") {
+        return Ok(None);
+    }
+    let operation = Operation::parse(name).ok_or_else(|| {
+        anyhow!(
+            "Comando desconhecido: {} (use central_monitor --help)",
+            name
+        )
+    })?;
+
+    let mut command = CommandArgs {
+        operation,
+        json: false,
+        all: false,
+        parallelism: fleet::DEFAULT_PARALLELISM,
+        targets: Vec::new(),
+    };
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--json" => command.json = true,
+            "--all" => command.all = true,
+            "--parallel" => {
+                command.parallelism = rest
+                    .next()
+                    .and_then(|value| value.parse().ok())
+                    .filter(|parallelism| *parallelism > 0)
+                    .context("--parallel requer um número maior que zero")?;
+            }
+            "--help" | "-h" => return Ok(None),
+            option if option.starts_with("--") => {
+                return Err(anyhow!("Opção desconhecida: {}", option));
+            }
+            target => command.targets.push(target.to_string()),
+        }
+    }
+    if !command.all && command.targets.is_empty() {
+        return Err(anyhow!(
+            "Informe as VMs (nomes ou tags) ou --all para todas"
+        ));
+    }
+    Ok(Some(command))
+}
+
+/// VMs selecionadas pelos alvos, na ordem do inventário
+/// Cada alvo é o nome de uma VM (sem diferenciar maiúsculas) ou uma tag
+fn select_targets(vms: &[VMConnection], command: &CommandArgs) -> Result<Vec<usize>> {
+    if command.all {
+        return Ok((0..vms.len()).collect());
+    }
+    let mut selected = vec![false; vms.len()];
+    for target in &command.targets {
+        let mut found = false;
+        for (idx, vm) in vms.iter().enumerate() {
+            if vm.name().eq_ignore_ascii_case(target) || vm.entry.tags.contains(target) {
+                selected[idx] = true;
+                found = true;
+            }
+        }
+        if !found {
+            return Err(anyhow!(
+                "Alvo desconhecido: {} (não é nome nem tag de uma VM do inventário)",
+                target
+            ));
+        }
+    }
+    Ok((0..vms.len()).filter(|idx| selected[*idx]).collect())
+}
+
+/// Executa o subcomando nas VMs selecionadas, em paralelo
+/// Retorna se a operação teve sucesso em todas elas
+pub async fn run_command(command: CommandArgs, vms: Vec<VMConnection>) -> Result<bool> {
+    let targets = select_targets(&vms, &command)?;
+    let selected: Vec<VMConnection> = vms
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| targets.contains(idx))
+        .map(|(_, vm)| vm)
+        .collect();
+
+    // Em JSON a saída padrão fica só com o relatório
+    let json = command.json;
+    let results = fleet::run(
+        selected,
+        command.operation,
+        command.parallelism,
+        |progress| {
+            if !json {
+                print_progress(&progress);
+            }
+        },
+    )
+    .await;
+
+    let ok = results.iter().all(|result| result.outcome.ok);
+    if json {
+        let outcomes: Vec<&Outcome> = results.iter().map(|result| &result.outcome).collect();
+        let report = Report {
+            command: command.operation.name(),
+            ok,
+            results: &outcomes,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if results.len() > 1 {
+        print_summary(command.operation, &results);
+    }
+    Ok(ok)
+}
+
+/// Andamento de um lote em texto: uma linha ao iniciar cada VM e o
+/// resultado completo ao terminar
+pub(super) fn print_progress(progress: &Progress) {
+    match progress {
+        Progress::Started { vm } => println!("🔄 {}...", vm.cyan()),
+        Progress::Finished {
+            done,
+            total,
+            result,
+        } => {
+            let outcome = &result.outcome;
+            let vm = format!("{} ({})", outcome.vm, outcome.host);
+            let counter = format!("[{}/{}]", done, total);
+            let elapsed = format_elapsed(result.elapsed);
+            if outcome.ok {
+                println!(
+                    "✅ {} {}: {} ({})",
+                    counter.dimmed(),
+                    vm.green(),
+                    outcome.message,
+                    elapsed
+                );
+            } else {
+                println!(
+                    "❌ {} {}: {} ({})",
+                    counter.dimmed(),
+                    vm.red(),
+                    outcome.message.red(),
+                    elapsed
+                );
+            }
+            for line in outcome.details.lines() {
+                println!("   │ {}", line);
+            }
+            for record in &outcome.records {
+                render::print_record(record);
+            }
+        }
+    }
+}
+
+/// Tabela final de um lote com sucessos e falhas por VM
+pub(super) fn print_summary(operation: Operation, results: &[FleetResult]) {
+    let failures = results.iter().filter(|result| !result.outcome.ok).count();
+    println!(
+        "\n📋 Resumo de {}: {} com sucesso, {} com falha",
+        operation.name().bold(),
+        (results.len() - failures).to_string().green(),
+        if failures > 0 {
+            failures.to_string().red()
+        } else {
+            failures.to_string().normal()
+        }
+    );
+
+    let vm_width = results
+        .iter()
+        .map(|result| result.outcome.vm.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("VM".len());
+    let host_width = results
+        .iter()
+        .map(|result| result.outcome.host.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Host".len());
+
+    println!(
+        "{:<vm_width$}  {:<host_width$}  {:<9}  {:>7}  Mensagem",
+        "VM", "Host", "Resultado", "Tempo"
+    );
+    println!("{}", "─".repeat(vm_width + host_width + 34));
+    for result in results {
+        let outcome = &result.outcome;
+        // O preenchimento é feito antes da cor, que não ocupa espaço na tela
+        let status = if outcome.ok {
+            format!("{:<9}", "OK").green()
+        } else {
+            format!("{:<9}", "FALHA").red().bold()
+        };
+        println!(
+            "{:<vm_width$}  {:<host_width$}  {}  {:>7}  {}",
+            outcome.vm,
+            outcome.host,
+            status,
+            format_elapsed(result.elapsed),
+            outcome.message
+        );
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    format!("{:.1}s", elapsed.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::VmEntry;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn vm(name: &str, tags: &[&str]) -> VMConnection {
+        VMConnection::new(VmEntry {
+            name: name.to_string(),
+            ssh_alias: Some(name.to_lowercase()),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        })
+    }
+
+    fn targets(line: &str) -> Vec<String> {
+        let vms = vec![
+            vm("web1", &["lab", "web"]),
+            vm("Banco", &["lab"]),
+            vm("web2", &["web"]),
+        ];
+        let command = parse_command(&args(line)).unwrap().unwrap();
+        select_targets(&vms, &command)
+            .unwrap()
+            .into_iter()
+            .map(|idx| vms[idx].name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn parses_options_and_targets() {
+        let command = parse_command(&args("status --json --parallel 8 web1 lab"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(command.operation, Operation::Status);
+        assert!(command.json);
+        assert!(!command.all);
+        assert_eq!(command.parallelism, 8);
+        assert_eq!(command.targets, vec!["web1", "lab"]);
+
+        let command = parse_command(&args("clear-logs --all")).unwrap().unwrap();
+        assert_eq!(command.operation, Operation::ClearLogs);
+        assert_eq!(command.parallelism, fleet::DEFAULT_PARALLELISM);
+        assert!(command.all);
+
+        assert!(parse_command(&args("help")).unwrap().is_none());
+        assert!(parse_command(&args("deploy web1 --help"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_commands() {
+        let error = |line: &str| match parse_command(&args(line)) {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("{} deveria falhar", line),
+        };
+        assert!(error("").contains("Nenhum comando"));
+        assert!(error("reboot web1").contains("Comando desconhecido: reboot"));
+        assert!(error("status --parallel 0 web1").contains("--parallel"));
+        assert!(error("status --parallel muitos web1").contains("--parallel"));
+        assert!(error("status web1 --parallel").contains("--parallel"));
+        assert!(error("status --force web1").contains("Opção desconhecida: --force"));
+        assert!(error("status --json").contains("Informe as VMs"));
+    }
+
+    #[test]
+    fn selects_targets_by_name_or_tag_in_inventory_order() {
+        assert_eq!(targets("status BANCO"), vec!["Banco"]);
+        assert_eq!(targets("status web"), vec!["web1", "web2"]);
+        assert_eq!(targets("status web2 lab"), vec!["web1", "Banco", "web2"]);
+        // Um nome e uma tag que o incluem não duplicam a VM
+        assert_eq!(targets("status web1 web"), vec!["web1", "web2"]);
+        assert_eq!(targets("status --all"), vec!["web1", "Banco", "web2"]);
+
+        let vms = vec![vm("web1", &["lab"])];
+        let command = parse_command(&args("status web1 db")).unwrap().unwrap();
+        let error = select_targets(&vms, &command).unwrap_err().to_string();
+        assert!(error.contains("Alvo desconhecido: db"), "{}", error);
+    }
+}
@@ -1,46 +1,54 @@
+use super::commands;
+use crate::fleet::{self, FleetResult, Progress};
+use crate::inventory::{ExpectedOs, Inventory, VmEntry};
+use crate::ops::{self, Operation};
+use crate::ssh_config::{self, ResolvedHost};
 use crate::VMConnection;
-use anyhow::{Context, Result};
-use chrono::{DateTime, Local, TimeZone, Utc};
+use anyhow::Result;
 use colored::*;
-use dialoguer::{theme::ColorfulTheme, Select};
-use serde_json;
-use std::process::Command;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, MultiSelect, Select};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// Ações do menu principal sobre uma VM (opções 0 a 7): texto da seleção
+/// da VM e operação executada
+const VM_ACTIONS: [(&str, Operation); 8] = [
+    ("Selecione a VM para testar conexão", Operation::Test),
+    (
+        "Selecione a VM para instalar/atualizar o agente",
+        Operation::Deploy,
+    ),
+    ("Selecione a VM para verificar o status", Operation::Status),
+    ("Selecione a VM para ver os logs", Operation::Logs),
+    ("Selecione a VM para reiniciar o agente", Operation::Restart),
+    ("Selecione a VM para parar o agente", Operation::Stop),
+    ("Selecione a VM para remover o agente", Operation::Remove),
+    ("Selecione a VM para apagar os logs", Operation::ClearLogs),
+];
 
 pub struct Menu {
     vms: Vec<VMConnection>,
+    inventory_path: PathBuf, // Arquivo regravado quando o inventário muda
 }
 
 impl Menu {
-    pub fn new(vms: Vec<VMConnection>) -> Self {
-        Self { vms }
-    }
-
-    fn test_vm_connection(&mut self, vm_idx: usize) -> Result<()> {
-        let vm = &mut self.vms[vm_idx];
-        match vm.test_connection() {
-            Ok(true) => {
-                println!("\n✅ Conectado com sucesso:");
-                println!("   • Host: {}", vm.name.green().bold());
-                println!("   • SSH:  {}\n", vm.get_current_hostname().green().bold());
-            }
-            Ok(false) => {
-                println!("\n❌ Falha ao conectar:");
-                println!("   • Host: {}", vm.name.red().bold());
-                println!("   • SSH:  {}", vm.get_current_hostname().red().bold());
-                println!("   • Motivo: Não foi possível estabelecer conexão SSH\n");
-            }
-            Err(e) => {
-                println!("\n❌ Erro ao testar conexão:");
-                println!("   • Host: {}", vm.name.yellow().bold());
-                println!("   • SSH:  {}", vm.get_current_hostname().yellow().bold());
-                println!("   • Erro: {}\n", e.to_string().red());
-            }
+    pub fn new(vms: Vec<VMConnection>, inventory_path: PathBuf) -> Self {
+        Self {
+            vms,
+            inventory_path,
         }
-        Ok(())
     }
 
     fn select_vm(&self, prompt: &str) -> Result<Option<usize>> {
-        let mut vm_names: Vec<_> = self.vms.iter().map(|vm| vm.name.as_str()).collect();
+        if self.vms.is_empty() {
+            println!(
+                "\n📭 Nenhuma VM no inventário. Use {} para adicionar.",
+                "'Gerenciar inventário'".green().bold()
+            );
+            return Ok(None);
+        }
+
+        let mut vm_names: Vec<_> = self.vms.iter().map(|vm| vm.name()).collect();
         vm_names.push("« Voltar ao menu principal");
 
         let selection = Select::with_theme(&ColorfulTheme::default())
@@ -56,120 +64,28 @@ impl Menu {
         }
     }
 
-    fn ensure_connection(&self, vm_idx: usize) -> Result<()> {
-        let vm = &self.vms[vm_idx];
-        if !vm.is_connected() {
-            println!(
-                "\n❌ Sem conexão SSH com {} (Host: {})",
-                vm.name.red(),
-                vm.get_current_hostname().red()
-            );
-            println!(
-                "   Execute primeiro a opção {} disponível",
-                "'Testar conexão com VM'".green().bold()
-            );
-            println!("   no menu principal para estabelecer a conexão primeiro.\n");
-            return Err(anyhow::anyhow!("Conexão SSH não estabelecida"));
-        }
-        Ok(())
-    }
-
     pub fn run(&mut self) -> Result<()> {
         loop {
             match self.show_main_menu()? {
-                0 => {
-                    // Testar conexão
-                    if let Some(vm_idx) = self.select_vm("Selecione a VM para testar conexão")? {
-                        if let Err(e) = self.test_vm_connection(vm_idx) {
-                            println!("❌ Erro ao testar conexão: {}", e);
-                        }
-                    }
-                }
-                1 => {
-                    // Instalar/Atualizar
-                    if let Some(vm_idx) =
-                        self.select_vm("Selecione a VM para instalar/atualizar o agente")?
-                    {
-                        if let Err(_) = self.ensure_connection(vm_idx) {
-                            continue;
-                        }
-                        let vm = &self.vms[vm_idx];
-                        if let Err(e) = vm.deploy_snapshot_agent() {
-                            println!("❌ Erro ao instalar/atualizar agente: {}", e);
-                        }
-                    }
-                }
-                2 => {
-                    // Listar status
-                    if let Some(vm_idx) =
-                        self.select_vm("Selecione a VM para verificar o status")?
-                    {
-                        if let Err(_) = self.ensure_connection(vm_idx) {
-                            continue;
-                        }
-                        if let Err(e) = self.show_agent_status(vm_idx) {
-                            println!("❌ Erro ao verificar status: {}", e);
-                        }
+                action @ 0..=7 => {
+                    let (prompt, operation) = VM_ACTIONS[action];
+                    if let Some(vm_idx) = self.select_vm(prompt)? {
+                        self.run_operation(vm_idx, operation);
                     }
                 }
-                3 => {
-                    // Ver logs
-                    if let Some(vm_idx) = self.select_vm("Selecione a VM para ver os logs")? {
-                        if let Err(_) = self.ensure_connection(vm_idx) {
-                            continue;
-                        }
-                        if let Err(e) = self.show_agent_logs(vm_idx) {
-                            println!("❌ Erro ao obter logs: {}", e);
-                        }
+                8 => {
+                    // Operação em lote
+                    if let Err(e) = self.run_batch() {
+                        println!("❌ Erro na operação em lote: {}", e);
                     }
                 }
-                4 => {
-                    // Reiniciar
-                    if let Some(vm_idx) =
-                        self.select_vm("Selecione a VM para reiniciar o agente")?
-                    {
-                        if let Err(_) = self.ensure_connection(vm_idx) {
-                            continue;
-                        }
-                        if let Err(e) = self.restart_agent(vm_idx) {
-                            println!("❌ Erro ao reiniciar agente: {}", e);
-                        }
-                    }
-                }
-                5 => {
-                    // Parar
-                    if let Some(vm_idx) = self.select_vm("Selecione a VM para parar o agente")? {
-                        if let Err(_) = self.ensure_connection(vm_idx) {
-                            continue;
-                        }
-                        if let Err(e) = self.stop_agent(vm_idx) {
-                            println!("❌ Erro ao parar agente: {}", e);
-                        }
+                9 => {
+                    // Gerenciar inventário
+                    if let Err(e) = self.manage_inventory() {
+                        println!("❌ Erro ao gerenciar inventário: {}", e);
                     }
                 }
-                6 => {
-                    // Remover
-                    if let Some(vm_idx) = self.select_vm("Selecione a VM para remover o agente")? {
-                        if let Err(_) = self.ensure_connection(vm_idx) {
-                            continue;
-                        }
-                        if let Err(e) = self.remove_agent(vm_idx) {
-                            println!("❌ Erro ao remover agente: {}", e);
-                        }
-                    }
-                }
-                7 => {
-                    // Apagar logs
-                    if let Some(vm_idx) = self.select_vm("Selecione a VM para apagar os logs")? {
-                        if let Err(_) = self.ensure_connection(vm_idx) {
-                            continue;
-                        }
-                        if let Err(e) = self.clear_agent_logs(vm_idx) {
-                            println!("❌ Erro ao apagar logs: {}", e);
-                        }
-                    }
-                }
-                8 => break, // Sair
+                10 => break, // Sair
                 _ => unreachable!(),
             }
 
@@ -191,6 +107,8 @@ impl Menu {
             "Parar um agente",
             "Remover agente",
             "Apagar logs de um agente",
+            "Executar em várias VMs",
+            "Gerenciar inventário",
             "Sair",
         ];
 
@@ -203,408 +121,459 @@ impl Menu {
         Ok(selection)
     }
 
-    fn show_agent_status(&self, vm_idx: usize) -> Result<()> {
-        let vm = &self.vms[vm_idx];
-
-        println!("\n🔍 Verificando status do agente em {}...", vm.name.cyan());
-
-        match &vm.os {
-            Some(crate::OperatingSystem::Linux(_)) => {
-                vm.check_linux_agent_status()?;
+    /// Executa uma operação em uma VM, exibindo o resultado como os
+    /// subcomandos (o sistema é detectado na hora se ainda não for conhecido)
+    fn run_operation(&mut self, vm_idx: usize, operation: Operation) {
+        let vm = &mut self.vms[vm_idx];
+        println!();
+        commands::print_progress(&Progress::Started { vm: vm.name() });
+        let started = Instant::now();
+        let outcome = ops::execute(vm, operation);
+        let result = FleetResult {
+            vm: vm.clone(),
+            outcome,
+            elapsed: started.elapsed(),
+        };
+        commands::print_progress(&Progress::Finished {
+            done: 1,
+            total: 1,
+            result: &result,
+        });
+
+        if !result.outcome.ok {
+            return;
+        }
+        match operation {
+            Operation::Test => vm.warn_unexpected_os(),
+            Operation::Logs if result.outcome.records.is_empty() => {
+                println!("\n📝 Nenhum registro encontrado.");
+                println!("ℹ️  Possíveis motivos:");
+                println!("   • O agente ainda não foi instalado");
+                println!("   • O agente foi instalado mas ainda não gerou logs");
+                println!("   • Os logs foram apagados recentemente");
             }
-            Some(crate::OperatingSystem::Windows(_)) => {
-                println!("\nStatus do agente Windows:");
-                println!("═══════════════════════");
-
-                // Verifica processo
-                let proc_output = Command::new("ssh")
-                    .args([
-                        &vm.ssh_config,
-                        "tasklist /FI \"IMAGENAME eq snapshot_agent.exe\" /FO LIST",
-                    ])
-                    .output()
-                    .context("Falha ao verificar status do processo")?;
-
-                if let Ok(stdout) = String::from_utf8(proc_output.stdout) {
-                    if stdout.contains("snapshot_agent.exe") {
-                        println!("• Estado: {}", "ATIVO".green().bold());
-
-                        // Extrai e mostra informações do processo
-                        for line in stdout.lines() {
-                            if line.starts_with("PID:") {
-                                println!(
-                                    "• PID: {}",
-                                    line.split_once(':').unwrap().1.trim().cyan().bold()
-                                );
-                            } else if line.starts_with("Mem Usage:") {
-                                println!(
-                                    "• Memória: {}",
-                                    line.split_once(':').unwrap().1.trim().cyan()
-                                );
-                            }
-                        }
+            _ => {}
+        }
+    }
 
-                        // Verifica estado da tarefa agendada
-                        let task_output = Command::new("ssh")
-                            .args([&vm.ssh_config, "schtasks /Query /TN SnapshotAgent /FO LIST"])
-                            .output()
-                            .context("Falha ao verificar tarefa agendada")?;
-
-                        if let Ok(task_stdout) = String::from_utf8(task_output.stdout) {
-                            println!("\nDetalhes da tarefa agendada:");
-                            println!("══════════════════════════");
-                            for line in task_stdout.lines() {
-                                if line.starts_with("Status:")
-                                    || line.starts_with("Next Run Time:")
-                                    || line.starts_with("Last Run Time:")
-                                {
-                                    let (key, value) = line.split_once(':').unwrap();
-                                    println!("• {}: {}", key, value.trim().cyan());
-                                }
-                            }
-                        }
-                    } else {
-                        println!("• Estado: {}", "INATIVO".red().bold());
-                        println!(
-                            "ℹ️  O agente não está em execução. Use a opção {} para iniciar.",
-                            "'Instalar/Atualizar agente'".green().bold()
-                        );
-                    }
+    /// Executa uma operação em todas as VMs ou nas de uma tag, em paralelo,
+    /// mostrando o andamento de cada VM e um resumo no final
+    fn run_batch(&mut self) -> Result<()> {
+        if self.vms.is_empty() {
+            println!(
+                "\n📭 Nenhuma VM no inventário. Use {} para adicionar.",
+                "'Gerenciar inventário'".green().bold()
+            );
+            return Ok(());
+        }
+        let theme = ColorfulTheme::default();
+
+        let operations = [
+            ("Testar conexão", Operation::Test),
+            ("Instalar/Atualizar agente", Operation::Deploy),
+            ("Verificar status do agente", Operation::Status),
+            ("Reiniciar agente", Operation::Restart),
+        ];
+        let labels: Vec<&str> = operations.iter().map(|(label, _)| *label).collect();
+        let (label, operation) = operations[Select::with_theme(&theme)
+            .with_prompt("Operação")
+            .items(&labels)
+            .default(0)
+            .interact()?];
+
+        // Alvos: todas as VMs ou as de uma tag
+        let mut tags: Vec<&str> = self
+            .vms
+            .iter()
+            .flat_map(|vm| vm.entry.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        let tagged = |tag: &str| -> Vec<usize> {
+            (0..self.vms.len())
+                .filter(|idx| self.vms[*idx].entry.tags.iter().any(|t| t == tag))
+                .collect()
+        };
+        let mut groups = vec![(
+            format!("Todas as VMs ({})", self.vms.len()),
+            (0..self.vms.len()).collect::<Vec<usize>>(),
+        )];
+        for tag in tags {
+            let idxs = tagged(tag);
+            groups.push((format!("Tag {} ({} VMs)", tag, idxs.len()), idxs));
+        }
+        let group_labels: Vec<&str> = groups.iter().map(|(label, _)| label.as_str()).collect();
+        let targets = &groups[Select::with_theme(&theme)
+            .with_prompt("VMs")
+            .items(&group_labels)
+            .default(0)
+            .interact()?]
+        .1;
+
+        let parallelism = Input::<usize>::with_theme(&theme)
+            .with_prompt("VMs ao mesmo tempo")
+            .default(fleet::DEFAULT_PARALLELISM)
+            .validate_with(|value: &usize| -> Result<(), &str> {
+                if *value > 0 {
+                    Ok(())
+                } else {
+                    Err("Informe um número maior que zero")
                 }
+            })
+            .interact_text()?;
+
+        if matches!(operation, Operation::Deploy | Operation::Restart) {
+            let confirmed = Confirm::with_theme(&theme)
+                .with_prompt(format!("{} em {} VMs?", label, targets.len()))
+                .default(true)
+                .interact()?;
+            if !confirmed {
+                return Ok(());
             }
-            _ => println!(
-                "❌ Sistema operacional não detectado. Execute a opção {} primeiro.",
-                "'Testar conexão com VM'".green().bold()
-            ),
+        }
+
+        println!();
+        let selected: Vec<VMConnection> =
+            targets.iter().map(|idx| self.vms[*idx].clone()).collect();
+        // O menu roda dentro do runtime do tokio; block_in_place libera a
+        // thread para que o lote possa ser aguardado sem sair do menu
+        let results = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(fleet::run(
+                selected,
+                operation,
+                parallelism,
+                |progress| commands::print_progress(&progress),
+            ))
+        });
+        commands::print_summary(operation, &results);
+
+        // Guarda o hostname e o sistema detectados em cada VM
+        for (idx, result) in targets.clone().into_iter().zip(results) {
+            self.vms[idx] = result.vm;
         }
 
         Ok(())
     }
 
-    fn show_agent_logs(&self, vm_idx: usize) -> Result<()> {
-        let vm = &self.vms[vm_idx];
-
-        println!("\n📖 Obtendo logs do agente em {}...", vm.name.cyan());
-
-        let log_command = match &vm.os {
-            Some(crate::OperatingSystem::Linux(_)) => "test -f ~/.snapshot_agent/snapshot.log && tail -n 50 ~/.snapshot_agent/snapshot.log || echo ''",
-            Some(crate::OperatingSystem::Windows(_)) => {
-                "if exist \"%USERPROFILE%\\.snapshot_agent\\snapshot.log\" (type \"%USERPROFILE%\\.snapshot_agent\\snapshot.log\") else (echo.)"
-            }
-            _ => {
-                println!("\n❌ Sistema operacional não detectado. Execute {} primeiro.", "'Testar conexão com VM'".green().bold());
-                return Ok(());
-            }
-        };
-
-        let output = Command::new("ssh")
-            .args([&vm.ssh_config, log_command])
-            .output()
-            .context("Falha ao obter logs")?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if stdout.trim().is_empty() {
-            println!("\n📝 Nenhum log encontrado.");
-            println!("ℹ️  Possíveis motivos:");
-            println!("   • O agente ainda não foi instalado");
-            println!("   • O agente foi instalado mas ainda não gerou logs");
-            println!("   • Os logs foram apagados recentemente");
-            println!("\nℹ️  Sugestões:");
+    /// Submenu para adicionar, editar e remover VMs do inventário
+    fn manage_inventory(&mut self) -> Result<()> {
+        println!(
+            "\n📒 Inventário: {}",
+            self.inventory_path.display().to_string().cyan()
+        );
+        for vm in &self.vms {
+            let tags = if vm.entry.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", vm.entry.tags.join(", "))
+            };
             println!(
-                "   1. Use {} para instalar o agente",
-                "'Instalar/Atualizar agente'".green().bold()
+                "   • {} ({}){}",
+                vm.name().green(),
+                vm.entry.connection(),
+                tags.cyan()
             );
-            println!(
-                "   2. Use {} para verificar se o agente está rodando",
-                "'Listar status dos agentes'".green().bold()
-            );
-            return Ok(());
         }
+        println!();
+
+        let options = [
+            "Adicionar VM",
+            "Editar VM",
+            "Remover VM",
+            "Importar hosts do ~/.ssh/config",
+            "« Voltar ao menu principal",
+        ];
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Selecione uma opção")
+            .items(&options)
+            .default(0)
+            .interact()?;
 
-        println!("\n📄 Últimos logs encontrados:");
-        println!("═════════════════════════");
-
-        let mut has_valid_logs = false;
-        for line in stdout.lines() {
-            if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(line) {
-                has_valid_logs = true;
-                if let Some(timestamp) = parsed["timestamp"].as_u64() {
-                    let datetime = Utc.timestamp_opt(timestamp as i64, 0).single();
-                    if let Some(utc_datetime) = datetime {
-                        let local_datetime: DateTime<Local> = DateTime::from(utc_datetime);
-                        println!(
-                            "\n⏰ Registro em {}",
-                            local_datetime
-                                .format("%d/%m/%Y %H:%M:%S")
-                                .to_string()
-                                .cyan()
-                        );
-                    }
+        match selection {
+            0 => {
+                let entry = prompt_vm_entry(&VmEntry::default())?;
+                let mut inventory = self.inventory();
+                inventory.vms.push(entry.clone());
+                if self.save_inventory(&inventory)? {
+                    self.vms.push(VMConnection::new(entry));
                 }
-
-                // Formatação dos valores com unidades apropriadas
-                let format_bytes = |bytes: u64| -> String {
-                    if bytes >= 1024 * 1024 * 1024 {
-                        format!("{:.2} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
-                    } else if bytes >= 1024 * 1024 {
-                        format!("{:.2} MB", bytes as f64 / (1024.0 * 1024.0))
-                    } else if bytes >= 1024 {
-                        format!("{:.2} KB", bytes as f64 / 1024.0)
-                    } else {
-                        format!("{} bytes", bytes)
+            }
+            1 => {
+                if let Some(vm_idx) = self.select_vm("Selecione a VM para editar")? {
+                    let entry = prompt_vm_entry(&self.vms[vm_idx].entry)?;
+                    let mut inventory = self.inventory();
+                    inventory.vms[vm_idx] = entry.clone();
+                    if self.save_inventory(&inventory)? {
+                        self.vms[vm_idx] = VMConnection::new(entry);
                     }
-                };
-
-                if let (Some(total_mem), Some(used_mem)) = (
-                    parsed["total_memory"].as_u64(),
-                    parsed["used_memory"].as_u64(),
-                ) {
-                    let mem_percent = (used_mem as f64 / total_mem as f64 * 100.0).round();
-                    println!(
-                        "💾 Memória: {} / {} ({}%)",
-                        format_bytes(used_mem).cyan(),
-                        format_bytes(total_mem),
-                        mem_percent
-                    );
-                }
-
-                if let Some(cpu) = parsed["cpu_usage_percent"].as_f64() {
-                    println!("🔄 CPU: {}%", format!("{:.1}", cpu).cyan());
                 }
-
-                if let (Some(total_disk), Some(used_disk)) =
-                    (parsed["total_disk"].as_u64(), parsed["used_disk"].as_u64())
+            }
+            2 => {
+                if let Some(vm_idx) = self.select_vm("Selecione a VM para remover do inventário")?
                 {
-                    let disk_percent = (used_disk as f64 / total_disk as f64 * 100.0).round();
-                    println!(
-                        "💿 Disco: {} / {} ({}%)",
-                        format_bytes(used_disk).cyan(),
-                        format_bytes(total_disk),
-                        disk_percent
-                    );
-                }
-
-                if let Some(files) = parsed["folder_files"].as_array() {
-                    if !files.is_empty() {
-                        println!("📁 Arquivos na pasta: {}", files.len());
-                        for file in files {
-                            if let Some(name) = file.as_str() {
-                                println!("   • {}", name);
-                            }
+                    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                        .with_prompt(format!(
+                            "Remover {} do inventário? (o agente instalado não é afetado)",
+                            self.vms[vm_idx].name()
+                        ))
+                        .default(false)
+                        .interact()?;
+                    if confirmed {
+                        let mut inventory = self.inventory();
+                        inventory.vms.remove(vm_idx);
+                        if self.save_inventory(&inventory)? {
+                            self.vms.remove(vm_idx);
                         }
-                    } else {
-                        println!("📁 Pasta vazia");
                     }
                 }
-                println!("───────────────────────");
             }
-        }
-
-        if !has_valid_logs {
-            println!("\n⚠️  Arquivo de log existe mas não contém registros válidos.");
-            println!("ℹ️  Isso pode indicar que o agente não está funcionando corretamente.");
-            println!(
-                "   Use {} para verificar o status do agente.",
-                "'Listar status dos agentes'".green().bold()
-            );
+            3 => self.import_ssh_hosts()?,
+            _ => {}
         }
 
         Ok(())
     }
 
-    fn clear_agent_logs(&self, vm_idx: usize) -> Result<()> {
-        let vm = &self.vms[vm_idx];
-
-        println!("\n🗑️ Apagando logs do agente em {}...", vm.name.cyan());
-
-        let clear_command = match &vm.os {
-            Some(crate::OperatingSystem::Linux(_)) => "rm -f ~/.snapshot_agent/snapshot.log",
-            Some(crate::OperatingSystem::Windows(_)) => {
-                "del \"%USERPROFILE%\\.snapshot_agent\\snapshot.log\""
+    /// Oferece as entradas Host do ~/.ssh/config que ainda não estão no
+    /// inventário, com hostname, usuário e porta resolvidos pelo `ssh -G`
+    fn import_ssh_hosts(&mut self) -> Result<()> {
+        let config_path = ssh_config::default_path();
+        println!(
+            "\n🔍 Lendo hosts de {}...",
+            config_path.display().to_string().cyan()
+        );
+
+        let mut candidates = Vec::new();
+        for alias in ssh_config::discover_hosts(&config_path)? {
+            match ssh_config::resolve(&alias) {
+                Ok(host) if !self.has_host(&host) => candidates.push(host),
+                Ok(_) => {}
+                Err(e) => println!("⚠️  {}: {}", alias.yellow(), e.to_string().red()),
             }
-            _ => return Ok(()),
-        };
-
-        Command::new("ssh")
-            .args([&vm.ssh_config, clear_command])
-            .status()
-            .context("Falha ao apagar logs")?;
-
-        println!("✅ Logs apagados com sucesso");
-
-        Ok(())
-    }
-
-    fn restart_agent(&self, vm_idx: usize) -> Result<()> {
-        let vm = &self.vms[vm_idx];
+        }
+        if candidates.is_empty() {
+            println!("📭 Nenhum host novo encontrado (wildcards e hosts já no inventário são ignorados).");
+            return Ok(());
+        }
 
-        println!("\n🔄 Reiniciando agente em {}...", vm.name.cyan());
+        let items: Vec<String> = candidates
+            .iter()
+            .map(|host| {
+                let user = host
+                    .user
+                    .as_deref()
+                    .map(|u| format!("{}@", u))
+                    .unwrap_or_default();
+                format!("{} → {}{}:{}", host.alias, user, host.hostname, host.port)
+            })
+            .collect();
+        let selected = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("Hosts a importar (espaço marca, Enter confirma)")
+            .items(&items)
+            .defaults(&vec![true; items.len()])
+            .interact()?;
+        if selected.is_empty() {
+            return Ok(());
+        }
 
-        match &vm.os {
-            Some(crate::OperatingSystem::Linux(_)) => {
-                vm.restart_linux_agent()?;
-            }
-            Some(crate::OperatingSystem::Windows(_)) => {
-                // Para o processo atual e reagenda a tarefa
-                Command::new("ssh")
-                    .args([
-                        &vm.ssh_config,
-                        "taskkill /F /IM snapshot_agent.exe 2>NUL & \
-                         schtasks /End /TN SnapshotAgent & \
-                         schtasks /Run /TN SnapshotAgent",
-                    ])
-                    .status()
-                    .context("Falha ao reiniciar processo")?;
-
-                // Aguarda um pouco para o processo iniciar
-                std::thread::sleep(std::time::Duration::from_secs(2));
-
-                // Verifica se o processo está rodando
-                let check = Command::new("ssh")
-                    .args([
-                        &vm.ssh_config,
-                        "tasklist /FI \"IMAGENAME eq snapshot_agent.exe\" /NH",
-                    ])
-                    .output()
-                    .context("Falha ao verificar processo")?;
-
-                if String::from_utf8_lossy(&check.stdout).contains("snapshot_agent.exe") {
-                    println!(
-                        "✅ Agente Windows reiniciado com sucesso em {}",
-                        vm.name.green()
-                    );
+        // Por alias, o ssh continua aplicando ProxyJump e demais opções do arquivo
+        let modes = [
+            "Host, usuário, porta e chave resolvidos",
+            "Alias do ~/.ssh/config",
+        ];
+        let as_alias = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Como importar")
+            .items(&modes)
+            .default(0)
+            .interact()?
+            == 1;
+
+        let entries: Vec<VmEntry> = selected
+            .into_iter()
+            .map(|idx| {
+                let host = &candidates[idx];
+                if as_alias {
+                    VmEntry {
+                        name: host.alias.clone(),
+                        ssh_alias: Some(host.alias.clone()),
+                        ..VmEntry::default()
+                    }
                 } else {
-                    println!(
-                        "⚠️ Agente reiniciado mas processo não detectado em {}",
-                        vm.name.yellow()
-                    );
-                    println!(
-                        "ℹ️ Tente usar a opção 'Instalar/Atualizar agente' se o problema persistir"
-                    );
+                    VmEntry {
+                        name: host.alias.clone(),
+                        host: Some(host.hostname.clone()),
+                        port: (host.port != 22).then_some(host.port),
+                        user: host.user.clone(),
+                        key: host
+                            .key
+                            .as_ref()
+                            .map(|key| key.to_string_lossy().into_owned()),
+                        ..VmEntry::default()
+                    }
                 }
-            }
-            _ => {
-                println!("\n❌ Sistema operacional não detectado");
-                println!(
-                    "ℹ️  Execute primeiro a opção {} para detectar o sistema operacional.",
-                    "'Testar conexão com VM'".green().bold()
-                );
-            }
+            })
+            .collect();
+
+        let mut inventory = self.inventory();
+        inventory.vms.extend(entries.iter().cloned());
+        if self.save_inventory(&inventory)? {
+            println!("📥 {} VMs importadas", entries.len());
+            self.vms.extend(entries.into_iter().map(VMConnection::new));
         }
 
         Ok(())
     }
 
-    fn stop_agent(&self, vm_idx: usize) -> Result<()> {
-        let vm = &self.vms[vm_idx];
+    /// Se o host já está no inventário (mesmo nome, alias ou destino)
+    fn has_host(&self, host: &ResolvedHost) -> bool {
+        self.vms.iter().any(|vm| {
+            let entry = &vm.entry;
+            entry.name.eq_ignore_ascii_case(&host.alias)
+                || entry.ssh_alias.as_deref() == Some(host.alias.as_str())
+                || (entry.host.as_deref() == Some(host.hostname.as_str())
+                    && entry.port.unwrap_or(22) == host.port
+                    && (entry.user.is_none() || entry.user == host.user))
+        })
+    }
 
-        println!("\n🛑 Parando agente em {}...", vm.name.cyan());
+    /// Inventário correspondente às VMs do menu
+    fn inventory(&self) -> Inventory {
+        Inventory {
+            vms: self.vms.iter().map(|vm| vm.entry.clone()).collect(),
+        }
+    }
 
-        match &vm.os {
-            Some(crate::OperatingSystem::Linux(_)) => {
-                vm.stop_linux_agent()?;
-            }
-            Some(crate::OperatingSystem::Windows(_)) => {
-                // Para o processo e a tarefa agendada no Windows
-                Command::new("ssh")
-                    .args([
-                        &vm.ssh_config,
-                        "schtasks /End /TN SnapshotAgent && \
-                         taskkill /F /IM snapshot_agent.exe",
-                    ])
-                    .status()
-                    .context("Falha ao parar processo no Windows")?;
-
-                // Verifica se o processo realmente parou
-                let check = Command::new("ssh")
-                    .args([
-                        &vm.ssh_config,
-                        "tasklist /FI \"IMAGENAME eq snapshot_agent.exe\" /NH",
-                    ])
-                    .output()
-                    .context("Falha ao verificar processo")?;
-
-                if String::from_utf8_lossy(&check.stdout).contains("snapshot_agent.exe") {
-                    println!("❌ ERRO: Processo snapshot_agent.exe ainda em execução");
-                    println!(
-                        "ℹ️  Tente remover e reinstalar o agente usando as opções apropriadas"
-                    );
-                } else {
-                    println!("✅ Processo snapshot_agent.exe encerrado com sucesso");
-                }
-            }
-            _ => {
-                println!("\n❌ Sistema operacional não detectado");
-                println!(
-                    "ℹ️  Execute primeiro a opção {} para detectar o sistema operacional.",
-                    "'Testar conexão com VM'".green().bold()
-                );
+    /// Valida e grava o inventário alterado
+    /// Retorna false (descartando a alteração) se houver erros de validação
+    fn save_inventory(&self, inventory: &Inventory) -> Result<bool> {
+        let errors = inventory.validate();
+        if !errors.is_empty() {
+            println!("\n❌ Alteração descartada, o inventário ficaria inválido:");
+            for error in errors {
+                println!("   • {}", error.red());
             }
+            return Ok(false);
         }
 
-        Ok(())
+        inventory.save(&self.inventory_path)?;
+        println!(
+            "\n✅ Inventário salvo em {}",
+            self.inventory_path.display().to_string().green()
+        );
+        for warning in inventory.warnings() {
+            println!("⚠️  {}", warning.yellow());
+        }
+        Ok(true)
     }
+}
 
-    fn remove_agent(&self, vm_idx: usize) -> Result<()> {
-        let vm = &self.vms[vm_idx];
-
-        println!("\n🗑️ Removendo agente de {}...", vm.name.cyan());
-
-        match &vm.os {
-            Some(crate::OperatingSystem::Linux(_)) => {
-                // Para o serviço primeiro
-                Command::new("ssh")
-                    .args([
-                        &vm.ssh_config,
-                        "systemctl --user stop snapshot-agent.service && \
-                         systemctl --user disable snapshot-agent.service && \
-                         rm -f ~/.config/systemd/user/snapshot-agent.service && \
-                         rm -f ~/snapshot_agent && \
-                         rm -rf ~/.snapshot_agent",
-                    ])
-                    .status()
-                    .context("Falha ao remover agente Linux")?;
-
-                // Verifica se o processo ainda está rodando
-                let check = Command::new("ssh")
-                    .args([&vm.ssh_config, "pgrep -af snapshot_agent"])
-                    .output()
-                    .context("Falha ao verificar processo")?;
-
-                if check.status.success() {
-                    println!("⚠️ Processo ainda detectado após remoção, detalhes:");
-                    if let Ok(output) = String::from_utf8(check.stdout) {
-                        println!("{}", output);
-                    }
-                    println!("ℹ️ Tente reiniciar a VM se o processo persistir.");
-                } else {
-                    println!("✅ Agente removido com sucesso");
+/// Pergunta os campos de uma VM, partindo dos valores de `current`
+fn prompt_vm_entry(current: &VmEntry) -> Result<VmEntry> {
+    let theme = ColorfulTheme::default();
+
+    let name = prompt_text("Nome da VM", &current.name, false)?;
+
+    let connection_types = ["Alias do ~/.ssh/config", "Host, porta, usuário e chave"];
+    let use_host = Select::with_theme(&theme)
+        .with_prompt("Como conectar")
+        .items(&connection_types)
+        .default(usize::from(current.host.is_some()))
+        .interact()?
+        == 1;
+
+    let mut entry = VmEntry {
+        name,
+        ..VmEntry::default()
+    };
+    if use_host {
+        entry.host = Some(prompt_text(
+            "Host",
+            current.host.as_deref().unwrap_or_default(),
+            false,
+        )?);
+        let port = Input::<String>::with_theme(&theme)
+            .with_prompt("Porta (vazio = padrão do ssh)")
+            .with_initial_text(current.port.map(|p| p.to_string()).unwrap_or_default())
+            .allow_empty(true)
+            .validate_with(|value: &String| -> Result<(), &str> {
+                match value.trim() {
+                    "" => Ok(()),
+                    port if matches!(port.parse::<u16>(), Ok(p) if p > 0) => Ok(()),
+                    _ => Err("Porta inválida"),
                 }
-            }
-            Some(crate::OperatingSystem::Windows(_)) => {
-                // Para o processo, deleta o agendamento e remove arquivos
-                Command::new("ssh")
-                    .args([
-                        &vm.ssh_config,
-                        "schtasks /End /TN SnapshotAgent & schtasks /Delete /TN SnapshotAgent /F & taskkill /F /IM snapshot_agent.exe 2>NUL & del C:\\Users\\Public\\snapshot_agent.exe & rmdir /S /Q \"C:\\Program Files\\SnapshotAgent\" & rmdir /S /Q \"%USERPROFILE%\\.snapshot_agent\""
-                    ])
-                    .status()
-                    .context("Falha ao remover agente Windows")?;
-                println!("✅ Agente e agendamento removidos com sucesso");
-            }
-            _ => {
-                println!("\n❌ Sistema operacional não detectado");
-                println!(
-                    "ℹ️  Execute primeiro a opção {} para detectar o sistema operacional.",
-                    "'Testar conexão com VM'".green().bold()
-                );
-            }
-        }
+            })
+            .interact_text()?;
+        entry.port = port.trim().parse().ok();
+        entry.user = optional(prompt_text(
+            "Usuário (vazio = padrão do ssh)",
+            current.user.as_deref().unwrap_or_default(),
+            true,
+        )?);
+        entry.key = optional(prompt_text(
+            "Chave privada (vazio = padrão do ssh)",
+            current.key.as_deref().unwrap_or_default(),
+            true,
+        )?);
+    } else {
+        entry.ssh_alias = Some(prompt_text(
+            "Alias (Host no ~/.ssh/config)",
+            current.ssh_alias.as_deref().unwrap_or_default(),
+            false,
+        )?);
+    }
 
-        Ok(())
+    let tags = prompt_text("Tags separadas por vírgula", &current.tags.join(", "), true)?;
+    entry.tags = tags
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let os_options = ["Detectar ao testar a conexão", "Linux", "Windows"];
+    let os_default = match current.expected_os {
+        None => 0,
+        Some(ExpectedOs::Linux) => 1,
+        Some(ExpectedOs::Windows) => 2,
+    };
+    entry.expected_os = match Select::with_theme(&theme)
+        .with_prompt("Sistema operacional esperado")
+        .items(&os_options)
+        .default(os_default)
+        .interact()?
+    {
+        1 => Some(ExpectedOs::Linux),
+        2 => Some(ExpectedOs::Windows),
+        _ => None,
+    };
+    entry.hmac_key = optional(prompt_text(
+        "Chave HMAC do log (vazio = sem chave; gerada no deploy se não existir)",
+        current.hmac_key.as_deref().unwrap_or_default(),
+        true,
+    )?);
+
+    Ok(entry)
+}
+
+/// Lê um texto já preenchido com `initial`
+fn prompt_text(prompt: &str, initial: &str, allow_empty: bool) -> Result<String> {
+    let text = Input::<String>::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .with_initial_text(initial)
+        .allow_empty(allow_empty)
+        .interact_text()?;
+    Ok(text.trim().to_string())
+}
+
+/// Texto vazio vira None
+fn optional(text: String) -> Option<String> {
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
     }
 }
 
-pub fn run_menu(vms: Vec<VMConnection>) -> Result<()> {
-    Menu::new(vms).run()
+pub fn run_menu(vms: Vec<VMConnection>, inventory_path: PathBuf) -> Result<()> {
+    Menu::new(vms, inventory_path).run()
 }
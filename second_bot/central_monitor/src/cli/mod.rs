@@ -1,2 +1,5 @@
+mod commands;
 mod menu;
+mod render;
+pub use commands::{parse_command, print_usage, run_command};
 pub use menu::run_menu;
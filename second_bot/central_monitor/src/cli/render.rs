@@ -0,0 +1,467 @@
+use chrono::{DateTime, Local, TimeZone, Utc};
+use colored::*;
+use snapshot_schema::{
+    AgentOverhead, AgentStatus, CgroupStats, FileState, ListeningSocket, PressureStall, Record,
+    ResourceScope, SystemHealth,
+};
+
+/// Formatação dos valores com unidades apropriadas
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.2} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.2} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.2} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// Converte um timestamp UNIX para data/hora local formatada
+pub fn format_timestamp(timestamp: u64) -> Option<String> {
+    let utc_datetime = Utc.timestamp_opt(timestamp as i64, 0).single()?;
+    let local_datetime: DateTime<Local> = DateTime::from(utc_datetime);
+    Some(local_datetime.format("%d/%m/%Y %H:%M:%S").to_string())
+}
+
+/// Percentual de uso, tratando total zero
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (used as f64 / total as f64 * 100.0).round()
+    }
+}
+
+/// Exibe os limites do cgroup; o escopo que vale para o agente é destacado
+fn print_cgroup(cgroup: &CgroupStats) {
+    println!(
+        "📦 cgroup v{} {}{}",
+        cgroup.version,
+        cgroup.path,
+        cgroup
+            .container
+            .as_deref()
+            .map(|runtime| format!(" ({})", runtime))
+            .unwrap_or_default()
+    );
+    if let Some(limit) = cgroup.memory_limit {
+        let line = format!(
+            "   Memória do cgroup: {} / {} ({}%)",
+            format_bytes(cgroup.memory_usage),
+            format_bytes(limit),
+            percent(cgroup.memory_usage, limit)
+        );
+        match cgroup.memory_scope {
+            ResourceScope::Cgroup => println!("{}", line.yellow()),
+            ResourceScope::Host => println!("{}", line),
+        }
+    }
+    if let Some(cores) = cgroup.cpu_limit_cores {
+        let line = format!(
+            "   CPU do cgroup: {} de {:.2} núcleos",
+            cgroup
+                .cpu_usage_percent
+                .map(|usage| format!("{:.1}%", usage))
+                .unwrap_or_else(|| "-".to_string()),
+            cores
+        );
+        match cgroup.cpu_scope {
+            ResourceScope::Cgroup => println!("{}", line.yellow()),
+            ResourceScope::Host => println!("{}", line),
+        }
+    }
+    if let Some(current) = cgroup.pids_current {
+        match cgroup.pids_max {
+            Some(max) => println!("   Processos: {} / {}", current, max),
+            None => println!("   Processos: {}", current),
+        }
+    }
+}
+
+/// Exibe swap, pressão (PSI), descritores, processos e I/O por disco
+/// Sem `processes` (varredura omitida pelo orçamento do agente) as contagens
+/// zeradas não são exibidas
+fn print_health(health: &SystemHealth, processes: bool) {
+    if health.swap_total > 0 {
+        println!(
+            "🔁 Swap: {} / {} ({}%)",
+            format_bytes(health.swap_used).cyan(),
+            format_bytes(health.swap_total),
+            percent(health.swap_used, health.swap_total)
+        );
+    }
+    if let Some(pressure) = &health.pressure {
+        let avg10 = |stall: &Option<PressureStall>| {
+            stall
+                .as_ref()
+                .map(|stall| format!("{:.2}%", stall.some.avg10))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        println!(
+            "⏳ Pressão (avg10): CPU {} • memória {} • I/O {}",
+            avg10(&pressure.cpu),
+            avg10(&pressure.memory),
+            avg10(&pressure.io)
+        );
+    }
+    if processes {
+        println!(
+            "🧵 Processos: {} • threads: {} • zumbis: {} • descritores: {} / {}",
+            health.processes,
+            health.threads,
+            if health.zombies > 0 {
+                health.zombies.to_string().red().bold()
+            } else {
+                health.zombies.to_string().normal()
+            },
+            health.open_fds,
+            health.max_fds
+        );
+    } else {
+        println!("🧵 Descritores: {} / {}", health.open_fds, health.max_fds);
+    }
+    for (device, io) in &health.disks {
+        println!(
+            "   • {}: {:.1} leituras/s ({}/s) • {:.1} escritas/s ({}/s) • {:.0}% ocupado",
+            device,
+            io.reads_per_sec,
+            format_bytes(io.read_bytes_per_sec as u64),
+            io.writes_per_sec,
+            format_bytes(io.write_bytes_per_sec as u64),
+            io.busy_percent
+        );
+    }
+}
+
+/// Processo dono de um socket em escuta ("nginx (pid 812)")
+fn socket_owner(socket: &ListeningSocket) -> String {
+    match (&socket.process, socket.pid) {
+        (Some(process), Some(pid)) => format!("{} (pid {})", process, pid),
+        _ => format!("uid {}", socket.uid),
+    }
+}
+
+/// Quantidade de entradas listadas, com o total quando a lista foi truncada
+fn folder_count(listed: usize, total: Option<u64>) -> String {
+    match total {
+        Some(total) if total > listed as u64 => format!("{} de {}", listed, total),
+        _ => listed.to_string(),
+    }
+}
+
+/// Exibe o consumo do próprio agente e os coletores omitidos pelo orçamento
+fn print_overhead(overhead: &AgentOverhead) {
+    println!(
+        "🤖 Agente: CPU {:.2}% • ciclo {} ms • RSS {}",
+        overhead.cpu_percent,
+        overhead.cycle_cpu_ms,
+        format_bytes(overhead.rss_bytes)
+    );
+    if overhead.throttled {
+        println!(
+            "{}",
+            format!(
+                "   Orçamento excedido; omitidos: {}",
+                overhead.skipped.join(", ")
+            )
+            .yellow()
+        );
+    }
+}
+
+/// Exibe um registro do log do agente no terminal
+pub fn print_record(record: &Record) {
+    if let Some(datetime) = format_timestamp(record.timestamp()) {
+        println!("\n⏰ Registro em {}", datetime.cyan());
+    }
+
+    match record {
+        Record::Snapshot(snapshot) => {
+            println!(
+                "💾 Memória: {} / {} ({}%)",
+                format_bytes(snapshot.used_memory).cyan(),
+                format_bytes(snapshot.total_memory),
+                percent(snapshot.used_memory, snapshot.total_memory)
+            );
+            println!(
+                "🔄 CPU: {}%",
+                format!("{:.1}", snapshot.cpu_usage_percent).cyan()
+            );
+            if let Some(cgroup) = &snapshot.cgroup {
+                print_cgroup(cgroup);
+            }
+            if let Some(health) = &snapshot.health {
+                let processes = !snapshot
+                    .overhead
+                    .as_ref()
+                    .is_some_and(|overhead| overhead.skipped.iter().any(|s| s == "processes"));
+                print_health(health, processes);
+            }
+            println!(
+                "💿 Disco: {} / {} ({}%)",
+                format_bytes(snapshot.used_disk).cyan(),
+                format_bytes(snapshot.total_disk),
+                percent(snapshot.used_disk, snapshot.total_disk)
+            );
+            if !snapshot.folder_files.is_empty() {
+                println!(
+                    "📁 Arquivos na pasta: {}",
+                    folder_count(snapshot.folder_files.len(), snapshot.folder_total)
+                );
+                for name in &snapshot.folder_files {
+                    println!("   • {}", name);
+                }
+            } else {
+                println!("📁 Pasta vazia");
+            }
+            for folder in &snapshot.folders {
+                println!(
+                    "📁 {}: {}",
+                    folder.path,
+                    folder_count(folder.files.len(), Some(folder.total))
+                );
+                for name in &folder.files {
+                    println!("   • {}", name);
+                }
+            }
+            if !snapshot.sessions.is_empty() {
+                println!("👤 Sessões ativas: {}", snapshot.sessions.len());
+                for session in &snapshot.sessions {
+                    println!(
+                        "   • {} em {} {} desde {}",
+                        session.user.cyan(),
+                        session.tty,
+                        if session.host.is_empty() {
+                            "(local)".to_string()
+                        } else {
+                            format!("de {}", session.host)
+                        },
+                        format_timestamp(session.login_time).unwrap_or_default()
+                    );
+                }
+            }
+            if !snapshot.listening.is_empty() {
+                println!("🔌 Portas em escuta: {}", snapshot.listening.len());
+                for socket in &snapshot.listening {
+                    println!("   • {} {}", socket.key().cyan(), socket_owner(socket));
+                }
+            }
+            if !snapshot.custom.is_empty() {
+                println!("🧩 Coletores personalizados:");
+                for (name, metric) in &snapshot.custom {
+                    let output = match (&metric.value, &metric.stdout) {
+                        (Some(value), _) => value.to_string(),
+                        (None, Some(stdout)) => stdout.lines().next().unwrap_or("").to_string(),
+                        (None, None) => String::new(),
+                    };
+                    let state = if metric.timed_out {
+                        "timeout".red().to_string()
+                    } else {
+                        match metric.exit_code {
+                            Some(0) => "ok".green().to_string(),
+                            Some(code) => format!("código {}", code).red().to_string(),
+                            None => "sinal".red().to_string(),
+                        }
+                    };
+                    println!(
+                        "   • {} [{} • {} ms] {}",
+                        name.cyan(),
+                        state,
+                        metric.duration_ms,
+                        output
+                    );
+                }
+            }
+            if let Some(overhead) = &snapshot.overhead {
+                print_overhead(overhead);
+            }
+        }
+        Record::ServiceStatus(status) => {
+            let label = match status.status {
+                AgentStatus::Started => status.status.as_str().green().bold(),
+                AgentStatus::Stopped => status.status.as_str().red().bold(),
+                _ => status.status.as_str().yellow().bold(),
+            };
+            match &status.reason {
+                Some(reason) => println!("🔔 Agente {} ({})", label, reason),
+                None => println!("🔔 Agente {}", label),
+            }
+        }
+        Record::Error(error) => {
+            println!("❌ Erro: {}", error.error.red());
+        }
+        Record::Inventory(record) => {
+            let inventory = &record.inventory;
+            println!(
+                "🖥️  Inventário: {} • kernel {} • {}",
+                inventory.os_release.cyan(),
+                inventory.kernel,
+                inventory.architecture
+            );
+            println!(
+                "   CPU: {} ({} núcleos) • Agente v{}",
+                inventory.cpu_model, inventory.cpu_cores, inventory.agent_version
+            );
+            println!("   Memória total: {}", format_bytes(inventory.total_memory));
+        }
+        Record::ProcessDown(event) => {
+            println!(
+                "🛑 Processo {} {} ({}) • PIDs {:?}",
+                event.name.cyan(),
+                "PARADO".red().bold(),
+                event.target,
+                event.pids
+            );
+            if let Some(last_seen) = event.last_seen.and_then(format_timestamp) {
+                println!("   Visto rodando pela última vez em {}", last_seen);
+            }
+            if let Some(restart) = &event.restart {
+                let result = match (restart.timed_out, restart.exit_code) {
+                    (true, _) => "timeout".red().to_string(),
+                    (false, Some(0)) => "ok".green().to_string(),
+                    (false, Some(code)) => format!("código {}", code).red().to_string(),
+                    (false, None) => "sinal".red().to_string(),
+                };
+                println!("   Reinício: {} ({} ms)", result, restart.duration_ms);
+            }
+        }
+        Record::SessionOpened(event) | Record::SessionClosed(event) => {
+            let session = &event.session;
+            let label = match record {
+                Record::SessionOpened(_) => "LOGIN".green().bold(),
+                _ => "LOGOUT".yellow().bold(),
+            };
+            let origin = if session.host.is_empty() {
+                String::new()
+            } else {
+                format!(" de {}", session.host)
+            };
+            println!(
+                "👤 {} {} em {}{}",
+                label,
+                session.user.cyan(),
+                session.tty,
+                origin
+            );
+        }
+        Record::AuthFailures(failures) => {
+            println!(
+                "🔐 Falhas de autenticação: {} SSH • {} sudo",
+                failures.ssh_failures.to_string().red().bold(),
+                failures.sudo_failures.to_string().red().bold()
+            );
+            for (source, count) in &failures.by_source {
+                println!("   • origem {}: {}", source, count);
+            }
+            for (user, count) in &failures.by_user {
+                println!("   • usuário {}: {}", user, count);
+            }
+        }
+        Record::ProcessUp(event) => {
+            println!(
+                "✅ Processo {} {} ({}) • PIDs {:?}",
+                event.name.cyan(),
+                "RODANDO".green().bold(),
+                event.target,
+                event.pids
+            );
+            if let Some(started_at) = event.started_at.and_then(format_timestamp) {
+                println!("   Iniciado em {}", started_at);
+            }
+        }
+        Record::FileChanged(change) => {
+            println!(
+                "📝 Arquivo {} {} ({})",
+                change.path.cyan(),
+                "ALTERADO".yellow().bold(),
+                change.changed.join(", ")
+            );
+            let describe = |state: &FileState| {
+                format!(
+                    "{} {}:{} {} bytes{}",
+                    state.mode,
+                    state.owner,
+                    state.group,
+                    state.size,
+                    state
+                        .sha256
+                        .as_deref()
+                        .map(|hash| format!(" sha256 {}", &hash[..hash.len().min(16)]))
+                        .unwrap_or_default()
+                )
+            };
+            if let Some(old) = &change.old {
+                println!("   antes:  {}", describe(old));
+            }
+            if let Some(new) = &change.new {
+                println!("   depois: {}", describe(new));
+            }
+        }
+        Record::PortOpened(event) => {
+            println!(
+                "🔌 Porta {} {} por {}",
+                event.socket.key().cyan(),
+                "ABERTA".yellow().bold(),
+                socket_owner(&event.socket)
+            );
+        }
+        Record::PortClosed(event) => {
+            println!(
+                "🔌 Porta {} {} (era {})",
+                event.socket.key().cyan(),
+                "FECHADA".normal().bold(),
+                socket_owner(&event.socket)
+            );
+        }
+        Record::PackageInventory(inventory) => {
+            println!(
+                "📦 Inventário: {} pacotes ({}), {} unidades",
+                inventory.packages.len(),
+                inventory.manager.as_deref().unwrap_or("sem gerenciador"),
+                inventory.units.len()
+            );
+            let failed: Vec<&str> = inventory
+                .units
+                .iter()
+                .filter(|unit| unit.active == "failed")
+                .map(|unit| unit.name.as_str())
+                .collect();
+            if !failed.is_empty() {
+                println!("   Em falha: {}", failed.join(", ").red());
+            }
+        }
+        Record::PackageChanged(change) => {
+            let versions = match (&change.old_version, &change.new_version) {
+                (Some(old), Some(new)) => format!("{} → {}", old, new),
+                (None, Some(new)) => new.clone(),
+                (Some(old), None) => old.clone(),
+                (None, None) => String::new(),
+            };
+            println!(
+                "📦 Pacote {} {}: {} {}",
+                change.name.cyan(),
+                change.architecture,
+                change.change.to_uppercase().yellow().bold(),
+                versions
+            );
+        }
+        Record::UnitChanged(change) => {
+            let unit = change.new.as_ref().or(change.old.as_ref());
+            let name = unit.map(|u| u.name.as_str()).unwrap_or("?");
+            let label = if change.change == "failed" {
+                "EM FALHA".red().bold()
+            } else {
+                "RECUPERADA".green().bold()
+            };
+            let state = change
+                .new
+                .as_ref()
+                .map(|u| format!("{}/{}", u.active, u.sub))
+                .unwrap_or_else(|| "removida".to_string());
+            println!("⚙️  Unidade {} {} ({})", name.cyan(), label, state);
+        }
+    }
+    println!("───────────────────────");
+}
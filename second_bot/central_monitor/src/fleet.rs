@@ -0,0 +1,227 @@
+// ------------------------------------------------------------------------------
+// OPERAÇÕES EM LOTE
+// ------------------------------------------------------------------------------
+// Executa uma operação em várias VMs ao mesmo tempo. Cada operação continua
+// sendo uma sequência de comandos ssh bloqueantes, então roda em uma thread
+// do pool de bloqueio do tokio; um semáforo limita quantas VMs são
+// atendidas simultaneamente.
+//
+// O progresso é entregue por eventos consumidos em uma única task, de modo
+// que quem imprime nunca recebe dois eventos ao mesmo tempo.
+// ------------------------------------------------------------------------------
+
+use crate::ops::{self, Operation, Outcome};
+use crate::VMConnection;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+
+/// VMs atendidas ao mesmo tempo quando não configurado
+pub const DEFAULT_PARALLELISM: usize = 4;
+
+/// Resultado de uma VM no lote
+pub struct FleetResult {
+    pub vm: VMConnection, // Devolvida com o hostname e o sistema detectados
+    pub outcome: Outcome,
+    pub elapsed: Duration,
+}
+
+/// Andamento do lote
+pub enum Progress<'a> {
+    Started {
+        vm: &'a str,
+    },
+    Finished {
+        done: usize,  // VMs concluídas até agora
+        total: usize, // VMs no lote
+        result: &'a FleetResult,
+    },
+}
+
+enum Event {
+    Started(usize),
+    Finished(usize, Box<FleetResult>),
+}
+
+/// Executa `operation` em todas as `vms`, com no máximo `parallelism` ao
+/// mesmo tempo, chamando `on_progress` a cada início e término
+/// Os resultados voltam na ordem de `vms`
+pub async fn run(
+    vms: Vec<VMConnection>,
+    operation: Operation,
+    parallelism: usize,
+    on_progress: impl FnMut(Progress),
+) -> Vec<FleetResult> {
+    run_with(
+        vms,
+        move |vm| ops::execute(vm, operation),
+        parallelism,
+        on_progress,
+    )
+    .await
+}
+
+/// Como `run`, mas com a operação de cada VM fornecida por quem chama
+async fn run_with<F>(
+    vms: Vec<VMConnection>,
+    execute: F,
+    parallelism: usize,
+    mut on_progress: impl FnMut(Progress),
+) -> Vec<FleetResult>
+where
+    F: Fn(&mut VMConnection) -> Outcome + Send + Sync + 'static,
+{
+    let total = vms.len();
+    let semaphore = Arc::new(Semaphore::new(parallelism.max(1)));
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let execute = Arc::new(execute);
+
+    // Cópias para os nomes no andamento e para o resultado de uma VM cuja
+    // operação não terminou
+    let fallback = vms.clone();
+    for (idx, mut vm) in vms.into_iter().enumerate() {
+        let semaphore = Arc::clone(&semaphore);
+        let tx = tx.clone();
+        let execute = Arc::clone(&execute);
+        tokio::spawn(async move {
+            // O semáforo nunca é fechado
+            let Ok(_permit) = semaphore.acquire_owned().await else {
+                return;
+            };
+            let _ = tx.send(Event::Started(idx));
+            let started = Instant::now();
+            let backup = vm.clone();
+            let job = tokio::task::spawn_blocking(move || {
+                let outcome = execute(&mut vm);
+                (vm, outcome)
+            });
+            let (vm, outcome) = match job.await {
+                Ok(result) => result,
+                // Pânico na operação: só esta VM falha
+                Err(e) => {
+                    let message = format!("Operação interrompida: {}", e);
+                    let outcome = Outcome::failed(&backup, message);
+                    (backup, outcome)
+                }
+            };
+            let result = FleetResult {
+                vm,
+                outcome,
+                elapsed: started.elapsed(),
+            };
+            let _ = tx.send(Event::Finished(idx, Box::new(result)));
+        });
+    }
+    // Só as tasks ficam com o canal: ele fecha quando a última termina
+    drop(tx);
+
+    let mut results: Vec<Option<FleetResult>> = (0..total).map(|_| None).collect();
+    let mut done = 0;
+    while let Some(event) = rx.recv().await {
+        match event {
+            Event::Started(idx) => on_progress(Progress::Started {
+                vm: fallback[idx].name(),
+            }),
+            Event::Finished(idx, result) => {
+                done += 1;
+                let result = results[idx].insert(*result);
+                on_progress(Progress::Finished {
+                    done,
+                    total,
+                    result,
+                });
+            }
+        }
+    }
+
+    // Uma task que terminou sem enviar o resultado conta como falha da VM
+    results
+        .into_iter()
+        .zip(fallback)
+        .map(|(result, vm)| {
+            result.unwrap_or_else(|| FleetResult {
+                outcome: Outcome::failed(&vm, "Operação interrompida".to_string()),
+                vm,
+                elapsed: Duration::ZERO,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::VmEntry;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn vms(count: usize) -> Vec<VMConnection> {
+        (0..count)
+            .map(|idx| {
+                VMConnection::new(VmEntry {
+                    name: format!("vm{}", idx),
+                    ssh_alias: Some(format!("vm{}", idx)),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn results_keep_inventory_order_within_the_parallelism() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let operation = {
+            let (running, peak) = (Arc::clone(&running), Arc::clone(&peak));
+            move |vm: &mut VMConnection| {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                // As primeiras VMs demoram mais: terminam fora de ordem
+                let idx: u64 = vm.name()[2..].parse().unwrap();
+                std::thread::sleep(Duration::from_millis(60 - idx * 10));
+                running.fetch_sub(1, Ordering::SeqCst);
+                if idx == 3 {
+                    panic!("falha simulada");
+                }
+                Outcome {
+                    ok: true,
+                    message: format!("ok {}", idx),
+                    ..Outcome::failed(vm, String::new())
+                }
+            }
+        };
+
+        let mut started = 0;
+        let mut finished = Vec::new();
+        let results = run_with(vms(5), operation, 2, |progress| match progress {
+            Progress::Started { .. } => started += 1,
+            Progress::Finished {
+                done,
+                total,
+                result,
+            } => {
+                assert_eq!(total, 5);
+                assert_eq!(done, finished.len() + 1);
+                finished.push(result.vm.name().to_string());
+            }
+        })
+        .await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(started, 5);
+        assert_eq!(finished.len(), 5);
+        assert_ne!(finished, vec!["vm0", "vm1", "vm2", "vm3", "vm4"]);
+
+        let names: Vec<&str> = results.iter().map(|result| result.vm.name()).collect();
+        assert_eq!(names, vec!["vm0", "vm1", "vm2", "vm3", "vm4"]);
+        for (idx, result) in results.iter().enumerate() {
+            if idx == 3 {
+                // O pânico de uma VM não afeta as demais
+                assert!(!result.outcome.ok);
+                assert!(result.outcome.message.contains("Operação interrompida"));
+            } else {
+                assert!(result.outcome.ok);
+                assert_eq!(result.outcome.message, format!("ok {}", idx));
+            }
+        }
+    }
+}
@@ -0,0 +1,436 @@
+// ------------------------------------------------------------------------------
+// INVENTÁRIO DE VMs
+// ------------------------------------------------------------------------------
+// A frota gerenciada fica em um arquivo TOML (padrão:
+// ~/.central_monitor/inventory.toml), com uma tabela [[vm]] por máquina:
+//
+//   [[vm]]
+//   name = "computer 1"
+//   ssh_alias = "so-lin"          # Host do ~/.ssh/config
+//   tags = ["lab", "linux"]
+//   expected_os = "linux"
+//
+//   [[vm]]
+//   name = "computer 2"
+//   host = "192.168.1.2"          # Ou host/porta/usuário/chave explícitos
+//   port = 22
+//   user = "so"
+//   key = "~/.ssh/id_rsa_so"
+//   expected_os = "windows"
+//
+// `hmac_key` (só Linux) é a chave do log à prova de adulteração: o deploy
+// a gera, se não existir, e a copia para a VM.
+//
+// O arquivo é validado na inicialização e regravado (de forma atômica)
+// quando VMs são adicionadas, editadas ou removidas pelo menu.
+// ------------------------------------------------------------------------------
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Sistema operacional esperado de uma VM
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpectedOs {
+    Linux,
+    Windows,
+}
+
+impl fmt::Display for ExpectedOs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectedOs::Linux => write!(f, "linux"),
+            ExpectedOs::Windows => write!(f, "windows"),
+        }
+    }
+}
+
+/// Uma VM do inventário
+/// A conexão usa `ssh_alias` (entrada do ~/.ssh/config) ou `host` com
+/// `port`, `user` e `key` opcionais
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct VmEntry {
+    pub name: String, // Nome exibido no menu e usado como alvo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>, // Chave privada; aceita ~/
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_os: Option<ExpectedOs>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hmac_key: Option<String>, // Chave HMAC local do log; aceita ~/
+}
+
+impl VmEntry {
+    /// Destino para o ssh/scp: o alias ou `usuário@host`
+    pub fn destination(&self) -> String {
+        match (&self.ssh_alias, &self.host, &self.user) {
+            (Some(alias), _, _) => alias.clone(),
+            (None, Some(host), Some(user)) => format!("{}@{}", user, host),
+            (None, Some(host), None) => host.clone(),
+            (None, None, _) => String::new(),
+        }
+    }
+
+    /// Opções do ssh para entradas com host explícito
+    /// (`port_flag` é `-p` no ssh e `-P` no scp)
+    pub fn ssh_options(&self, port_flag: &str) -> Vec<String> {
+        let mut options = Vec::new();
+        if self.ssh_alias.is_some() {
+            return options;
+        }
+        if let Some(port) = self.port {
+            options.extend([port_flag.to_string(), port.to_string()]);
+        }
+        if let Some(key) = &self.key {
+            options.extend([
+                "-i".to_string(),
+                expand_home(key).to_string_lossy().into_owned(),
+            ]);
+        }
+        options
+    }
+
+    /// Descrição curta da conexão ("alias so-lin" ou "so@192.168.1.2:22")
+    pub fn connection(&self) -> String {
+        match (&self.ssh_alias, self.port) {
+            (Some(alias), _) => format!("alias {}", alias),
+            (None, Some(port)) => format!("{}:{}", self.destination(), port),
+            (None, None) => self.destination(),
+        }
+    }
+
+    /// Verifica uma entrada isolada (a unicidade do nome é do inventário)
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("VM sem nome".to_string());
+        }
+        let name = &self.name;
+        if self.hmac_key.is_some() && self.expected_os == Some(ExpectedOs::Windows) {
+            return Err(format!("{}: hmac_key só vale para o agente Linux", name));
+        }
+        match (&self.ssh_alias, &self.host) {
+            (Some(_), Some(_)) => {
+                return Err(format!("{}: use ssh_alias ou host, não ambos", name));
+            }
+            (None, None) => return Err(format!("{}: informe ssh_alias ou host", name)),
+            (Some(alias), None) => {
+                // Um valor iniciado por '-' seria lido pelo ssh como opção
+                if alias.trim().is_empty()
+                    || alias.contains(char::is_whitespace)
+                    || alias.starts_with('-')
+                {
+                    return Err(format!("{}: ssh_alias inválido: {:?}", name, alias));
+                }
+                if self.port.is_some() || self.user.is_some() || self.key.is_some() {
+                    return Err(format!(
+                        "{}: port, user e key só valem com host (o alias usa o ~/.ssh/config)",
+                        name
+                    ));
+                }
+            }
+            (None, Some(host)) => {
+                if host.trim().is_empty()
+                    || host.contains(char::is_whitespace)
+                    || host.starts_with('-')
+                {
+                    return Err(format!("{}: host inválido: {:?}", name, host));
+                }
+                if self.port == Some(0) {
+                    return Err(format!("{}: porta inválida: 0", name));
+                }
+                if let Some(user) = &self.user {
+                    if user.trim().is_empty() || user.contains(['@', ' ']) {
+                        return Err(format!("{}: usuário inválido: {:?}", name, user));
+                    }
+                }
+            }
+        }
+        for tag in &self.tags {
+            if tag.trim().is_empty() || tag.contains(char::is_whitespace) || tag.contains(',') {
+                return Err(format!("{}: tag inválida: {:?}", name, tag));
+            }
+        }
+        Ok(())
+    }
+
+    /// Problemas que não impedem o uso do inventário (a VM afetada apenas
+    /// falha ao conectar), como uma chave que ainda não foi copiada
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(key) = &self.key {
+            if !expand_home(key).is_file() {
+                warnings.push(format!("{}: chave não encontrada: {}", self.name, key));
+            }
+        }
+        warnings
+    }
+}
+
+/// Conteúdo do arquivo de inventário
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Inventory {
+    #[serde(default, rename = "vm")]
+    pub vms: Vec<VmEntry>,
+}
+
+impl Inventory {
+    /// Lê e valida o inventário
+    /// Arquivo inexistente equivale a um inventário vazio
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Falha ao ler o inventário {:?}", path))
+            }
+        };
+        let inventory: Inventory = toml::from_str(&content)
+            .with_context(|| format!("Inventário inválido em {:?}", path))?;
+        let errors = inventory.validate();
+        if !errors.is_empty() {
+            return Err(anyhow!(
+                "Inventário inválido em {:?}:\n  • {}",
+                path,
+                errors.join("\n  • ")
+            ));
+        }
+        Ok(inventory)
+    }
+
+    /// Grava o inventário (arquivo temporário + rename)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Falha ao criar o diretório {:?}", dir))?;
+        }
+        let content = toml::to_string_pretty(self).context("Falha ao serializar o inventário")?;
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, content).with_context(|| format!("Falha ao gravar {:?}", tmp))?;
+        fs::rename(&tmp, path).with_context(|| format!("Falha ao gravar {:?}", path))?;
+        Ok(())
+    }
+
+    /// Todos os problemas encontrados (vazio se o inventário é válido)
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut names = HashSet::new();
+        for vm in &self.vms {
+            if let Err(e) = vm.validate() {
+                errors.push(e);
+            }
+            if !names.insert(vm.name.trim().to_lowercase()) {
+                errors.push(format!("nome duplicado: {}", vm.name));
+            }
+        }
+        errors
+    }
+
+    /// Avisos de todas as VMs (veja `VmEntry::warnings`)
+    pub fn warnings(&self) -> Vec<String> {
+        self.vms.iter().flat_map(VmEntry::warnings).collect()
+    }
+}
+
+/// Caminho padrão do inventário: ~/.central_monitor/inventory.toml
+pub fn default_path() -> PathBuf {
+    let mut path = dirs_next::home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".central_monitor");
+    path.push("inventory.toml");
+    path
+}
+
+/// Expande `~/` para a home do usuário
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs_next::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alias_vm(name: &str, alias: &str) -> VmEntry {
+        VmEntry {
+            name: name.to_string(),
+            ssh_alias: Some(alias.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn host_vm(name: &str, host: &str) -> VmEntry {
+        VmEntry {
+            name: name.to_string(),
+            host: Some(host.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("inventory-test-{}", std::process::id()));
+        let path = dir.join("inventory.toml");
+        let inventory = Inventory {
+            vms: vec![
+                VmEntry {
+                    tags: vec!["lab".to_string(), "linux".to_string()],
+                    expected_os: Some(ExpectedOs::Linux),
+                    ..alias_vm("computer 1", "so-lin")
+                },
+                VmEntry {
+                    port: Some(2222),
+                    user: Some("so".to_string()),
+                    expected_os: Some(ExpectedOs::Windows),
+                    ..host_vm("computer 2", "192.168.1.2")
+                },
+            ],
+        };
+        inventory.save(&path).unwrap();
+        assert_eq!(Inventory::load(&path).unwrap(), inventory);
+        assert!(!path.with_extension("toml.tmp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_an_empty_inventory() {
+        let path = std::env::temp_dir().join("inventory-test-nao-existe.toml");
+        assert!(Inventory::load(&path).unwrap().vms.is_empty());
+    }
+
+    #[test]
+    fn load_lists_every_problem() {
+        let dir = std::env::temp_dir().join(format!("inventory-bad-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("inventory.toml");
+        fs::write(
+            &path,
+            "[[vm]]\nname = \"a\"\nhost = \"10.0.0.1\"\nport = 0\n\n\
+             [[vm]]\nname = \"A\"\nssh_alias = \"a\"\n",
+        )
+        .unwrap();
+        let error = Inventory::load(&path).unwrap_err().to_string();
+        assert!(error.contains("porta inválida"), "{}", error);
+        assert!(error.contains("nome duplicado: A"), "{}", error);
+
+        // Campos desconhecidos são rejeitados
+        fs::write(&path, "[[vm]]\nname = \"a\"\nhots = \"10.0.0.1\"\n").unwrap();
+        assert!(Inventory::load(&path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn duplicate_names_ignore_case_and_spaces() {
+        let inventory = Inventory {
+            vms: vec![alias_vm("web", "web1"), alias_vm(" WEB ", "web2")],
+        };
+        assert_eq!(inventory.validate(), vec!["nome duplicado:  WEB "]);
+    }
+
+    #[test]
+    fn alias_and_host_are_exclusive() {
+        let both = VmEntry {
+            host: Some("10.0.0.1".to_string()),
+            ..alias_vm("vm", "so-lin")
+        };
+        assert!(both.validate().unwrap_err().contains("não ambos"));
+        assert!(VmEntry {
+            name: "vm".to_string(),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+
+        let alias_with_port = VmEntry {
+            port: Some(22),
+            ..alias_vm("vm", "so-lin")
+        };
+        assert!(alias_with_port.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let port_zero = VmEntry {
+            port: Some(0),
+            ..host_vm("vm", "10.0.0.1")
+        };
+        assert!(port_zero.validate().is_err());
+        assert!(host_vm("vm", "10.0.0.1 -p 2").validate().is_err());
+
+        // Seriam interpretados pelo ssh como opções
+        assert!(host_vm("vm", "-oProxyCommand=id").validate().is_err());
+        assert!(alias_vm("vm", "-F/tmp/x").validate().is_err());
+
+        for tag in ["", "dois nomes", "a,b"] {
+            let vm = VmEntry {
+                tags: vec![tag.to_string()],
+                ..alias_vm("vm", "so-lin")
+            };
+            assert!(vm.validate().is_err(), "tag {:?}", tag);
+        }
+        let user = VmEntry {
+            user: Some("so@lab".to_string()),
+            ..host_vm("vm", "10.0.0.1")
+        };
+        assert!(user.validate().is_err());
+
+        // O agente Windows não tem log encadeado
+        let windows_key = VmEntry {
+            hmac_key: Some("~/.central_monitor/vm.key".to_string()),
+            expected_os: Some(ExpectedOs::Windows),
+            ..alias_vm("vm", "so-win")
+        };
+        assert!(windows_key.validate().unwrap_err().contains("hmac_key"));
+    }
+
+    #[test]
+    fn missing_key_is_only_a_warning() {
+        let vm = VmEntry {
+            key: Some("/nao/existe/id_rsa".to_string()),
+            ..host_vm("vm", "10.0.0.1")
+        };
+        assert!(vm.validate().is_ok());
+        let inventory = Inventory {
+            vms: vec![vm, alias_vm("outra", "so-lin")],
+        };
+        assert!(inventory.validate().is_empty());
+        assert_eq!(
+            inventory.warnings(),
+            vec!["vm: chave não encontrada: /nao/existe/id_rsa"]
+        );
+    }
+
+    #[test]
+    fn destination_and_options() {
+        let vm = VmEntry {
+            port: Some(2222),
+            user: Some("so".to_string()),
+            key: Some("/tmp/id".to_string()),
+            ..host_vm("vm", "10.0.0.1")
+        };
+        assert_eq!(vm.destination(), "so@10.0.0.1");
+        assert_eq!(vm.connection(), "so@10.0.0.1:2222");
+        assert_eq!(vm.ssh_options("-P"), vec!["-P", "2222", "-i", "/tmp/id"]);
+
+        let alias = alias_vm("vm", "so-lin");
+        assert_eq!(alias.destination(), "so-lin");
+        assert!(alias.ssh_options("-p").is_empty());
+    }
+}
@@ -0,0 +1,502 @@
+// ------------------------------------------------------------------------------
+// OPERAÇÕES SOBRE OS AGENTES
+// ------------------------------------------------------------------------------
+// Comandos remotos usados pelo menu e pelos subcomandos, e as operações em
+// versão silenciosa: em vez de imprimir, cada operação devolve um `Outcome`
+// (sucesso, mensagem, detalhes), que o chamador exibe como texto ou JSON.
+//
+// Nem o menu nem os subcomandos exigem que a conexão seja testada antes:
+// o sistema operacional é detectado na hora quando ainda não é conhecido.
+// ------------------------------------------------------------------------------
+
+use crate::inventory::expand_home;
+use crate::{OperatingSystem, VMConnection};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use snapshot_schema::Record;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Output};
+use std::time::Duration;
+
+/// Binários publicados pelo build local
+pub const LINUX_AGENT_BINARY: &str =
+    "/home/drp/my/so/bots/second_bot/target/release/snapshot_agent_linux";
+pub const WINDOWS_AGENT_BINARY: &str =
+    "/home/drp/my/so/bots/second_bot/target/x86_64-pc-windows-gnu/release/snapshot_agent_windows.exe";
+
+/// Serviço systemd do usuário
+/// Type=notify: o agente avisa quando está pronto e envia pings ao
+/// watchdog; SIGHUP recarrega ~/.snapshot_agent/config.json
+pub const LINUX_SERVICE: &str = "[Unit]\n\
+    Description=Snapshot Agent Service\n\
+    \n\
+    [Service]\n\
+    Type=notify\n\
+    NotifyAccess=main\n\
+    WatchdogSec=90\n\
+    ExecStart=/home/%u/snapshot_agent\n\
+    ExecReload=/bin/kill -HUP $MAINPID\n\
+    ExecStop=/bin/kill -TERM $MAINPID\n\
+    KillMode=process\n\
+    Restart=always\n\
+    WorkingDirectory=/home/%u\n\
+    \n\
+    [Install]\n\
+    WantedBy=default.target";
+
+// Linux (systemd do usuário)
+pub const LINUX_PROCESS_CHECK: &str = "pgrep -af snapshot_agent";
+pub const LINUX_STOP: &str = "systemctl --user stop snapshot-agent.service";
+pub const LINUX_START: &str = "systemctl --user start snapshot-agent.service";
+pub const LINUX_REMOVE: &str = "systemctl --user stop snapshot-agent.service && \
+    systemctl --user disable snapshot-agent.service && \
+    rm -f ~/.config/systemd/user/snapshot-agent.service && \
+    rm -f ~/snapshot_agent && \
+    rm -rf ~/.snapshot_agent";
+// No formato compacto (snapshot.bin) o próprio agente decodifica o log
+pub const LINUX_LOGS: &str = "if [ -s ~/.snapshot_agent/snapshot.bin ]; then ~/snapshot_agent decode | tail -n 50; elif [ -f ~/.snapshot_agent/snapshot.log ]; then tail -n 50 ~/.snapshot_agent/snapshot.log; else echo ''; fi";
+// A chave chega pelo stdin; umask 077 evita que ela exista com outro modo
+pub const LINUX_INSTALL_KEY: &str =
+    "umask 077 && mkdir -p ~/.snapshot_agent && cat > ~/.snapshot_agent/log.key && chmod 600 ~/.snapshot_agent/log.key";
+pub const LINUX_CLEAR_LOGS: &str =
+    "rm -f ~/.snapshot_agent/snapshot.log ~/.snapshot_agent/snapshot.bin";
+
+// Windows (tarefa agendada)
+pub const WINDOWS_CREATE_LOG_DIR: &str = "powershell -Command \"New-Item -ItemType Directory -Path C:\\Users\\so\\.snapshot_agent -Force | Out-Null\"";
+pub const WINDOWS_CREATE_TASK: &str = "schtasks /Create /TN SnapshotAgent /TR \"C:\\Users\\Public\\snapshot_agent.exe\" /SC ONCE /ST 00:00 /RL HIGHEST /F";
+pub const WINDOWS_RUN_TASK: &str = "schtasks /Run /TN SnapshotAgent";
+pub const WINDOWS_PROCESS_CHECK: &str = "tasklist /FI \"IMAGENAME eq snapshot_agent.exe\" /NH";
+pub const WINDOWS_RESTART: &str = "taskkill /F /IM snapshot_agent.exe 2>NUL & \
+    schtasks /End /TN SnapshotAgent & \
+    schtasks /Run /TN SnapshotAgent";
+pub const WINDOWS_STOP: &str = "schtasks /End /TN SnapshotAgent && \
+    taskkill /F /IM snapshot_agent.exe";
+pub const WINDOWS_REMOVE: &str = "schtasks /End /TN SnapshotAgent & schtasks /Delete /TN SnapshotAgent /F & taskkill /F /IM snapshot_agent.exe 2>NUL & del C:\\Users\\Public\\snapshot_agent.exe & rmdir /S /Q \"C:\\Program Files\\SnapshotAgent\" & rmdir /S /Q \"%USERPROFILE%\\.snapshot_agent\"";
+pub const WINDOWS_LOGS: &str = "if exist \"%USERPROFILE%\\.snapshot_agent\\snapshot.log\" (type \"%USERPROFILE%\\.snapshot_agent\\snapshot.log\") else (echo.)";
+pub const WINDOWS_CLEAR_LOGS: &str = "del \"%USERPROFILE%\\.snapshot_agent\\snapshot.log\"";
+
+/// Código de saída do ssh quando o erro é dele e não do comando remoto
+const SSH_ERROR: i32 = 255;
+
+/// Limite, em segundos, para o ssh/scp estabelecer a conexão: uma VM
+/// desligada ou inalcançável falha logo em vez de ocupar uma vaga do lote
+/// até o timeout do TCP
+const CONNECT_TIMEOUT: &str = "ConnectTimeout=10";
+
+/// Espera para o agente subir depois de iniciado
+pub const STARTUP_WAIT: Duration = Duration::from_secs(2);
+
+/// Comando de instalação do serviço no Linux (o binário já copiado)
+pub fn linux_install_command() -> String {
+    format!(
+        "mkdir -p ~/.config/systemd/user ~/.snapshot_agent && \
+         echo '{}' > ~/.config/systemd/user/snapshot-agent.service && \
+         chmod +x ~/snapshot_agent && \
+         systemctl --user daemon-reload && \
+         systemctl --user enable snapshot-agent.service && \
+         systemctl --user start snapshot-agent.service && \
+         sleep 2 && \
+         systemctl --user status snapshot-agent.service",
+        LINUX_SERVICE
+    )
+}
+
+/// Operações disponíveis fora do menu
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Test,
+    Deploy,
+    Status,
+    Logs,
+    Restart,
+    Stop,
+    Remove,
+    ClearLogs,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 8] = [
+        Operation::Test,
+        Operation::Deploy,
+        Operation::Status,
+        Operation::Logs,
+        Operation::Restart,
+        Operation::Stop,
+        Operation::Remove,
+        Operation::ClearLogs,
+    ];
+
+    /// Nome do subcomando
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Test => "test",
+            Operation::Deploy => "deploy",
+            Operation::Status => "status",
+            Operation::Logs => "logs",
+            Operation::Restart => "restart",
+            Operation::Stop => "stop",
+            Operation::Remove => "remove",
+            Operation::ClearLogs => "clear-logs",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|op| op.name() == name)
+    }
+}
+
+/// Resultado de uma operação em uma VM
+#[derive(Serialize, Debug)]
+pub struct Outcome {
+    pub vm: String,
+    pub host: String, // Hostname resolvido ou endereço do inventário
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<&'static str>, // "linux", "windows" ou "unknown"
+    pub ok: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub details: String, // Saída relevante do comando remoto
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<Record>, // Registros do log (operação logs)
+}
+
+/// Executa `operation` na VM sem interação e sem imprimir nada
+pub fn execute(vm: &mut VMConnection, operation: Operation) -> Outcome {
+    if vm.hostname.is_none() {
+        let _ = vm.get_ssh_hostname();
+    }
+    let mut details = String::new();
+    let mut records = Vec::new();
+    let (ok, message) = match dispatch(vm, operation, &mut details, &mut records) {
+        Ok(Ok(message)) => (true, message),
+        Ok(Err(message)) => (false, message),
+        Err(e) => (false, format!("{:#}", e)),
+    };
+    Outcome {
+        ok,
+        details: details.trim().to_string(),
+        records,
+        ..Outcome::failed(vm, message)
+    }
+}
+
+impl Outcome {
+    /// Falha de `vm` com a mensagem, sem detalhes nem registros
+    pub fn failed(vm: &VMConnection, message: String) -> Self {
+        Outcome {
+            vm: vm.name().to_string(),
+            host: vm.get_current_hostname().to_string(),
+            os: vm.os.as_ref().map(|os| match os {
+                OperatingSystem::Linux(_) => "linux",
+                OperatingSystem::Windows(_) => "windows",
+                OperatingSystem::Unknown => "unknown",
+            }),
+            ok: false,
+            message,
+            details: String::new(),
+            records: Vec::new(),
+        }
+    }
+}
+
+/// Ok(Ok) com a mensagem de sucesso, Ok(Err) quando o comando rodou mas o
+/// resultado não é o esperado, Err quando não foi possível executá-lo
+type Step = Result<std::result::Result<String, String>>;
+
+fn dispatch(
+    vm: &mut VMConnection,
+    operation: Operation,
+    details: &mut String,
+    records: &mut Vec<Record>,
+) -> Step {
+    if operation != Operation::Test {
+        ensure_os(vm)?;
+    }
+    match operation {
+        Operation::Test => test(vm),
+        Operation::Deploy => deploy(vm, details),
+        Operation::Status => status(vm, details),
+        Operation::Logs => logs(vm, records),
+        Operation::Restart => restart(vm, details),
+        Operation::Stop => stop(vm, details),
+        Operation::Remove => remove(vm, details),
+        Operation::ClearLogs => clear_logs(vm),
+    }
+}
+
+/// Abre uma sessão sem comando, como o teste de conexão do menu
+fn connect(vm: &VMConnection) -> Result<Output> {
+    vm.ssh_with(&["-T", "-o", "BatchMode=yes", "-o", CONNECT_TIMEOUT])
+        .output()
+        .with_context(|| format!("Falha ao executar ssh em {}", vm.name()))
+}
+
+/// ssh sem prompts: sem a chave certa falha em vez de pedir senha
+pub fn remote(vm: &VMConnection, command: &str) -> Command {
+    let mut ssh = vm.ssh_with(&["-o", "BatchMode=yes", "-o", CONNECT_TIMEOUT]);
+    ssh.arg(command);
+    ssh
+}
+
+/// Executa um comando remoto capturando a saída
+/// O ssh sai com 255 nos próprios erros (conexão, autenticação), que não
+/// podem ser confundidos com o resultado do comando remoto
+fn run(vm: &VMConnection, command: &str) -> Result<Output> {
+    let output = remote(vm, command)
+        .output()
+        .with_context(|| format!("Falha ao executar ssh em {}", vm.name()))?;
+    if output.status.code() == Some(SSH_ERROR) {
+        return Err(anyhow!("Sem conexão SSH: {}", stderr_line(&output)));
+    }
+    Ok(output)
+}
+
+/// Primeira linha não vazia do stderr, para mensagens de erro
+fn stderr_line(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("sem detalhes")
+        .to_string()
+}
+
+/// Detecta o sistema se ainda não for conhecido
+fn ensure_os(vm: &mut VMConnection) -> Result<()> {
+    if matches!(vm.os, None | Some(OperatingSystem::Unknown)) {
+        let check = connect(vm)?;
+        if !check.status.success() {
+            return Err(anyhow!("Sem conexão SSH: {}", stderr_line(&check)));
+        }
+        vm.detect_os()?;
+    }
+    match vm.os {
+        Some(OperatingSystem::Unknown) | None => Err(anyhow!("Sistema operacional não detectado")),
+        _ => Ok(()),
+    }
+}
+
+fn is_linux(vm: &VMConnection) -> bool {
+    matches!(vm.os, Some(OperatingSystem::Linux(_)))
+}
+
+/// Se o processo do agente está em execução (saída do comando em `details`)
+fn agent_running(vm: &VMConnection, details: &mut String) -> Result<bool> {
+    if is_linux(vm) {
+        let check = run(vm, LINUX_PROCESS_CHECK)?;
+        *details = String::from_utf8_lossy(&check.stdout).into_owned();
+        Ok(check.status.success())
+    } else {
+        let check = run(vm, WINDOWS_PROCESS_CHECK)?;
+        let stdout = String::from_utf8_lossy(&check.stdout).into_owned();
+        let running = stdout.contains("snapshot_agent.exe");
+        *details = stdout;
+        Ok(running)
+    }
+}
+
+fn test(vm: &mut VMConnection) -> Step {
+    let check = connect(vm)?;
+    if !check.status.success() {
+        return Ok(Err(format!("Falha na conexão: {}", stderr_line(&check))));
+    }
+    vm.detect_os()?;
+    Ok(Ok(format!("Conectado ({})", vm.os_description())))
+}
+
+fn deploy(vm: &VMConnection, details: &mut String) -> Step {
+    let binary = if is_linux(vm) {
+        LINUX_AGENT_BINARY
+    } else {
+        WINDOWS_AGENT_BINARY
+    };
+    let destination = if is_linux(vm) {
+        "~/snapshot_agent"
+    } else {
+        "C:/Users/Public/snapshot_agent.exe"
+    };
+    let copy = vm
+        .scp_with(&["-B", "-o", CONNECT_TIMEOUT], binary, destination)
+        .output()
+        .context("Falha ao executar scp")?;
+    if !copy.status.success() {
+        return Ok(Err(format!(
+            "Falha ao copiar o agente: {}",
+            stderr_line(&copy)
+        )));
+    }
+
+    if is_linux(vm) {
+        if let Some(key) = &vm.entry.hmac_key {
+            if let Err(message) = provision_key(vm, &expand_home(key))? {
+                return Ok(Err(message));
+            }
+        }
+        let install = run(vm, &linux_install_command())?;
+        *details = String::from_utf8_lossy(&install.stdout).into_owned();
+        return Ok(if install.status.success() {
+            Ok("Agente instalado e iniciado (systemd)".to_string())
+        } else {
+            Err(format!(
+                "Falha ao configurar o serviço: {}",
+                stderr_line(&install)
+            ))
+        });
+    }
+
+    for command in [
+        WINDOWS_CREATE_LOG_DIR,
+        WINDOWS_CREATE_TASK,
+        WINDOWS_RUN_TASK,
+    ] {
+        let step = run(vm, command)?;
+        if !step.status.success() {
+            return Ok(Err(format!(
+                "Falha ao configurar a tarefa agendada: {}",
+                stderr_line(&step)
+            )));
+        }
+    }
+    std::thread::sleep(STARTUP_WAIT);
+    Ok(if agent_running(vm, details)? {
+        Ok("Agente instalado e iniciado (tarefa agendada)".to_string())
+    } else {
+        Err("Agente instalado, mas o processo não foi detectado".to_string())
+    })
+}
+
+/// Copia a chave do log para ~/.snapshot_agent/log.key (modo 0600),
+/// gerando-a localmente na primeira vez; a cópia local fica para o
+/// `verify` fora da VM
+fn provision_key(vm: &VMConnection, path: &Path) -> Step {
+    if !path.exists() {
+        generate_key(path)
+            .with_context(|| format!("Falha ao gerar a chave HMAC {}", path.display()))?;
+    }
+    let key = File::open(path)
+        .with_context(|| format!("Falha ao abrir a chave HMAC {}", path.display()))?;
+    let copy = remote(vm, LINUX_INSTALL_KEY)
+        .stdin(key)
+        .output()
+        .with_context(|| format!("Falha ao executar ssh em {}", vm.name()))?;
+    Ok(if copy.status.success() {
+        Ok("Chave HMAC copiada".to_string())
+    } else {
+        Err(format!(
+            "Falha ao copiar a chave HMAC: {}",
+            stderr_line(&copy)
+        ))
+    })
+}
+
+/// Gera 32 bytes aleatórios (hex) legíveis apenas pelo usuário
+fn generate_key(path: &Path) -> Result<()> {
+    let mut bytes = [0u8; 32];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    writeln!(options.open(path)?, "{}", hex)?;
+    Ok(())
+}
+
+fn status(vm: &VMConnection, details: &mut String) -> Step {
+    Ok(if agent_running(vm, details)? {
+        Ok("Agente em execução".to_string())
+    } else {
+        Err("Agente parado".to_string())
+    })
+}
+
+/// Linhas inválidas e tipos de registro de agentes mais novos são ignorados
+fn logs(vm: &VMConnection, records: &mut Vec<Record>) -> Step {
+    let command = if is_linux(vm) {
+        LINUX_LOGS
+    } else {
+        WINDOWS_LOGS
+    };
+    let output = run(vm, command)?;
+    if !output.status.success() {
+        return Ok(Err(format!(
+            "Falha ao ler os logs: {}",
+            stderr_line(&output)
+        )));
+    }
+    records.extend(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| Record::from_json(line).ok()),
+    );
+    Ok(Ok(format!("{} registros", records.len())))
+}
+
+fn restart(vm: &VMConnection, details: &mut String) -> Step {
+    if is_linux(vm) {
+        run(vm, LINUX_STOP)?;
+        std::thread::sleep(STARTUP_WAIT);
+        run(vm, LINUX_START)?;
+    } else {
+        run(vm, WINDOWS_RESTART)?;
+        std::thread::sleep(STARTUP_WAIT);
+    }
+    Ok(if agent_running(vm, details)? {
+        Ok("Agente reiniciado".to_string())
+    } else {
+        Err("Agente não está em execução após o reinício".to_string())
+    })
+}
+
+fn stop(vm: &VMConnection, details: &mut String) -> Step {
+    run(
+        vm,
+        if is_linux(vm) {
+            LINUX_STOP
+        } else {
+            WINDOWS_STOP
+        },
+    )?;
+    Ok(if agent_running(vm, details)? {
+        Err("Processo do agente ainda em execução".to_string())
+    } else {
+        details.clear();
+        Ok("Agente parado".to_string())
+    })
+}
+
+fn remove(vm: &VMConnection, details: &mut String) -> Step {
+    run(
+        vm,
+        if is_linux(vm) {
+            LINUX_REMOVE
+        } else {
+            WINDOWS_REMOVE
+        },
+    )?;
+    Ok(if agent_running(vm, details)? {
+        Err("Processo do agente ainda em execução após a remoção".to_string())
+    } else {
+        details.clear();
+        Ok("Agente removido".to_string())
+    })
+}
+
+fn clear_logs(vm: &VMConnection) -> Step {
+    let command = if is_linux(vm) {
+        LINUX_CLEAR_LOGS
+    } else {
+        WINDOWS_CLEAR_LOGS
+    };
+    let output = run(vm, command)?;
+    Ok(if output.status.success() {
+        Ok("Logs apagados".to_string())
+    } else {
+        Err(format!("Falha ao apagar logs: {}", stderr_line(&output)))
+    })
+}
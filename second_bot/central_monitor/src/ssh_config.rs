@@ -0,0 +1,354 @@
+// ------------------------------------------------------------------------------
+// DESCOBERTA DE HOSTS NO ~/.ssh/config
+// ------------------------------------------------------------------------------
+// Lê as entradas `Host` do ~/.ssh/config (seguindo as diretivas `Include`)
+// para importá-las no inventário. Padrões com curingas (`*`, `?`) e
+// negações (`!host`) não são máquinas e ficam de fora; os valores efetivos
+// de cada host (hostname, usuário, porta, chave) vêm do próprio ssh via
+// `ssh -G`, que já aplica os blocos com curingas e os padrões do sistema.
+// ------------------------------------------------------------------------------
+
+use crate::inventory::expand_home;
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Limite de Includes aninhados (o mesmo do OpenSSH)
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Configuração efetiva de um host segundo `ssh -G`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedHost {
+    pub alias: String,        // Nome da entrada Host
+    pub hostname: String,     // HostName efetivo (ou o próprio alias)
+    pub user: Option<String>, // User efetivo
+    pub port: u16,            // Port efetivo (22 se não configurado)
+    pub key: Option<PathBuf>, // Primeiro IdentityFile que existe no disco
+}
+
+/// Caminho do arquivo de configuração do usuário
+pub fn default_path() -> PathBuf {
+    expand_home("~/.ssh/config")
+}
+
+/// Nomes de host concretos declarados em `path` e nos arquivos incluídos,
+/// na ordem em que aparecem e sem repetições
+/// Arquivo inexistente equivale a uma configuração vazia
+pub fn discover_hosts(path: &Path) -> Result<Vec<String>> {
+    let mut hosts = Vec::new();
+    if path.exists() {
+        read_config(path, 0, &mut hosts)?;
+    }
+    Ok(hosts)
+}
+
+/// Lê um arquivo de configuração acumulando os hosts em `hosts`
+fn read_config(path: &Path, depth: usize, hosts: &mut Vec<String>) -> Result<()> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(anyhow!(
+            "Muitos Includes aninhados em {:?} (possível ciclo)",
+            path
+        ));
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("Falha ao ler {:?}", path))?;
+
+    for line in content.lines() {
+        let Some((keyword, args)) = split_directive(line) else {
+            continue;
+        };
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                for host in args {
+                    let concrete = !host.starts_with('!') && !host.contains(['*', '?']);
+                    if concrete && !hosts.contains(&host) {
+                        hosts.push(host);
+                    }
+                }
+            }
+            "include" => {
+                for pattern in args {
+                    for included in expand_include(&pattern)? {
+                        read_config(&included, depth + 1, hosts)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Separa uma linha em diretiva e argumentos
+/// Aceita `Chave valor` e `Chave=valor`, comentários e argumentos entre aspas
+fn split_directive(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let keyword = line[..end].to_string();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            // Palavra iniciada por '#' fora de aspas começa um comentário
+            '#' if current.is_empty() && !quoted => break,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    Some((keyword, args))
+}
+
+/// Arquivos de um `Include`, em ordem alfabética
+/// Caminhos relativos partem de ~/.ssh; curingas valem no nome do arquivo
+fn expand_include(pattern: &str) -> Result<Vec<PathBuf>> {
+    let path = if pattern.starts_with('~') || Path::new(pattern).is_absolute() {
+        expand_home(pattern)
+    } else {
+        expand_home("~/.ssh").join(pattern)
+    };
+
+    let file_pattern = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !file_pattern.contains(['*', '?']) {
+        // Include de arquivo inexistente é ignorado, como no ssh
+        return Ok(if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        });
+    }
+
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|file| {
+                file.is_file()
+                    && file
+                        .file_name()
+                        .is_some_and(|name| glob_match(&file_pattern, &name.to_string_lossy()))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    Ok(files)
+}
+
+/// Compara um nome com um padrão em que `*` casa qualquer sequência e `?`
+/// um caractere
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Resolve a configuração efetiva de um host com `ssh -G`
+pub fn resolve(alias: &str) -> Result<ResolvedHost> {
+    let output = Command::new("ssh")
+        .args(["-G", alias])
+        .output()
+        .with_context(|| format!("Falha ao executar ssh -G {}", alias))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ssh -G {} falhou: {}",
+            alias,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let mut host = ResolvedHost {
+        alias: alias.to_string(),
+        hostname: alias.to_string(),
+        user: None,
+        port: 22,
+        key: None,
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((keyword, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match keyword {
+            "hostname" => host.hostname = value.to_string(),
+            "user" => host.user = Some(value.to_string()),
+            "port" => host.port = value.parse().unwrap_or(22),
+            // O ssh lista as chaves padrão mesmo que não existam
+            "identityfile" if host.key.is_none() => {
+                let key = expand_home(value);
+                if key.is_file() {
+                    host.key = Some(key);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn directives_and_arguments() {
+        assert_eq!(
+            split_directive("  Host web db  "),
+            Some(("Host".to_string(), args(&["web", "db"])))
+        );
+        assert_eq!(
+            split_directive("HostName=10.0.0.1"),
+            Some(("HostName".to_string(), args(&["10.0.0.1"])))
+        );
+        assert_eq!(
+            split_directive("Port = 2222"),
+            Some(("Port".to_string(), args(&["2222"])))
+        );
+        assert_eq!(
+            split_directive("IdentityFile \"~/chaves/minha chave\""),
+            Some(("IdentityFile".to_string(), args(&["~/chaves/minha chave"])))
+        );
+        assert_eq!(split_directive("# Host comentado"), None);
+        assert_eq!(split_directive("   "), None);
+    }
+
+    #[test]
+    fn trailing_comments_end_the_line() {
+        assert_eq!(
+            split_directive("Host web # prod"),
+            Some(("Host".to_string(), args(&["web"])))
+        );
+        assert_eq!(
+            split_directive("Host web #prod db"),
+            Some(("Host".to_string(), args(&["web"])))
+        );
+        // '#' no meio de uma palavra ou entre aspas faz parte do valor
+        assert_eq!(
+            split_directive("Host web#1 \"#2\""),
+            Some(("Host".to_string(), args(&["web#1", "#2"])))
+        );
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("*.conf", "lab.conf"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("lab-?.conf", "lab-1.conf"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("lab-?.conf", "lab-10.conf"));
+        assert!(!glob_match("*.conf", "lab.conf.bak"));
+        assert!(!glob_match("lab", "lab2"));
+    }
+
+    /// Diretório temporário com os arquivos informados
+    fn temp_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+        for (file, content) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn includes_are_expanded_in_order() {
+        let dir = temp_dir(
+            "ssh-config-include",
+            &[
+                ("conf.d/b.conf", "Host beta\n"),
+                ("conf.d/a.conf", "Host alfa\n"),
+                ("conf.d/notas.txt", "Host ignorado\n"),
+                ("extra", "Host extra"),
+            ],
+        );
+        let pattern = dir.join("conf.d/*.conf");
+        assert_eq!(
+            expand_include(&pattern.to_string_lossy()).unwrap(),
+            vec![dir.join("conf.d/a.conf"), dir.join("conf.d/b.conf")]
+        );
+        let missing = dir.join("nao-existe");
+        assert!(expand_include(&missing.to_string_lossy())
+            .unwrap()
+            .is_empty());
+
+        let config = dir.join("config");
+        fs::write(
+            &config,
+            format!(
+                "Host web web # prod\n  HostName 10.0.0.1\n\
+                 Host *.lab !bastion gw?\n\
+                 Include {} {}\n\
+                 Include {}\n\
+                 Host db\n",
+                pattern.display(),
+                dir.join("extra").display(),
+                missing.display(),
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            discover_hosts(&config).unwrap(),
+            args(&["web", "alfa", "beta", "extra", "db"])
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn include_cycles_are_reported() {
+        let dir = temp_dir("ssh-config-cycle", &[]);
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config");
+        fs::write(&config, format!("Host a\nInclude {}\n", config.display())).unwrap();
+        let mut hosts = Vec::new();
+        let error = read_config(&config, 0, &mut hosts).unwrap_err();
+        assert!(error.to_string().contains("Includes aninhados"));
+        assert_eq!(hosts, args(&["a"]));
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(discover_hosts(&config).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,1366 @@
+use chrono::{SecondsFormat, Utc};
+use dirs_next::home_dir;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::compact::CompactEncoder;
+use crate::config::{AgentConfig, LogFormat, SinkConfig};
+use crate::integrity::{HashChain, GENESIS_HASH};
+use crate::signals::AgentEvent;
+use crate::{
+    auth, budget, compact, config, control, fim, identity, integrity, packages, ports, query,
+    signals, simulate, sinks, systemd, update, watch,
+};
+use serde_json::Value;
+use snapshot_agent::collectors::{
+    CgroupCollector, CpuCollector, CustomCollectors, DiskCollector, DiskUsage, FolderCollector,
+    HealthCollector, MemoryCollector, MemoryUsage, PortsCollector, ProcessCounts,
+    ProcessesCollector, SessionTracker, SessionsCollector,
+};
+use snapshot_agent::{Collector, Handle, Scheduler};
+use snapshot_schema::{
+    AgentOverhead, AgentStatus, CgroupStats, CustomMetric, FolderListing, Inventory,
+    ListeningSocket, LogError, LoginSession, Record, ServiceStatus, Snapshot, SystemHealth,
+    SCHEMA_VERSION,
+};
+
+/// =========================================================================
+/// ESTRUTURAS DE DADOS
+/// =========================================================================
+/// Os tipos de registro (Snapshot, ServiceStatus, LogError, Inventory) são
+/// definidos no crate compartilhado `snapshot_schema`, também usado pelo
+/// central_monitor para ler o log
+
+/// =========================================================================
+/// FUNÇÕES DE UTILITÁRIOS E CAMINHOS
+/// =========================================================================
+
+/// Retorna o caminho completo do arquivo de log
+/// Localizado em ~/.snapshot_agent/snapshot.log
+fn get_log_path() -> PathBuf {
+    let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".snapshot_agent");
+    path.push("snapshot.log");
+    path
+}
+
+/// Retorna o caminho do log no formato compacto
+/// Localizado em ~/.snapshot_agent/snapshot.bin
+fn get_compact_log_path() -> PathBuf {
+    get_log_path().with_file_name("snapshot.bin")
+}
+
+/// Retorna o caminho do log usado por um formato
+fn get_log_path_for(format: LogFormat) -> PathBuf {
+    match format {
+        LogFormat::Json => get_log_path(),
+        LogFormat::Compact => get_compact_log_path(),
+    }
+}
+
+/// Retorna o caminho do log no formato atualmente configurado
+fn get_active_log_path() -> PathBuf {
+    get_log_path_for(LOG_WRITER.lock().expect("Erro ao acessar o log").format)
+}
+
+/// Estado compartilhado da escrita do log
+/// Guarda o formato em uso, o codificador do formato compacto, que
+/// precisa lembrar o registro anterior para gerar deltas, o estado da
+/// cadeia de hashes quando ativa e as demais saídas configuradas
+struct LogWriter {
+    format: LogFormat,
+    encoder: CompactEncoder,
+    chain: Option<HashChain>,
+    file: bool,                 // Grava no arquivo de log (saída `file`)
+    sinks: Vec<SinkSlot>,       // Saídas além do arquivo
+    file_error: Option<String>, // Última falha ao gravar o arquivo
+}
+
+/// Saída configurada e sua última falha (o mesmo erro não é repetido)
+struct SinkSlot {
+    sink: Box<dyn sinks::Sink>,
+    last_error: Option<String>,
+}
+
+static LOG_WRITER: Mutex<LogWriter> = Mutex::new(LogWriter {
+    format: LogFormat::Json,
+    encoder: CompactEncoder::new(),
+    chain: None,
+    file: true,
+    sinks: Vec::new(),
+    file_error: None,
+});
+
+/// Aplica o formato, a integridade e as saídas do log (na inicialização e
+/// ao recarregar)
+/// Se a chave do HMAC não puder ser lida, a cadeia segue sem HMAC; saídas
+/// que não puderem ser criadas ficam de fora. Os erros são devolvidos para
+/// serem registrados
+fn configure_log(config: &AgentConfig) -> Vec<String> {
+    let mut writer = LOG_WRITER.lock().expect("Erro ao acessar o log");
+    if writer.format != config.log_format {
+        writer.format = config.log_format;
+        writer.encoder = CompactEncoder::new();
+    }
+
+    writer.file = config.sinks.contains(&SinkConfig::File);
+    let (sinks, mut errors) = sinks::build(&config.sinks);
+    writer.sinks = sinks
+        .into_iter()
+        .map(|sink| SinkSlot {
+            sink,
+            last_error: None,
+        })
+        .collect();
+
+    if !config.integrity.hash_chain {
+        writer.chain = None;
+        return errors;
+    }
+    let key = match config.integrity.key_file() {
+        Some(path) => integrity::load_key(&path).map_err(|e| errors.push(e)).ok(),
+        None => None,
+    };
+    // A cadeia continua a partir do último registro já gravado
+    let prev_hash = last_record_hash(&get_log_path_for(writer.format));
+    writer.chain = Some(HashChain::new(prev_hash, key));
+    errors
+}
+
+/// Hash do último registro legível do log (GENESIS_HASH se vazio)
+fn last_record_hash(path: &Path) -> String {
+    query::read_values(path)
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .find_map(Result::ok)
+        .map(|record| integrity::record_hash(&record))
+        .unwrap_or_else(|| GENESIS_HASH.to_string())
+}
+
+/// Grava uma entrada JSON no arquivo de log
+/// Cria diretórios necessários se não existirem
+/// No formato compacto a entrada é convertida em um frame CBOR
+fn write_log_file(writer: &mut LogWriter, json: &str) -> io::Result<()> {
+    let log_path = get_log_path_for(writer.format);
+    if writer.format == LogFormat::Compact {
+        return compact::append_record(&log_path, &mut writer.encoder, json);
+    }
+
+    if let Some(log_dir) = log_path.parent() {
+        fs::create_dir_all(log_dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+    writeln!(file, "{}", json)
+}
+
+/// Envia uma entrada JSON ao arquivo de log e às demais saídas
+/// Cada saída falha de forma independente; falhas novas são registradas
+/// como ERROR nas outras saídas e na saída de erro
+fn append_to_log(json: &str) {
+    let (failures, agent_id, hostname) = {
+        let mut writer = LOG_WRITER.lock().expect("Erro ao acessar o log");
+        let mut record: Value = serde_json::from_str(json).expect("Registro JSON inválido");
+
+        // Com a cadeia ativa, o registro recebe prev_hash (e hmac) antes de ser gravado
+        let log_path = get_log_path_for(writer.format);
+        let sealed;
+        let json = match &mut writer.chain {
+            Some(chain) => {
+                // Log esvaziado (--reset ou limpeza pelo central_monitor): nova cadeia
+                if fs::metadata(&log_path).map_or(true, |m| m.len() == 0) {
+                    chain.prev_hash = GENESIS_HASH.to_string();
+                }
+                chain.seal(&mut record);
+                sealed = record.to_string();
+                sealed.as_str()
+            }
+            None => json,
+        };
+
+        let mut failures = Vec::new();
+        if writer.file {
+            let result = write_log_file(&mut writer, json).map_err(|e| e.to_string());
+            if let Some(error) = track_failure(&mut writer.file_error, result) {
+                failures.push(format!("Erro ao gravar log: {}", error));
+            }
+        }
+        for slot in &mut writer.sinks {
+            let result = slot.sink.write(&record, json);
+            if let Some(error) = track_failure(&mut slot.last_error, result) {
+                failures.push(format!("Saída {}: {}", slot.sink.name(), error));
+            }
+        }
+        let field = |name: &str| record.get(name).and_then(Value::as_str).map(str::to_string);
+        (
+            failures,
+            field("agent_id").unwrap_or_default(),
+            field("hostname"),
+        )
+    };
+
+    // Fora do lock: o ERROR passa pelas mesmas saídas; a que falhou repete o
+    // mesmo erro, que não é registrado de novo
+    for failure in failures {
+        eprintln!("{}", failure);
+        append_record(Record::Error(LogError {
+            schema_version: SCHEMA_VERSION,
+            agent_id: agent_id.clone(),
+            hostname: hostname.clone().unwrap_or_else(get_hostname),
+            datetime: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            timestamp: Utc::now().timestamp().max(0) as u64,
+            error: failure,
+        }));
+    }
+}
+
+/// Atualiza a última falha de uma saída
+/// Retorna o erro apenas se ele for novo
+fn track_failure(last_error: &mut Option<String>, result: Result<(), String>) -> Option<String> {
+    match result {
+        Ok(()) => {
+            *last_error = None;
+            None
+        }
+        Err(e) if last_error.as_ref() == Some(&e) => None,
+        Err(e) => {
+            *last_error = Some(e.clone());
+            Some(e)
+        }
+    }
+}
+
+/// Serializa um registro (com `record_type` e `schema_version`) e grava no log
+fn append_record(record: Record) {
+    if let Record::Error(error) = &record {
+        control::record_error(error);
+    }
+    append_to_log(&record.to_json());
+}
+
+/// Converte um log compacto de volta para linhas JSON na saída padrão
+/// Subcomando: `snapshot_agent decode [arquivo]` (padrão: snapshot.bin)
+fn decode_compact_log(path: PathBuf) -> Result<(), String> {
+    let file = File::open(&path).map_err(|e| format!("Erro ao abrir {:?}: {}", path, e))?;
+    let decoder = compact::CompactDecoder::new(BufReader::new(file))?;
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    for record in decoder {
+        let json = serde_json::to_string(&record?).map_err(|e| e.to_string())?;
+        if writeln!(out, "{}", json).is_err() {
+            break; // Saída fechada (ex.: `| head`)
+        }
+    }
+    let _ = out.flush();
+    Ok(())
+}
+
+/// Lê ou cria o agent_id persistente
+/// Sem ele os registros não podem ser correlacionados: o agente não inicia
+fn load_agent_id() -> String {
+    snapshot_agent::identity::load_or_create_agent_id().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Lê ou cria o sal secreto dos nomes de arquivos em hash
+/// Se não puder ser gravado, usa um sal só desta execução: os nomes
+/// continuam protegidos, mas os hashes mudam no próximo reinício
+fn load_name_salt(agent_id: &str, hostname: &str) -> String {
+    snapshot_agent::identity::load_or_create_name_salt().unwrap_or_else(|e| {
+        log_agent_error(agent_id, hostname, e);
+        snapshot_agent::identity::ephemeral_name_salt()
+    })
+}
+
+/// Obtém o nome do host atual do sistema
+/// Utiliza a crate hostname para acessar esta informação
+fn get_hostname() -> String {
+    hostname::get()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// =========================================================================
+/// FUNÇÕES DE LOG E STATUS
+/// =========================================================================
+
+/// Registra o status de execução do serviço (iniciado/parado)
+/// Essencial para rastreamento do ciclo de vida do agente
+fn log_service_status(status: AgentStatus, agent_id: &str, reason: Option<&str>) {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Erro ao obter timestamp");
+
+    let status_log = ServiceStatus {
+        schema_version: SCHEMA_VERSION,
+        agent_id: agent_id.to_string(),
+        datetime: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        timestamp: now.as_secs(),
+        status,
+        hostname: get_hostname(),
+        reason: reason.map(str::to_string),
+    };
+
+    append_record(Record::ServiceStatus(status_log));
+}
+
+/// Registra o inventário do host (SO, kernel, CPU, memória, boot)
+/// Gravado a cada inicialização, logo após o registro STARTED
+fn log_inventory(agent_id: &str, hostname: &str) {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Erro ao obter timestamp");
+
+    let record = Inventory {
+        schema_version: SCHEMA_VERSION,
+        agent_id: agent_id.to_string(),
+        hostname: hostname.to_string(),
+        datetime: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        timestamp: now.as_secs(),
+        inventory: identity::collect_inventory(),
+    };
+
+    append_record(Record::Inventory(record));
+}
+
+/// Registra um erro do agente no log
+/// Usado para falhas fora da coleta (ex.: configuração inválida)
+fn log_agent_error(agent_id: &str, hostname: &str, error: String) {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Erro ao obter timestamp");
+
+    let log_error = LogError {
+        schema_version: SCHEMA_VERSION,
+        agent_id: agent_id.to_string(),
+        hostname: hostname.to_string(),
+        datetime: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        timestamp: now.as_secs(),
+        error,
+    };
+
+    append_record(Record::Error(log_error));
+}
+
+/// =========================================================================
+/// COLETA DO SNAPSHOT
+/// =========================================================================
+
+/// Coleta um snapshot e grava no log
+fn executar_snapshot(
+    agent_id: &str,
+    hostname: &str,
+    collectors: &mut SnapshotCollectors,
+    overhead: Option<AgentOverhead>,
+) -> Snapshot {
+    let snapshot = coletar_snapshot(agent_id, hostname, collectors, overhead);
+    append_record(Record::Snapshot(snapshot.clone()));
+
+    eprintln!("Snapshot salvo em {}", get_active_log_path().display());
+    snapshot
+}
+
+/// Verifica a watchlist e grava as mudanças de estado (PROCESS_DOWN/UP)
+fn verificar_watchlist(
+    watcher: &mut watch::Watcher,
+    agent_id: &str,
+    hostname: &str,
+    config: &AgentConfig,
+) {
+    let (records, errors) = watcher.check(&config.watchlist, agent_id, hostname);
+    for record in records {
+        if let Record::ProcessDown(event) | Record::ProcessUp(event) = &record {
+            eprintln!("{} {} {:?}", record.record_type(), event.name, event.pids);
+        }
+        append_record(record);
+    }
+    for error in errors {
+        log_agent_error(agent_id, hostname, error);
+    }
+}
+
+/// Estado do acompanhamento de logins (wtmp) e dos logs de autenticação
+struct SecurityTrackers {
+    sessions: Option<SessionTracker>,   // Só com `security.sessions`
+    auth: Option<auth::AuthLogTracker>, // Só com `security.auth_failures`
+}
+
+impl SecurityTrackers {
+    fn new(config: &AgentConfig) -> Self {
+        let mut trackers = Self {
+            sessions: None,
+            auth: None,
+        };
+        trackers.reconfigure(config);
+        trackers
+    }
+
+    /// Ajusta o acompanhamento do wtmp e dos logs de autenticação após
+    /// recarregar a configuração; ao ser ativado, o tail começa no fim
+    fn reconfigure(&mut self, config: &AgentConfig) {
+        let security = &config.security;
+        if !security.auth_failures {
+            self.auth = None;
+        } else if self.auth.is_none() {
+            self.auth = Some(auth::AuthLogTracker::new(&security.auth_logs));
+        }
+
+        let unchanged = self
+            .sessions
+            .as_ref()
+            .is_some_and(|tracker| tracker.wtmp() == security.wtmp_file);
+        if !security.sessions {
+            self.sessions = None;
+        } else if !unchanged {
+            self.sessions = Some(SessionTracker::new(
+                &security.utmp_file,
+                &security.wtmp_file,
+            ));
+        }
+    }
+}
+
+/// Grava logins/logouts novos e as falhas de autenticação do intervalo
+fn verificar_seguranca(
+    trackers: &mut SecurityTrackers,
+    agent_id: &str,
+    hostname: &str,
+    config: &AgentConfig,
+) {
+    let mut errors = Vec::new();
+    if let Some(tracker) = &mut trackers.sessions {
+        let (records, session_errors) = tracker.poll(agent_id, hostname);
+        records.into_iter().for_each(append_record);
+        errors.extend(session_errors);
+    }
+
+    if let Some(tracker) = &mut trackers.auth {
+        let (failures, auth_errors) = tracker.poll(&config.security.auth_logs, agent_id, hostname);
+        if let Some(failures) = failures {
+            eprintln!(
+                "Falhas de autenticação: {} SSH, {} sudo",
+                failures.ssh_failures, failures.sudo_failures
+            );
+            append_record(Record::AuthFailures(failures));
+        }
+        errors.extend(auth_errors);
+    }
+
+    for error in errors {
+        log_agent_error(agent_id, hostname, error);
+    }
+}
+
+/// Compara os arquivos monitorados com o baseline e grava as alterações
+fn verificar_integridade_arquivos(
+    monitor: &mut fim::FileMonitor,
+    agent_id: &str,
+    hostname: &str,
+    config: &AgentConfig,
+) {
+    let (records, errors) = monitor.check(&config.file_integrity, agent_id, hostname);
+    for record in records {
+        if let Record::FileChanged(change) = &record {
+            eprintln!(
+                "Arquivo alterado: {} ({})",
+                change.path,
+                change.changed.join(", ")
+            );
+        }
+        append_record(record);
+    }
+    for error in errors {
+        log_agent_error(agent_id, hostname, error);
+    }
+}
+
+/// Coletores do snapshot registrados no agendador
+/// Ao recarregar a configuração (SIGHUP) os coletores com estado entre
+/// coletas (CPU, taxas de I/O) são mantidos; os que dependem da
+/// configuração são trocados
+struct SnapshotCollectors {
+    scheduler: Scheduler,
+    memory: Handle<MemoryUsage>,
+    cpu: Handle<f32>,
+    disk: Handle<DiskUsage>,
+    folders: Vec<Handle<FolderListing>>, // Na ordem de `folders` da configuração
+    custom: Handle<(BTreeMap<String, CustomMetric>, Vec<String>)>,
+    health: Handle<SystemHealth>,
+    processes: Handle<ProcessCounts>,
+    cgroup: Handle<Option<CgroupStats>>,
+    ports: Option<Handle<Vec<ListeningSocket>>>, // Só com `ports.enabled`
+    sessions: Option<Handle<Vec<LoginSession>>>, // Só com `security.sessions`
+}
+
+impl SnapshotCollectors {
+    /// `name_salt` é a chave secreta dos nomes de arquivos em hash
+    fn new(config: &AgentConfig, name_salt: &str) -> Self {
+        let mut scheduler = Scheduler::new();
+        let memory = agendar(&mut scheduler, config, MemoryCollector::new());
+        let cpu = agendar(&mut scheduler, config, CpuCollector::new());
+        let disk = agendar(&mut scheduler, config, DiskCollector::new());
+        let folders = config
+            .folders
+            .iter()
+            .map(|folder| agendar(&mut scheduler, config, folder_collector(folder, name_salt)))
+            .collect();
+        let custom = agendar(
+            &mut scheduler,
+            config,
+            CustomCollectors::new(config.custom_collectors.clone()),
+        );
+        let health = agendar(&mut scheduler, config, HealthCollector::new());
+        let processes = agendar(&mut scheduler, config, ProcessesCollector::new());
+        let cgroup = agendar(&mut scheduler, config, CgroupCollector::new());
+        let ports = config
+            .ports
+            .enabled
+            .then(|| agendar(&mut scheduler, config, PortsCollector::new()));
+        let sessions = config.security.sessions.then(|| {
+            let utmp = config.security.utmp_file.clone();
+            agendar(&mut scheduler, config, SessionsCollector::new(utmp))
+        });
+        Self {
+            scheduler,
+            memory,
+            cpu,
+            disk,
+            folders,
+            custom,
+            health,
+            processes,
+            cgroup,
+            ports,
+            sessions,
+        }
+    }
+
+    /// Aplica uma configuração recarregada sem recriar os coletores com estado
+    fn reconfigure(&mut self, config: &AgentConfig, name_salt: &str) {
+        let scheduler = &mut self.scheduler;
+        reagendar(scheduler, config, &self.memory);
+        reagendar(scheduler, config, &self.cpu);
+        reagendar(scheduler, config, &self.disk);
+        reagendar(scheduler, config, &self.health);
+        reagendar(scheduler, config, &self.processes);
+        reagendar(scheduler, config, &self.cgroup);
+
+        // Pastas: as posições existentes são reaproveitadas
+        let mut folders = config.folders.iter();
+        let mut handles = Vec::new();
+        for handle in self.folders.drain(..) {
+            match folders.next() {
+                Some(folder) => {
+                    substituir(
+                        scheduler,
+                        config,
+                        &handle,
+                        folder_collector(folder, name_salt),
+                    );
+                    handles.push(handle);
+                }
+                None => scheduler.remove(handle),
+            }
+        }
+        for folder in folders {
+            handles.push(agendar(
+                scheduler,
+                config,
+                folder_collector(folder, name_salt),
+            ));
+        }
+        self.folders = handles;
+
+        let custom = CustomCollectors::new(config.custom_collectors.clone());
+        substituir(scheduler, config, &self.custom, custom);
+
+        match (self.ports, config.ports.enabled) {
+            (Some(handle), true) => reagendar(scheduler, config, &handle),
+            (Some(handle), false) => {
+                scheduler.remove(handle);
+                self.ports = None;
+            }
+            (None, true) => self.ports = Some(agendar(scheduler, config, PortsCollector::new())),
+            (None, false) => {}
+        }
+        let sessions = SessionsCollector::new(config.security.utmp_file.clone());
+        match (self.sessions, config.security.sessions) {
+            (Some(handle), true) => substituir(scheduler, config, &handle, sessions),
+            (Some(handle), false) => {
+                scheduler.remove(handle);
+                self.sessions = None;
+            }
+            (None, true) => self.sessions = Some(agendar(scheduler, config, sessions)),
+            (None, false) => {}
+        }
+    }
+
+    /// Último valor de um coletor opcional (vazio se desativado)
+    fn optional<T: Clone + Default + 'static>(&self, handle: Option<Handle<T>>) -> T {
+        handle
+            .and_then(|handle| self.scheduler.latest(&handle).cloned())
+            .unwrap_or_default()
+    }
+}
+
+/// Coletor de uma pasta da configuração
+/// `name_salt` é a chave secreta dos nomes de arquivos em hash
+fn folder_collector(folder: &config::FolderConfig, name_salt: &str) -> FolderCollector {
+    FolderCollector::new(folder.path())
+        .with_filter(folder.filter.clone())
+        .with_hash_key(name_salt)
+}
+
+/// Registra um coletor com o intervalo de `collector_intervals`
+fn agendar<C: Collector + 'static>(
+    scheduler: &mut Scheduler,
+    config: &AgentConfig,
+    collector: C,
+) -> Handle<C::Output> {
+    let interval = config.collector_interval(collector.name());
+    scheduler.add_every(collector, interval)
+}
+
+/// Troca um coletor registrado, com o intervalo de `collector_intervals`
+fn substituir<C: Collector + 'static>(
+    scheduler: &mut Scheduler,
+    config: &AgentConfig,
+    handle: &Handle<C::Output>,
+    collector: C,
+) {
+    let interval = config.collector_interval(collector.name());
+    scheduler.replace(handle, collector, interval);
+}
+
+/// Atualiza o intervalo de um coletor mantido após recarregar a configuração
+fn reagendar<T>(scheduler: &mut Scheduler, config: &AgentConfig, handle: &Handle<T>) {
+    if let Some(name) = scheduler.name(handle) {
+        scheduler.set_interval(handle, config.collector_interval(name));
+    }
+}
+
+/// Executa os coletores vencidos, em paralelo, e monta o snapshot
+/// Coletores com intervalo próprio que não venceram entram com o último valor
+fn coletar_snapshot(
+    agent_id: &str,
+    hostname: &str,
+    collectors: &mut SnapshotCollectors,
+    overhead: Option<AgentOverhead>,
+) -> Snapshot {
+    // Orçamento do agente excedido: coletores caros ficam de fora
+    let skipped = overhead
+        .as_ref()
+        .map(|overhead| overhead.skipped.clone())
+        .unwrap_or_default();
+    for error in collectors.scheduler.run_due(&skipped) {
+        log_agent_error(agent_id, hostname, error.error);
+    }
+
+    let scheduler = &collectors.scheduler;
+    let memory = scheduler
+        .latest(&collectors.memory)
+        .copied()
+        .unwrap_or_default();
+    let disk = scheduler
+        .latest(&collectors.disk)
+        .copied()
+        .unwrap_or_default();
+
+    // Primeira pasta em `folder_files`; as demais em `folders` (as que
+    // falharam ficam de fora, com o erro no log)
+    let mut listings = collectors
+        .folders
+        .iter()
+        .map(|handle| scheduler.latest(handle).cloned());
+    let (folder_files, folder_total) = match listings.next() {
+        Some(Some(listing)) => (listing.files, Some(listing.total)),
+        Some(None) => (vec!["<pasta não encontrada>".to_string()], None),
+        None => (Vec::new(), None),
+    };
+    let folders = listings.flatten().collect();
+
+    // Falhas dos coletores personalizados vão para o log uma vez por execução
+    let (custom, custom_errors) = scheduler
+        .latest(&collectors.custom)
+        .cloned()
+        .unwrap_or_default();
+    if scheduler.is_fresh(&collectors.custom) {
+        for error in custom_errors {
+            log_agent_error(agent_id, hostname, error);
+        }
+    }
+
+    // Contagem de processos fica zerada quando o coletor é omitido
+    let counts = scheduler
+        .latest(&collectors.processes)
+        .copied()
+        .unwrap_or_default();
+    let health = scheduler
+        .latest(&collectors.health)
+        .cloned()
+        .map(|mut health| {
+            health.processes = counts.processes;
+            health.threads = counts.threads;
+            health.zombies = counts.zombies;
+            health
+        });
+
+    // =====================================================================
+    // GERAÇÃO E ARMAZENAMENTO DO SNAPSHOT
+    // =====================================================================
+    // Obtém timestamp atual para o snapshot
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("Erro ao obter timestamp");
+    let timestamp = now.as_secs();
+    let datetime = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    // Cria o objeto Snapshot com todos os dados coletados
+    Snapshot {
+        schema_version: SCHEMA_VERSION,
+        agent_id: agent_id.to_string(),
+        hostname: hostname.to_string(),
+        timestamp,
+        datetime,
+        total_memory: memory.total,
+        used_memory: memory.used,
+        cpu_usage_percent: scheduler.latest(&collectors.cpu).copied().unwrap_or(0.0),
+        total_disk: disk.total,
+        used_disk: disk.used,
+        folder_files,
+        folder_total,
+        folders,
+        custom,
+        sessions: collectors.optional(collectors.sessions),
+        cgroup: scheduler.latest(&collectors.cgroup).cloned().flatten(),
+        health,
+        overhead,
+        listening: collectors.optional(collectors.ports),
+    }
+}
+
+/// =========================================================================
+/// MODOS AUXILIARES (RESET E CONSULTA)
+/// =========================================================================
+
+/// Confere a cadeia de hashes do log
+/// Subcomando: `snapshot_agent verify [--file CAMINHO] [--key CAMINHO]`
+/// Retorna `Ok(false)` se algum elo estiver quebrado ou se nenhum registro
+/// estiver encadeado
+fn run_verify(args: &[String]) -> Result<bool, String> {
+    let options = integrity::parse_args(args)?;
+    let config = config::load_config()?;
+    let path = options
+        .file
+        .unwrap_or_else(|| get_log_path_for(config.log_format));
+    let key = match options.key.or(config.integrity.key_file()) {
+        Some(key_path) => Some(integrity::load_key(&key_path)?),
+        None => None,
+    };
+
+    let require_chain = key.is_some() || config.integrity.hash_chain;
+    let report = integrity::verify(query::read_values(&path)?, key.as_deref(), require_chain);
+    integrity::print_report(&report, &path, key.is_some());
+    Ok(report.is_intact())
+}
+
+/// Esvazia o log JSON e, se existir, o log compacto
+fn reset_logs() {
+    let log_path = get_log_path();
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent).expect("Erro ao criar pasta do log");
+    }
+
+    match fs::write(&log_path, b"") {
+        Ok(_) => println!("Arquivo de log resetado em {}", log_path.display()),
+        Err(e) => eprintln!("Erro ao resetar o log: {}", e),
+    }
+
+    // O log compacto, se existir, também é esvaziado
+    let compact_path = get_compact_log_path();
+    if compact_path.exists() {
+        match fs::write(&compact_path, b"") {
+            Ok(_) => println!("Arquivo de log resetado em {}", compact_path.display()),
+            Err(e) => eprintln!("Erro ao resetar o log compacto: {}", e),
+        }
+    }
+}
+
+/// Subcomando query: resume o log local em um intervalo de tempo
+/// Sem `--file`, lê o log do formato configurado
+fn run_query(args: &[String]) -> Result<(), String> {
+    let options = query::parse_args(args)?;
+    let path = match &options.file {
+        Some(path) => path.clone(),
+        None => get_log_path_for(config::load_config()?.log_format),
+    };
+
+    let records = query::read_records(&path)?;
+    let summary = query::summarize_records(&records, &options);
+    if options.json {
+        let json = serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?;
+        println!("{}", json);
+    } else {
+        query::print_summary(&summary);
+    }
+    Ok(())
+}
+
+/// Subcomando update: instala a versão publicada na fonte, reinicia o
+/// serviço e aguarda o primeiro snapshot da versão nova
+/// Opções: `--source <dir|url>` e `--no-restart`
+fn run_update(args: &[String]) -> Result<(), String> {
+    let mut source = None;
+    let mut restart = true;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--source" => source = Some(iter.next().ok_or("--source exige um valor")?.clone()),
+            "--no-restart" => restart = false,
+            other => return Err(format!("Argumento desconhecido: {}", other)),
+        }
+    }
+
+    let config = config::load_config()?;
+    let binary = update::current_binary()?;
+    let pending = match update::install(&config.update, source.as_deref(), &binary)? {
+        update::UpdateOutcome::UpToDate(hash) => {
+            println!("Agente já está atualizado ({})", update::short(&hash));
+            return Ok(());
+        }
+        update::UpdateOutcome::Installed(pending) => pending,
+    };
+    println!(
+        "Versão {} instalada em {} (anterior em {})",
+        update::short(&pending.sha256),
+        binary.display(),
+        pending.previous.display()
+    );
+    if !restart {
+        println!("Reinicie o serviço; a versão é confirmada no primeiro snapshot");
+        return Ok(());
+    }
+
+    // Sem o reinício a versão nova não roda: restaura a anterior
+    if let Err(e) = update::restart(&config.update) {
+        update::rollback(&pending)?;
+        return Err(format!("{}; versão anterior restaurada", e));
+    }
+    let timeout = Duration::from_secs(config.update.confirm_timeout_secs);
+    match update::wait_confirmation(&pending, timeout) {
+        Ok(true) => {
+            println!("Primeiro snapshot concluído; atualização confirmada");
+            Ok(())
+        }
+        Ok(false) => Err("A versão nova falhou no primeiro snapshot e foi revertida".to_string()),
+        Err(e) => {
+            update::rollback(&pending)?;
+            update::restart(&config.update)?;
+            Err(format!("{}; versão anterior restaurada", e))
+        }
+    }
+}
+
+/// Primeiro snapshot de uma versão recém-instalada
+/// Concluído, confirma a atualização; se falhar (pânico na coleta), restaura
+/// a versão anterior e a executa no lugar deste processo
+fn confirmar_atualizacao(
+    pending: &update::PendingUpdate,
+    agent_id: &str,
+    hostname: &str,
+    collectors: &mut SnapshotCollectors,
+    overhead: AgentOverhead,
+) -> Option<Snapshot> {
+    let result = std::panic::catch_unwind(AssertUnwindSafe(move || {
+        executar_snapshot(agent_id, hostname, collectors, Some(overhead))
+    }));
+    if let Ok(snapshot) = result {
+        match update::confirm() {
+            Ok(()) => eprintln!("Atualização {} confirmada", update::short(&pending.sha256)),
+            Err(e) => log_agent_error(agent_id, hostname, e),
+        }
+        return Some(snapshot);
+    }
+
+    log_agent_error(
+        agent_id,
+        hostname,
+        format!(
+            "Primeiro snapshot da versão {} falhou; restaurando a anterior",
+            update::short(&pending.sha256)
+        ),
+    );
+    match update::rollback(pending) {
+        Ok(()) => {
+            let e = update::reexec(&pending.binary);
+            log_agent_error(
+                agent_id,
+                hostname,
+                format!("Erro ao executar a versão restaurada: {}", e),
+            );
+        }
+        Err(e) => log_agent_error(agent_id, hostname, e),
+    }
+    None
+}
+
+/// Compara as portas do snapshot com as anteriores (PORT_OPENED/CLOSED)
+fn verificar_portas(
+    tracker: &mut ports::PortTracker,
+    snapshot: &Snapshot,
+    agent_id: &str,
+    hostname: &str,
+) {
+    let (records, errors) = tracker.check(&snapshot.listening, agent_id, hostname);
+    for record in records {
+        if let Record::PortOpened(event) | Record::PortClosed(event) = &record {
+            eprintln!(
+                "{} {} ({})",
+                record.record_type(),
+                event.socket.key(),
+                event.socket.process.as_deref().unwrap_or("?")
+            );
+        }
+        append_record(record);
+    }
+    for error in errors {
+        log_agent_error(agent_id, hostname, error);
+    }
+}
+
+/// Inventário de pacotes e unidades systemd, no intervalo próprio
+fn verificar_pacotes(
+    tracker: &mut packages::PackageTracker,
+    agent_id: &str,
+    hostname: &str,
+    config: &AgentConfig,
+) {
+    let (records, errors) = tracker.check(&config.packages, agent_id, hostname);
+    for record in records {
+        match &record {
+            Record::PackageChanged(change) => eprintln!(
+                "PACKAGE_CHANGED {} {} ({} -> {})",
+                change.change,
+                change.name,
+                change.old_version.as_deref().unwrap_or("-"),
+                change.new_version.as_deref().unwrap_or("-")
+            ),
+            Record::UnitChanged(change) => {
+                let unit = change.new.as_ref().or(change.old.as_ref());
+                eprintln!(
+                    "UNIT_CHANGED {} {}",
+                    change.change,
+                    unit.map(|u| u.name.as_str()).unwrap_or("?")
+                );
+            }
+            Record::PackageInventory(inventory) => eprintln!(
+                "PACKAGE_INVENTORY {} pacotes, {} unidades",
+                inventory.packages.len(),
+                inventory.units.len()
+            ),
+            _ => {}
+        }
+        append_record(record);
+    }
+    for error in errors {
+        log_agent_error(
+            agent_id,
+            hostname,
+            format!("Inventário de pacotes: {}", error),
+        );
+    }
+}
+
+/// Verificação periódica de atualização (em segundo plano)
+fn verificar_atualizacao(
+    checker: &mut update::UpdateChecker,
+    agent_id: &str,
+    hostname: &str,
+    config: &AgentConfig,
+) {
+    match checker.poll(&config.update) {
+        Some(Ok(update::UpdateOutcome::Installed(pending))) => eprintln!(
+            "Versão {} instalada; aguardando reinício do serviço",
+            update::short(&pending.sha256)
+        ),
+        Some(Err(e)) => log_agent_error(agent_id, hostname, format!("Atualização: {}", e)),
+        Some(Ok(update::UpdateOutcome::UpToDate(_))) | None => {}
+    }
+}
+
+/// Abre, fecha ou muda de caminho o socket de controle conforme `control`
+/// (na inicialização e ao recarregar); sem mudança o socket atual continua
+fn configure_control(
+    listener: &mut Option<control::ControlListener>,
+    config: &AgentConfig,
+    state: &Arc<Mutex<control::ControlState>>,
+    tx: &Sender<AgentEvent>,
+) -> Option<String> {
+    let wanted = config
+        .control
+        .enabled
+        .then(|| control::socket_path(&config.control));
+    if listener.as_ref().map(|l| l.path()) == wanted.as_deref() {
+        return None;
+    }
+    if let Some(current) = listener.take() {
+        current.close();
+    }
+    match control::spawn_control_listener(&wanted?, state.clone(), tx.clone()) {
+        Ok(opened) => {
+            *listener = Some(opened);
+            None
+        }
+        Err(e) => Some(e),
+    }
+}
+
+/// Subcomando control: envia um comando ao socket de controle do agente
+/// Uso: `control <snapshot|status|config|last-error> [--socket <caminho>]`
+fn run_control(args: &[String]) -> Result<bool, String> {
+    let mut command = None;
+    let mut socket = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--socket" => {
+                socket = Some(PathBuf::from(iter.next().ok_or("--socket exige um valor")?))
+            }
+            other if command.is_none() => command = Some(other.to_string()),
+            other => return Err(format!("Argumento desconhecido: {}", other)),
+        }
+    }
+    let command = command.ok_or("Informe o comando: snapshot, status, config ou last-error")?;
+    let path = match socket {
+        Some(path) => path,
+        None => control::socket_path(&config::load_config()?.control),
+    };
+
+    let response = control::send_command(&path, &command)?;
+    let json = serde_json::to_string_pretty(&response).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(response["ok"] == Value::Bool(true))
+}
+
+/// =========================================================================
+/// FUNÇÃO PRINCIPAL E CICLO DE VIDA DO AGENTE
+/// =========================================================================
+
+pub fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // Processamento de argumentos: modos auxiliares que encerram em seguida
+    match args.get(1).map(String::as_str) {
+        Some("--reset") => {
+            reset_logs();
+            return;
+        }
+        Some("decode") => {
+            // Subcomando decode: converte o log compacto em linhas JSON
+            let path = args
+                .get(2)
+                .map(PathBuf::from)
+                .unwrap_or_else(get_compact_log_path);
+            if let Err(e) = decode_compact_log(path) {
+                eprintln!("Erro ao decodificar log: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("--once") => {
+            // Um único snapshot na saída padrão, sem gravar no log
+            let agent_id = load_agent_id();
+            let config = config::load_config().unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                AgentConfig::default()
+            });
+            let name_salt =
+                snapshot_agent::identity::load_or_create_name_salt().unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    snapshot_agent::identity::ephemeral_name_salt()
+                });
+            let mut collectors = SnapshotCollectors::new(&config, &name_salt);
+            let snapshot = coletar_snapshot(&agent_id, &get_hostname(), &mut collectors, None);
+            println!("{}", Record::Snapshot(snapshot).to_json());
+            return;
+        }
+        Some("verify") => match run_verify(&args[2..]) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Erro na verificação: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some("query") => {
+            if let Err(e) = run_query(&args[2..]) {
+                eprintln!("Erro na consulta: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("control") => match run_control(&args[2..]) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Erro no controle: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Some("update") => {
+            if let Err(e) = run_update(&args[2..]) {
+                eprintln!("Erro na atualização: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("simulate") => {
+            let result = simulate::parse_args(&args[2..]).and_then(|options| {
+                let records = simulate::run(&options)?;
+                if let Some(path) = &options.output {
+                    println!("{} registros simulados gravados em {:?}", records, path);
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                eprintln!("Erro na simulação: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some("--version") => {
+            // Usado também para testar um binário novo antes da troca
+            println!("snapshot_agent {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        _ => {}
+    }
+
+    // Inicialização: identificador persistente do agente (mantido entre reinícios)
+    let agent_id = load_agent_id();
+    let hostname = get_hostname();
+
+    // Carrega a configuração; em caso de erro usa os valores padrão
+    let mut config = config::load_config().unwrap_or_else(|e| {
+        log_agent_error(&agent_id, &hostname, e);
+        AgentConfig::default()
+    });
+    for e in configure_log(&config) {
+        log_agent_error(&agent_id, &hostname, e);
+    }
+
+    // Atualização pendente: a versão nova é confirmada no primeiro snapshot
+    let mut pending_update = match update::check_pending() {
+        update::StartupState::Normal => None,
+        update::StartupState::Pending(pending) => Some(pending),
+        update::StartupState::RolledBack(reason) => {
+            log_agent_error(
+                &agent_id,
+                &hostname,
+                format!("Atualização revertida: {}", reason),
+            );
+            match update::current_binary() {
+                Ok(binary) => {
+                    let e = update::reexec(&binary);
+                    log_agent_error(
+                        &agent_id,
+                        &hostname,
+                        format!("Erro ao executar a versão restaurada: {}", e),
+                    );
+                }
+                Err(e) => log_agent_error(&agent_id, &hostname, e),
+            }
+            None
+        }
+        update::StartupState::RollbackFailed(reason) => {
+            log_agent_error(
+                &agent_id,
+                &hostname,
+                format!("Atualização não revertida: {}", reason),
+            );
+            None
+        }
+    };
+
+    // Modo de autolimitação: a prioridade reduzida é herdada pelas threads
+    // criadas depois; ao recarregar é aplicada de novo a todas, ou a
+    // original é restaurada se o orçamento foi desativado
+    let original_priority = budget::current_priority();
+    if config.budget.enabled {
+        for e in budget::lower_priority(&config.budget, &original_priority) {
+            log_agent_error(&agent_id, &hostname, e);
+        }
+    }
+
+    // Sinais (SIGTERM/SIGINT/SIGHUP/SIGUSR1) e pedidos do socket de controle
+    // chegam ao loop principal por este canal
+    let (tx, rx) = mpsc::channel();
+    signals::spawn_signal_listener(tx.clone());
+
+    // Socket de controle: snapshot sob demanda, status, configuração e último erro
+    let control_state = Arc::new(Mutex::new(control::ControlState::new(
+        &agent_id,
+        &hostname,
+        &config,
+        get_active_log_path(),
+    )));
+    let mut control_socket = None;
+    if let Some(e) = configure_control(&mut control_socket, &config, &control_state, &tx) {
+        log_agent_error(&agent_id, &hostname, e);
+    }
+
+    // Registra início do serviço no log e avisa o systemd (Type=notify)
+    let reason = pending_update.as_ref().map(|_| "update");
+    log_service_status(AgentStatus::Started, &agent_id, reason);
+    log_inventory(&agent_id, &hostname);
+    systemd::notify_ready();
+    let watchdog = systemd::watchdog_interval();
+
+    // Loop principal: coleta snapshots periodicamente e atende sinais
+    let mut watcher = watch::Watcher::new();
+    let mut security = SecurityTrackers::new(&config);
+    let mut file_monitor = fim::FileMonitor::new();
+    let mut update_checker = update::UpdateChecker::new();
+    let mut budget = budget::Budget::new();
+    let mut port_tracker = ports::PortTracker::new();
+    let mut package_tracker = packages::PackageTracker::new();
+    let name_salt = load_name_salt(&agent_id, &hostname);
+    let mut collectors = SnapshotCollectors::new(&config, &name_salt);
+    let mut next_snapshot = Instant::now();
+    let mut snapshot_replies: Vec<control::SnapshotReply> = Vec::new();
+    loop {
+        if Instant::now() >= next_snapshot {
+            let overhead = budget.begin_cycle(&config);
+            let skip_fim = overhead.skipped.iter().any(|name| name == "fim");
+            let snapshot = match pending_update.take() {
+                Some(pending) => {
+                    confirmar_atualizacao(&pending, &agent_id, &hostname, &mut collectors, overhead)
+                }
+                None => Some(executar_snapshot(
+                    &agent_id,
+                    &hostname,
+                    &mut collectors,
+                    Some(overhead),
+                )),
+            };
+            if let Some(snapshot) = &snapshot {
+                let mut state = control_state.lock().unwrap_or_else(|e| e.into_inner());
+                state.snapshot_taken(snapshot);
+                drop(state);
+                if config.ports.enabled {
+                    verificar_portas(&mut port_tracker, snapshot, &agent_id, &hostname);
+                }
+            }
+            // Quem pediu o snapshot pelo socket recebe o resultado
+            for reply in snapshot_replies.drain(..) {
+                let _ = reply.send(
+                    snapshot
+                        .clone()
+                        .ok_or_else(|| "Falha na coleta".to_string()),
+                );
+            }
+            verificar_watchlist(&mut watcher, &agent_id, &hostname, &config);
+            verificar_seguranca(&mut security, &agent_id, &hostname, &config);
+            if !skip_fim {
+                verificar_integridade_arquivos(&mut file_monitor, &agent_id, &hostname, &config);
+            }
+            if config.packages.enabled {
+                verificar_pacotes(&mut package_tracker, &agent_id, &hostname, &config);
+            }
+            verificar_atualizacao(&mut update_checker, &agent_id, &hostname, &config);
+            budget.end_cycle();
+            next_snapshot = Instant::now() + config.interval();
+            systemd::notify_status("Coletando snapshots");
+        }
+        if watchdog.is_some() {
+            systemd::ping_watchdog();
+        }
+
+        // Aguarda o próximo snapshot, acordando antes se o watchdog exigir
+        let mut wait = next_snapshot.saturating_duration_since(Instant::now());
+        if let Some(ping) = watchdog {
+            wait = wait.min(ping);
+        }
+
+        match rx.recv_timeout(wait) {
+            Ok(AgentEvent::Snapshot(source, reply)) => {
+                // Coleta imediata; o intervalo volta a contar a partir dela
+                eprintln!("Snapshot solicitado ({})", source);
+                next_snapshot = Instant::now();
+                snapshot_replies.extend(reply);
+            }
+            Ok(AgentEvent::Terminate(signal)) => {
+                systemd::notify_stopping();
+                log_service_status(AgentStatus::Stopped, &agent_id, Some(signal));
+                break;
+            }
+            Ok(AgentEvent::Reload) => {
+                systemd::notify_reloading();
+                match config::load_config() {
+                    Ok(new_config) => {
+                        // Reagenda a próxima coleta conforme o novo intervalo
+                        if new_config.interval() != config.interval() {
+                            next_snapshot = Instant::now() + new_config.interval();
+                        }
+                        let was_limited = config.budget.enabled;
+                        config = new_config;
+                        collectors.reconfigure(&config, &name_salt);
+                        security.reconfigure(&config);
+                        let priority_errors = if config.budget.enabled {
+                            budget::lower_priority(&config.budget, &original_priority)
+                        } else if was_limited {
+                            budget::restore_priority(&original_priority)
+                        } else {
+                            Vec::new()
+                        };
+                        for e in priority_errors {
+                            log_agent_error(&agent_id, &hostname, e);
+                        }
+                        for e in configure_log(&config) {
+                            log_agent_error(&agent_id, &hostname, e);
+                        }
+                        {
+                            let mut state = control_state.lock().unwrap_or_else(|e| e.into_inner());
+                            state.config = config.clone();
+                            state.status.log_path = get_active_log_path();
+                        }
+                        if let Some(e) =
+                            configure_control(&mut control_socket, &config, &control_state, &tx)
+                        {
+                            log_agent_error(&agent_id, &hostname, e);
+                        }
+                        log_service_status(AgentStatus::Reloaded, &agent_id, Some("SIGHUP"));
+                    }
+                    Err(e) => log_agent_error(&agent_id, &hostname, e),
+                }
+                systemd::notify_ready();
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                log_service_status(
+                    AgentStatus::Stopped,
+                    &agent_id,
+                    Some("signal listener closed"),
+                );
+                break;
+            }
+        }
+    }
+
+    if let Some(listener) = control_socket {
+        listener.close();
+    }
+}
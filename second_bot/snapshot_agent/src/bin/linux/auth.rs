@@ -0,0 +1,248 @@
+use chrono::{SecondsFormat, Utc};
+use snapshot_schema::{AuthFailures, SCHEMA_VERSION};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// =========================================================================
+/// FALHAS DE AUTENTICAÇÃO (AUTH LOGS)
+/// =========================================================================
+/// Acompanha os arquivos de `security.auth_logs` como um tail e conta as
+/// falhas de login SSH e de senha do sudo encontradas desde o intervalo
+/// anterior. Arquivos inexistentes são ignorados; a rotação é detectada
+/// pela troca de inode ou pela redução do tamanho.
+
+/// Posição de leitura de um arquivo
+struct Cursor {
+    inode: u64,
+    offset: u64,
+}
+
+/// Acompanha os logs de autenticação entre intervalos
+pub struct AuthLogTracker {
+    cursors: HashMap<PathBuf, Cursor>,
+    reported: HashSet<PathBuf>, // Arquivos cujo erro de leitura já foi registrado
+}
+
+/// Contagem parcial de um intervalo
+#[derive(Default)]
+struct Counts {
+    ssh: u64,
+    sudo: u64,
+    by_user: BTreeMap<String, u64>,
+    by_source: BTreeMap<String, u64>,
+}
+
+impl AuthLogTracker {
+    /// Começa no fim dos arquivos existentes: linhas antigas não são contadas
+    pub fn new(paths: &[PathBuf]) -> Self {
+        let mut tracker = Self {
+            cursors: HashMap::new(),
+            reported: HashSet::new(),
+        };
+        for path in paths {
+            if let Ok(metadata) = fs::metadata(path) {
+                tracker.cursors.insert(
+                    path.clone(),
+                    Cursor {
+                        inode: metadata.ino(),
+                        offset: metadata.len(),
+                    },
+                );
+            }
+        }
+        tracker
+    }
+
+    /// Lê as linhas novas e retorna as falhas (se houver) e os erros
+    pub fn poll(
+        &mut self,
+        paths: &[PathBuf],
+        agent_id: &str,
+        hostname: &str,
+    ) -> (Option<AuthFailures>, Vec<String>) {
+        let mut counts = Counts::default();
+        let mut errors = Vec::new();
+
+        for path in paths {
+            match self.read_file(path, &mut counts) {
+                Ok(()) => {
+                    self.reported.remove(path);
+                }
+                Err(e) => {
+                    if self.reported.insert(path.clone()) {
+                        errors.push(e);
+                    }
+                }
+            }
+        }
+
+        if counts.ssh == 0 && counts.sudo == 0 {
+            return (None, errors);
+        }
+        let record = AuthFailures {
+            schema_version: SCHEMA_VERSION,
+            agent_id: agent_id.to_string(),
+            hostname: hostname.to_string(),
+            datetime: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            timestamp: Utc::now().timestamp().max(0) as u64,
+            ssh_failures: counts.ssh,
+            sudo_failures: counts.sudo,
+            by_user: counts.by_user,
+            by_source: counts.by_source,
+        };
+        (Some(record), errors)
+    }
+
+    /// Processa as linhas completas gravadas desde a última leitura
+    fn read_file(&mut self, path: &Path, counts: &mut Counts) -> Result<(), String> {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(format!("Erro ao ler {:?}: {}", path, e)),
+        };
+
+        // Arquivo novo ou rotacionado é lido desde o início
+        let cursor = self.cursors.entry(path.to_path_buf()).or_insert(Cursor {
+            inode: metadata.ino(),
+            offset: 0,
+        });
+        if cursor.inode != metadata.ino() || metadata.len() < cursor.offset {
+            cursor.inode = metadata.ino();
+            cursor.offset = 0;
+        }
+        if metadata.len() == cursor.offset {
+            return Ok(());
+        }
+
+        let mut file = File::open(path).map_err(|e| format!("Erro ao abrir {:?}: {}", path, e))?;
+        file.seek(SeekFrom::Start(cursor.offset))
+            .map_err(|e| format!("Erro ao ler {:?}: {}", path, e))?;
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(|e| format!("Erro ao ler {:?}: {}", path, e))?;
+            // Linha incompleta fica para o próximo intervalo
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            cursor.offset += read as u64;
+            count_line(&line, counts);
+        }
+        Ok(())
+    }
+}
+
+/// Classifica uma linha do log de autenticação
+///
+/// sshd: "Failed password for [invalid user] USER from ADDR port N ssh2"
+///       (também publickey, keyboard-interactive e none)
+/// sudo: "pam_unix(sudo:auth): authentication failure; logname=... user=USER"
+fn count_line(line: &str, counts: &mut Counts) {
+    if line.contains("sshd") {
+        let Some(rest) = line.split_once(": Failed ").map(|(_, rest)| rest) else {
+            return;
+        };
+        let Some((_, rest)) = rest.split_once(" for ") else {
+            return;
+        };
+        let rest = rest.strip_prefix("invalid user ").unwrap_or(rest);
+        let (user, source) = match rest.rsplit_once(" from ") {
+            Some((user, after)) => (user, after.split_whitespace().next().unwrap_or("")),
+            None => (rest.split_whitespace().next().unwrap_or(""), ""),
+        };
+        counts.ssh += 1;
+        *counts.by_user.entry(user.to_string()).or_insert(0) += 1;
+        if !source.is_empty() {
+            *counts.by_source.entry(source.to_string()).or_insert(0) += 1;
+        }
+    } else if line.contains("pam_unix(sudo:auth): authentication failure") {
+        // O usuário que tentou o sudo é o logname (user= é o alvo, em geral root)
+        let field = |name: &str| {
+            line.split_whitespace()
+                .find_map(|token| token.strip_prefix(name))
+                .filter(|value| !value.is_empty())
+        };
+        let user = field("logname=").or_else(|| field("ruser=")).unwrap_or("");
+        counts.sudo += 1;
+        *counts.by_user.entry(user.to_string()).or_insert(0) += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_auth_log() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/root/var/log/auth.log")
+    }
+
+    #[test]
+    fn counts_ssh_and_sudo_failures() {
+        let mut counts = Counts::default();
+        for line in fs::read_to_string(fixture_auth_log()).unwrap().lines() {
+            count_line(line, &mut counts);
+        }
+        assert_eq!((counts.ssh, counts.sudo), (3, 1));
+        // "invalid user" é descartado; o sudo conta o logname, não o alvo
+        assert_eq!(
+            counts.by_user,
+            BTreeMap::from([
+                ("admin".to_string(), 1),
+                ("alice".to_string(), 2),
+                ("bob".to_string(), 1),
+            ])
+        );
+        assert_eq!(
+            counts.by_source,
+            BTreeMap::from([("10.0.0.5".to_string(), 2), ("203.0.113.7".to_string(), 1)])
+        );
+    }
+
+    #[test]
+    fn ssh_failure_without_source() {
+        let mut counts = Counts::default();
+        count_line("sshd[9]: Failed none for carol", &mut counts);
+        assert_eq!(counts.ssh, 1);
+        assert_eq!(counts.by_user.get("carol"), Some(&1));
+        assert!(counts.by_source.is_empty());
+    }
+
+    #[test]
+    fn tail_counts_only_new_complete_lines() {
+        let dir = std::env::temp_dir().join(format!("auth-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("auth.log");
+        fs::write(
+            &log,
+            "sshd[1]: Failed password for antigo from 10.0.0.1 port 1 ssh2\n",
+        )
+        .unwrap();
+        let paths = vec![log.clone()];
+        let mut tracker = AuthLogTracker::new(&paths);
+
+        let mut content = fs::read_to_string(&log).unwrap();
+        content.push_str(&fs::read_to_string(fixture_auth_log()).unwrap());
+        content.push_str("sshd[2]: Failed password for parcial");
+        fs::write(&log, &content).unwrap();
+        let (failures, errors) = tracker.poll(&paths, "agente", "vm1");
+        let failures = failures.unwrap();
+        assert!(errors.is_empty());
+        assert_eq!((failures.ssh_failures, failures.sudo_failures), (3, 1));
+        assert!(!failures.by_user.contains_key("antigo"));
+
+        // A linha incompleta é contada quando termina
+        content.push_str(" from 10.0.0.2 port 2 ssh2\n");
+        fs::write(&log, &content).unwrap();
+        let (failures, _) = tracker.poll(&paths, "agente", "vm1");
+        assert_eq!(failures.unwrap().by_user.get("parcial"), Some(&1));
+        assert!(tracker.poll(&paths, "agente", "vm1").0.is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+use crate::config::{AgentConfig, BudgetConfig};
+use snapshot_schema::AgentOverhead;
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// =========================================================================
+/// ORÇAMENTO DE RECURSOS DO AGENTE
+/// =========================================================================
+/// Em VMs pequenas o custo da própria coleta aparece nas métricas. O agente
+/// mede o próprio consumo a cada ciclo e o grava em `Snapshot.overhead`:
+///   CPU - getrusage do processo e dos filhos (comandos dos coletores)
+///   RSS - VmRSS de /proc/self/status
+///
+/// Com `budget.enabled`, o agente também reduz a própria prioridade de CPU e
+/// de I/O (na inicialização e ao recarregar) e, quando o consumo passa do orçamento, omite os
+/// coletores caros até voltar a ter folga.
+///
+/// Desativar o orçamento (ou o `idle_io`) ao recarregar restaura a
+/// prioridade da inicialização. A classe de I/O sempre volta; o nice só volta
+/// com CAP_SYS_NICE ou RLIMIT_NICE, já que baixar o nice exige privilégio.
+/// Sem isso, o erro é registrado e é preciso reiniciar o agente (o mesmo vale
+/// para reduzir `budget.nice` sem desativar o orçamento).
+
+/// Coletores omitidos quando o orçamento é excedido: comandos externos,
+/// hash de arquivos (FIM) e a varredura de /proc/<pid>/stat
+const SKIPPABLE: [&str; 3] = ["custom", "fim", "processes"];
+
+/// Classe de I/O idle do ioprio_set (IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT)
+const IOPRIO_IDLE: libc::c_int = 3 << 13;
+
+/// IOPRIO_WHO_PROCESS
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+/// Prioridades do processo antes do modo de autolimitação
+pub struct Priority {
+    nice: libc::c_int,
+    ioprio: libc::c_int,
+}
+
+/// Prioridade de CPU e de I/O atuais (lidas antes de reduzi-las)
+pub fn current_priority() -> Priority {
+    // SAFETY: consultas sem ponteiros sobre o próprio processo; getpriority
+    // pode devolver -1 sem erro, então o errno é zerado antes
+    let nice = unsafe {
+        *libc::__errno_location() = 0;
+        libc::getpriority(libc::PRIO_PROCESS, 0)
+    };
+    let nice = if nice == -1 && io::Error::last_os_error().raw_os_error() != Some(0) {
+        0
+    } else {
+        nice
+    };
+    // SAFETY: ioprio_get não recebe ponteiros
+    let ioprio = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, 0) };
+    Priority {
+        nice,
+        ioprio: ioprio.max(0) as libc::c_int,
+    }
+}
+
+/// Medição do consumo entre ciclos e estado do modo reduzido
+pub struct Budget {
+    cycle_start: Instant,   // Início do ciclo atual
+    cycle_start_cpu: u64,   // CPU acumulada no início do ciclo (µs)
+    last_cycle_cpu_us: u64, // CPU gasta no trabalho do ciclo anterior
+    measured: bool,         // Já houve um ciclo completo para comparar
+    throttled: bool,
+}
+
+impl Budget {
+    /// O primeiro ciclo mede desde o início do processo; a inicialização não
+    /// conta para o orçamento
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            cycle_start: now.checked_sub(process_age()).unwrap_or(now),
+            cycle_start_cpu: 0,
+            last_cycle_cpu_us: 0,
+            measured: false,
+            throttled: false,
+        }
+    }
+
+    /// Início de um ciclo: mede o consumo desde o início do anterior e decide
+    /// se este ciclo roda com coleta reduzida
+    ///
+    /// Com o orçamento estourado (CPU ou RSS), os coletores caros ficam de
+    /// fora até a CPU cair para a metade do orçamento, evitando alternar a
+    /// cada ciclo quando o consumo fica perto do limite
+    pub fn begin_cycle(&mut self, config: &AgentConfig) -> AgentOverhead {
+        let now = Instant::now();
+        let cpu = cpu_time_us();
+        let wall_us = now.duration_since(self.cycle_start).as_micros() as f64;
+        let cpu_percent = if wall_us > 0.0 {
+            (cpu.saturating_sub(self.cycle_start_cpu) as f64 / wall_us * 100.0) as f32
+        } else {
+            0.0
+        };
+        let rss_bytes = rss_bytes();
+        self.cycle_start = now;
+        self.cycle_start_cpu = cpu;
+
+        let budget = &config.budget;
+        let measured = std::mem::replace(&mut self.measured, true);
+        if !budget.enabled || !measured {
+            self.throttled = false;
+        } else if cpu_percent > budget.max_cpu_percent || rss_bytes > budget.max_rss_bytes {
+            self.throttled = true;
+        } else if cpu_percent <= budget.max_cpu_percent / 2.0 {
+            self.throttled = false;
+        }
+
+        let skipped = if self.throttled {
+            SKIPPABLE
+                .iter()
+                .filter(|name| match **name {
+                    "custom" => !config.custom_collectors.is_empty(),
+                    "fim" => !config.file_integrity.paths.is_empty(),
+                    _ => true,
+                })
+                .map(|name| name.to_string())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        AgentOverhead {
+            cpu_percent,
+            cycle_cpu_ms: self.last_cycle_cpu_us / 1000,
+            rss_bytes,
+            throttled: self.throttled,
+            skipped,
+        }
+    }
+
+    /// Fim do trabalho do ciclo (snapshot e verificações)
+    pub fn end_cycle(&mut self) {
+        self.last_cycle_cpu_us = cpu_time_us().saturating_sub(self.cycle_start_cpu);
+    }
+}
+
+/// Reduz a prioridade de CPU (nice) e, se configurado, de I/O (classe idle);
+/// sem `idle_io`, a classe de I/O volta à original
+/// No Linux as duas são por thread: a inicialização e o SIGHUP aplicam a
+/// todas as threads do processo, e as criadas depois as herdam
+pub fn lower_priority(config: &BudgetConfig, original: &Priority) -> Vec<String> {
+    let ioprio = if config.idle_io {
+        IOPRIO_IDLE
+    } else {
+        original.ioprio
+    };
+    let (nice_error, io_error) = set_priority(config.nice, ioprio);
+    let mut errors = Vec::new();
+    if let Some(e) = nice_error {
+        errors.push(format!("Erro ao aplicar nice {}: {}", config.nice, e));
+    }
+    if let Some(e) = io_error {
+        errors.push(format!("Erro ao aplicar a classe de I/O: {}", e));
+    }
+    errors
+}
+
+/// Volta às prioridades da inicialização (orçamento desativado ao recarregar)
+pub fn restore_priority(original: &Priority) -> Vec<String> {
+    let (nice_error, io_error) = set_priority(original.nice, original.ioprio);
+    let mut errors = Vec::new();
+    if let Some(e) = nice_error {
+        errors.push(format!(
+            "Erro ao restaurar nice {} ({}); reinicie o agente para voltar à prioridade original",
+            original.nice, e
+        ));
+    }
+    if let Some(e) = io_error {
+        errors.push(format!("Erro ao restaurar a classe de I/O: {}", e));
+    }
+    errors
+}
+
+/// Aplica nice e ioprio a todas as threads; devolve o primeiro erro de cada
+fn set_priority(nice: libc::c_int, ioprio: libc::c_int) -> (Option<io::Error>, Option<io::Error>) {
+    let mut nice_error = None;
+    let mut io_error = None;
+    for tid in thread_ids() {
+        // SAFETY: chamadas sem ponteiros, apenas sobre threads do próprio processo
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, nice) } != 0 {
+            nice_error.get_or_insert_with(io::Error::last_os_error);
+        }
+        let result =
+            unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, ioprio) };
+        if result != 0 {
+            io_error.get_or_insert_with(io::Error::last_os_error);
+        }
+    }
+    (nice_error, io_error)
+}
+
+/// Threads do processo (/proc/self/task); só a atual se não for possível listar
+fn thread_ids() -> Vec<libc::id_t> {
+    let tids: Vec<libc::id_t> = fs::read_dir("/proc/self/task")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    if tids.is_empty() {
+        vec![0]
+    } else {
+        tids
+    }
+}
+
+/// CPU (usuário + sistema) do processo e dos filhos já encerrados, em µs
+fn cpu_time_us() -> u64 {
+    let usage = |who: libc::c_int| {
+        // SAFETY: getrusage preenche a estrutura zerada passada por referência
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::getrusage(who, &mut usage) } != 0 {
+            return 0;
+        }
+        let micros = |tv: libc::timeval| tv.tv_sec as u64 * 1_000_000 + tv.tv_usec as u64;
+        micros(usage.ru_utime) + micros(usage.ru_stime)
+    };
+    usage(libc::RUSAGE_SELF) + usage(libc::RUSAGE_CHILDREN)
+}
+
+/// Tempo desde o início do processo (starttime de /proc/self/stat)
+fn process_age() -> Duration {
+    // SAFETY: sysconf apenas consulta um valor de configuração
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    let ticks = if ticks > 0 { ticks as f64 } else { 100.0 };
+    let uptime = fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|content| content.split_whitespace().next()?.parse::<f64>().ok());
+    // Campos após o nome do processo: starttime é o vigésimo
+    let started = fs::read_to_string("/proc/self/stat").ok().and_then(|stat| {
+        let (_, rest) = stat.rsplit_once(')')?;
+        rest.split_whitespace().nth(19)?.parse::<f64>().ok()
+    });
+    match (uptime, started) {
+        (Some(uptime), Some(started)) => {
+            Duration::from_secs_f64((uptime - started / ticks).max(0.0))
+        }
+        _ => Duration::ZERO,
+    }
+}
+
+/// Memória residente atual do processo (bytes)
+fn rss_bytes() -> u64 {
+    fs::read_to_string("/proc/self/status")
+        .unwrap_or_default()
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+        .unwrap_or(0)
+        * 1024
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use snapshot_agent::collectors::CustomCollectorConfig;
+
+    fn budget_config(max_cpu_percent: f32, max_rss_bytes: u64) -> AgentConfig {
+        AgentConfig {
+            budget: BudgetConfig {
+                enabled: true,
+                max_cpu_percent,
+                max_rss_bytes,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn over_budget_skips_only_configured_collectors() {
+        // RSS acima de 1 byte: sempre fora do orçamento
+        let mut config = budget_config(f32::MAX, 1);
+        let mut budget = Budget::new();
+
+        // O primeiro ciclo só mede
+        let overhead = budget.begin_cycle(&config);
+        assert!(!overhead.throttled);
+        assert!(overhead.skipped.is_empty());
+
+        // Sem coletores personalizados nem FIM, só a varredura de processos
+        let overhead = budget.begin_cycle(&config);
+        assert!(overhead.throttled);
+        assert_eq!(overhead.skipped, vec!["processes"]);
+
+        config.custom_collectors = vec![CustomCollectorConfig {
+            name: "fila".to_string(),
+            command: "true".to_string(),
+            timeout_secs: 1,
+            parse_json: false,
+        }];
+        config.file_integrity.paths = vec!["/etc/passwd".to_string()];
+        let overhead = budget.begin_cycle(&config);
+        assert_eq!(overhead.skipped, SKIPPABLE.to_vec());
+    }
+
+    #[test]
+    fn throttling_ends_with_room_or_when_disabled() {
+        let mut budget = Budget::new();
+        budget.begin_cycle(&budget_config(f32::MAX, 1));
+        assert!(budget.begin_cycle(&budget_config(f32::MAX, 1)).throttled);
+
+        // RSS dentro do orçamento e CPU abaixo da metade: volta ao normal
+        assert!(
+            !budget
+                .begin_cycle(&budget_config(f32::MAX, u64::MAX))
+                .throttled
+        );
+
+        assert!(budget.begin_cycle(&budget_config(f32::MAX, 1)).throttled);
+        let mut disabled = budget_config(f32::MAX, 1);
+        disabled.budget.enabled = false;
+        let overhead = budget.begin_cycle(&disabled);
+        assert!(!overhead.throttled);
+        assert!(overhead.skipped.is_empty());
+    }
+
+    #[test]
+    fn restoring_the_current_priority_needs_no_privilege() {
+        assert!(restore_priority(&current_priority()).is_empty());
+    }
+}
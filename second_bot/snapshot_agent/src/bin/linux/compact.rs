@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// =========================================================================
+/// FORMATO COMPACTO DE LOG (snapshot.bin)
+/// =========================================================================
+/// Layout do arquivo:
+///
+///   cabeçalho: MAGIC (8 bytes) + versão do formato (u16 LE)
+///   frames:    tipo (u8) + tamanho do payload (u32 LE) + payload CBOR
+///
+/// Frames FULL carregam o registro completo. Frames DELTA carregam apenas
+/// os campos que mudaram em relação ao registro anterior do arquivo, o que
+/// elimina a repetição de `folder_files` e dos campos de identificação.
+/// Cada execução do agente começa com um frame FULL, então o arquivo pode
+/// receber registros de várias execuções sem perder a consistência.
+
+/// Identifica o arquivo como log compacto do snapshot_agent
+pub const MAGIC: &[u8; 8] = b"SNAPLOG\0";
+
+/// Versão do layout de frames gravada no cabeçalho
+pub const FORMAT_VERSION: u16 = 1;
+
+const FRAME_FULL: u8 = 0;
+const FRAME_DELTA: u8 = 1;
+
+/// Limite de tamanho de um frame, protege contra arquivos corrompidos
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// Diferença entre um registro e o anterior
+#[derive(Serialize, Deserialize)]
+struct Delta {
+    set: Map<String, Value>, // Campos novos ou alterados
+    unset: Vec<String>,      // Campos que deixaram de existir
+}
+
+/// Codifica registros JSON em frames, lembrando o último registro gravado
+pub struct CompactEncoder {
+    previous: Option<Map<String, Value>>,
+}
+
+impl CompactEncoder {
+    /// Cria um codificador sem registro anterior (próximo frame será FULL)
+    pub const fn new() -> Self {
+        Self { previous: None }
+    }
+
+    /// Codifica um registro, escolhendo entre frame FULL e DELTA
+    /// O DELTA só é usado quando resulta em um payload menor
+    pub fn encode(&mut self, record: &Value) -> Vec<u8> {
+        let full = to_cbor(record);
+
+        let frame = match (&self.previous, record.as_object()) {
+            (Some(previous), Some(current)) => {
+                let delta = Delta {
+                    set: current
+                        .iter()
+                        .filter(|(key, value)| previous.get(*key) != Some(*value))
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect(),
+                    unset: previous
+                        .keys()
+                        .filter(|key| !current.contains_key(*key))
+                        .cloned()
+                        .collect(),
+                };
+                let delta = to_cbor(&delta);
+                if delta.len() < full.len() {
+                    frame(FRAME_DELTA, &delta)
+                } else {
+                    frame(FRAME_FULL, &full)
+                }
+            }
+            _ => frame(FRAME_FULL, &full),
+        };
+
+        self.previous = record.as_object().cloned();
+        frame
+    }
+}
+
+/// Serializa um valor em CBOR
+fn to_cbor<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    ciborium::into_writer(value, &mut buf).expect("Erro ao serializar CBOR");
+    buf
+}
+
+/// Monta um frame: tipo + tamanho + payload
+fn frame(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(payload.len() + 5);
+    buf.push(kind);
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    buf
+}
+
+/// Retorna o cabeçalho gravado no início de todo arquivo compacto
+pub fn header() -> Vec<u8> {
+    let mut buf = MAGIC.to_vec();
+    buf.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    buf
+}
+
+/// Acrescenta um registro ao arquivo compacto
+/// O cabeçalho é gravado quando o arquivo ainda está vazio; nesse caso o
+/// codificador recomeça, pois o arquivo pode ter sido esvaziado ou recriado
+/// e o próximo frame precisa ser FULL
+pub fn append_record(path: &Path, encoder: &mut CompactEncoder, json: &str) -> io::Result<()> {
+    let record: Value =
+        serde_json::from_str(json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        *encoder = CompactEncoder::new();
+        file.write_all(&header())?;
+    }
+    file.write_all(&encoder.encode(&record))
+}
+
+/// Lê registros de um arquivo compacto, reconstruindo os deltas
+pub struct CompactDecoder<R: Read> {
+    reader: R,
+    previous: Option<Map<String, Value>>,
+}
+
+impl<R: Read> CompactDecoder<R> {
+    /// Valida o cabeçalho e prepara a leitura dos frames
+    pub fn new(mut reader: R) -> Result<Self, String> {
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|e| format!("Cabeçalho ausente: {}", e))?;
+        if &magic != MAGIC {
+            return Err("Arquivo não está no formato compacto do snapshot_agent".to_string());
+        }
+
+        let mut version = [0u8; 2];
+        reader
+            .read_exact(&mut version)
+            .map_err(|e| format!("Versão ausente no cabeçalho: {}", e))?;
+        let version = u16::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(format!(
+                "Versão do formato não suportada: {} (esperada {})",
+                version, FORMAT_VERSION
+            ));
+        }
+
+        Ok(Self {
+            reader,
+            previous: None,
+        })
+    }
+
+    /// Lê o próximo frame; `Ok(None)` indica fim do arquivo
+    fn read_frame(&mut self) -> Result<Option<(u8, Vec<u8>)>, String> {
+        let mut kind = [0u8; 1];
+        match self.reader.read_exact(&mut kind) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(format!("Erro ao ler frame: {}", e)),
+        }
+
+        let mut len = [0u8; 4];
+        self.reader
+            .read_exact(&mut len)
+            .map_err(|e| format!("Frame truncado: {}", e))?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(format!("Frame com tamanho inválido: {} bytes", len));
+        }
+        let mut payload = vec![0u8; len];
+        self.reader
+            .read_exact(&mut payload)
+            .map_err(|e| format!("Frame truncado: {}", e))?;
+
+        Ok(Some((kind[0], payload)))
+    }
+}
+
+impl<R: Read> Iterator for CompactDecoder<R> {
+    type Item = Result<Value, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (kind, payload) = match self.read_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+
+        let record = match kind {
+            FRAME_FULL => ciborium::from_reader::<Value, _>(payload.as_slice())
+                .map_err(|e| format!("Frame FULL inválido: {}", e)),
+            FRAME_DELTA => {
+                let delta = match ciborium::from_reader::<Delta, _>(payload.as_slice()) {
+                    Ok(delta) => delta,
+                    Err(e) => return Some(Err(format!("Frame DELTA inválido: {}", e))),
+                };
+                match &self.previous {
+                    Some(previous) => {
+                        let mut record = previous.clone();
+                        for key in &delta.unset {
+                            record.remove(key);
+                        }
+                        record.extend(delta.set);
+                        Ok(Value::Object(record))
+                    }
+                    None => Err("Frame DELTA sem registro anterior".to_string()),
+                }
+            }
+            other => Err(format!("Tipo de frame desconhecido: {}", other)),
+        };
+
+        if let Ok(value) = &record {
+            self.previous = value.as_object().cloned();
+        }
+        Some(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs::File;
+
+    fn decode(path: &Path) -> Vec<Value> {
+        CompactDecoder::new(File::open(path).unwrap())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn truncated_file_restarts_with_full_frame() {
+        let path = std::env::temp_dir().join(format!("compact-test-{}.bin", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut encoder = CompactEncoder::new();
+        let first = json!({"type": "snapshot", "hostname": "vm1", "cpu": 10});
+        let second = json!({"type": "snapshot", "hostname": "vm1", "cpu": 20});
+
+        append_record(&path, &mut encoder, &first.to_string()).unwrap();
+        append_record(&path, &mut encoder, &second.to_string()).unwrap();
+        assert_eq!(decode(&path), vec![first.clone(), second.clone()]);
+
+        // Log esvaziado (--reset ou limpeza remota) com o agente em execução
+        File::create(&path).unwrap();
+        append_record(&path, &mut encoder, &first.to_string()).unwrap();
+        append_record(&path, &mut encoder, &second.to_string()).unwrap();
+        assert_eq!(decode(&path), vec![first, second]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,433 @@
+use dirs_next::home_dir;
+use serde::{Deserialize, Serialize};
+use snapshot_agent::collectors::{CustomCollectorConfig, FolderFilter};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// =========================================================================
+/// CONFIGURAÇÃO DO AGENTE
+/// =========================================================================
+/// Configuração lida de ~/.snapshot_agent/config.json. Todos os campos são
+/// opcionais: campos ausentes (ou o arquivo inteiro) assumem os padrões.
+/// O arquivo é relido quando o agente recebe SIGHUP.
+
+/// Coletores do snapshot aceitos em `collector_intervals`
+pub const COLLECTOR_NAMES: [&str; 10] = [
+    "memory",
+    "cpu",
+    "disk",
+    "folder",
+    "health",
+    "processes",
+    "ports",
+    "sessions",
+    "cgroup",
+    "custom",
+];
+
+/// Formato de gravação do log local
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Json, // Uma linha JSON por registro (snapshot.log)
+    Compact, // Frames CBOR com delta entre registros (snapshot.bin)
+}
+
+/// Pasta cujas entradas vão para o snapshot, com os filtros de privacidade
+/// A primeira da lista preenche `folder_files`; as demais, `folders`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FolderConfig {
+    pub path: String, // Aceita ~ e ~/
+    #[serde(flatten)]
+    pub filter: FolderFilter, // include, exclude, hidden, names, max_entries
+}
+
+impl FolderConfig {
+    pub fn path(&self) -> PathBuf {
+        expand_home(&self.path)
+    }
+}
+
+/// Item da lista de processos que devem estar rodando
+/// Exatamente um de `process`, `cmdline` ou `unit` deve ser informado
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WatchConfig {
+    pub name: String, // Identificador usado nos eventos
+    #[serde(default)]
+    pub process: Option<String>, // Nome do processo (/proc/<pid>/comm)
+    #[serde(default)]
+    pub cmdline: Option<String>, // Trecho da linha de comando
+    #[serde(default)]
+    pub unit: Option<String>, // Unidade systemd
+    #[serde(default)]
+    pub user_unit: bool, // Unidade do systemd do usuário (systemctl --user)
+    #[serde(default)]
+    pub restart_command: Option<String>, // Executado quando o item cai
+    #[serde(default = "default_restart_timeout")]
+    pub restart_timeout_secs: u64,
+}
+
+fn default_restart_timeout() -> u64 {
+    30
+}
+
+/// Sessões de login e falhas de autenticação (desativadas por padrão)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SecurityConfig {
+    pub sessions: bool,          // Sessões ativas no snapshot e eventos de login
+    pub auth_failures: bool,     // Acompanha os logs de autenticação
+    pub utmp_file: PathBuf,      // Sessões ativas
+    pub wtmp_file: PathBuf,      // Histórico de logins/logouts
+    pub auth_logs: Vec<PathBuf>, // Logs de autenticação acompanhados (tail)
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            sessions: false,
+            auth_failures: false,
+            utmp_file: PathBuf::from("/var/run/utmp"),
+            wtmp_file: PathBuf::from("/var/log/wtmp"),
+            auth_logs: vec![
+                PathBuf::from("/var/log/auth.log"), // Debian/Ubuntu
+                PathBuf::from("/var/log/secure"),   // RHEL/Fedora
+            ],
+        }
+    }
+}
+
+/// Inventário de portas em escuta (PORT_OPENED/PORT_CLOSED), desativado
+/// por padrão
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PortsConfig {
+    pub enabled: bool, // Portas no snapshot e eventos de abertura/fechamento
+}
+
+/// Inventário de pacotes e unidades systemd (PACKAGE_*/UNIT_CHANGED),
+/// desativado por padrão
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PackagesConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,      // Intervalo entre inventórios comparados
+    pub full_interval_secs: u64, // Intervalo entre listas completas gravadas no log
+    pub dpkg_status: PathBuf,    // Banco do dpkg; sem ele o agente tenta o rpm
+}
+
+impl Default for PackagesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 3600,
+            full_interval_secs: 86400,
+            dpkg_status: PathBuf::from("/var/lib/dpkg/status"),
+        }
+    }
+}
+
+/// Monitoramento de integridade de arquivos (FIM)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FileIntegrityConfig {
+    pub paths: Vec<String>,  // Arquivos ou diretórios (recursivos); aceita ~/
+    pub max_file_bytes: u64, // Acima disso compara tamanho e mtime em vez do hash
+}
+
+impl Default for FileIntegrityConfig {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            max_file_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+/// Destino dos registros (`type` define a saída)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkConfig {
+    File,   // Log local (snapshot.log ou snapshot.bin, conforme log_format)
+    Stdout, // Uma linha JSON por registro na saída padrão
+    Syslog {
+        #[serde(default = "default_syslog_socket")]
+        socket: PathBuf, // Socket do syslog local
+        #[serde(default = "default_syslog_facility")]
+        facility: String,
+        #[serde(default = "default_app_name")]
+        app_name: String,
+    },
+    Journald {
+        #[serde(default = "default_journald_socket")]
+        socket: PathBuf,
+    },
+    Statsd {
+        address: String, // host:porta
+        #[serde(default = "default_app_name")]
+        prefix: String, // Prefixo das métricas
+    },
+}
+
+fn default_syslog_socket() -> PathBuf {
+    PathBuf::from("/dev/log")
+}
+
+fn default_syslog_facility() -> String {
+    "daemon".to_string()
+}
+
+fn default_app_name() -> String {
+    "snapshot_agent".to_string()
+}
+
+fn default_journald_socket() -> PathBuf {
+    PathBuf::from("/run/systemd/journal/socket")
+}
+
+/// Atualização automática do binário do agente
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct UpdateConfig {
+    pub source: Option<String>, // Diretório local ou URL base com o binário publicado
+    pub public_key: Option<String>, // Chave pública minisign; exige a assinatura
+    pub check_interval_secs: u64, // Verificação periódica (0 = apenas o comando update)
+    pub restart_command: String, // Reinicia o serviço após a troca do binário
+    pub confirm_timeout_secs: u64, // Espera do comando update pelo primeiro snapshot
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            public_key: None,
+            check_interval_secs: 0,
+            restart_command: "systemctl --user restart snapshot-agent.service".to_string(),
+            confirm_timeout_secs: 120,
+        }
+    }
+}
+
+/// Orçamento de recursos do próprio agente (modo de autolimitação)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BudgetConfig {
+    pub enabled: bool,        // Reduz prioridade e coletas quando o orçamento estoura
+    pub nice: i32,            // Prioridade de CPU aplicada na inicialização (0-19)
+    pub idle_io: bool,        // Classe de I/O idle (só usa o disco ocioso)
+    pub max_cpu_percent: f32, // CPU média do agente entre snapshots (% de um núcleo)
+    pub max_rss_bytes: u64,   // Memória residente do agente
+}
+
+impl Default for BudgetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            nice: 10,
+            idle_io: true,
+            max_cpu_percent: 2.0,
+            max_rss_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// Socket de controle local (snapshot sob demanda, status e configuração),
+/// desativado por padrão; o SIGUSR1 funciona sem ele
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ControlConfig {
+    pub enabled: bool,
+    pub socket: Option<PathBuf>, // Padrão: ~/.snapshot_agent/control.sock
+}
+
+/// Encadeamento de hashes do log (evidência de adulteração)
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct IntegrityConfig {
+    pub hash_chain: bool,               // Grava prev_hash em cada registro
+    pub hmac_key_file: Option<PathBuf>, // Chave do HMAC (padrão: a do deploy)
+}
+
+impl IntegrityConfig {
+    /// Chave do HMAC: a configurada ou, com a cadeia ativa, a copiada pelo
+    /// deploy do central_monitor (`hmac_key` no inventário)
+    pub fn key_file(&self) -> Option<PathBuf> {
+        if let Some(path) = &self.hmac_key_file {
+            return Some(path.clone());
+        }
+        let provisioned = expand_home(PROVISIONED_KEY);
+        (self.hash_chain && provisioned.is_file()).then_some(provisioned)
+    }
+}
+
+/// Onde o deploy do central_monitor grava a chave do HMAC
+const PROVISIONED_KEY: &str = "~/.snapshot_agent/log.key";
+
+/// Parâmetros ajustáveis do agente
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AgentConfig {
+    pub interval_secs: u64,         // Intervalo entre snapshots (segundos)
+    pub log_format: LogFormat,      // Formato do log local
+    pub folders: Vec<FolderConfig>, // Pastas listadas no snapshot
+    pub custom_collectors: Vec<CustomCollectorConfig>, // Comandos do usuário
+    pub collector_intervals: BTreeMap<String, u64>, // Intervalo próprio por coletor (segundos)
+    pub integrity: IntegrityConfig, // Encadeamento de hashes do log
+    pub watchlist: Vec<WatchConfig>, // Processos que devem estar rodando
+    pub security: SecurityConfig,   // Sessões e falhas de autenticação
+    pub file_integrity: FileIntegrityConfig, // Arquivos monitorados (FIM)
+    pub ports: PortsConfig,         // Portas em escuta
+    pub packages: PackagesConfig,   // Pacotes instalados e unidades systemd
+    pub sinks: Vec<SinkConfig>,     // Destinos dos registros
+    pub update: UpdateConfig,       // Atualização automática do binário
+    pub budget: BudgetConfig,       // Limite de consumo do próprio agente
+    pub control: ControlConfig,     // Socket de controle local
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 30,
+            log_format: LogFormat::Json,
+            folders: vec![FolderConfig {
+                path: "~".to_string(),
+                filter: FolderFilter::default(),
+            }],
+            custom_collectors: Vec::new(),
+            collector_intervals: BTreeMap::new(),
+            integrity: IntegrityConfig::default(),
+            watchlist: Vec::new(),
+            security: SecurityConfig::default(),
+            file_integrity: FileIntegrityConfig::default(),
+            ports: PortsConfig::default(),
+            packages: PackagesConfig::default(),
+            sinks: vec![SinkConfig::File],
+            update: UpdateConfig::default(),
+            budget: BudgetConfig::default(),
+            control: ControlConfig::default(),
+        }
+    }
+}
+
+impl AgentConfig {
+    /// Intervalo entre snapshots, nunca inferior a 1 segundo
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+
+    /// Intervalo de um coletor; sem valor configurado, roda em todo snapshot
+    pub fn collector_interval(&self, name: &str) -> Duration {
+        Duration::from_secs(self.collector_intervals.get(name).copied().unwrap_or(0))
+    }
+}
+
+/// Expande `~` e `~/` para a home do usuário
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = if path == "~" {
+        Some("")
+    } else {
+        path.strip_prefix("~/")
+    };
+    match (rest, home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Retorna o caminho do arquivo de configuração
+/// Localizado em ~/.snapshot_agent/config.json
+pub fn get_config_path() -> PathBuf {
+    let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".snapshot_agent");
+    path.push("config.json");
+    path
+}
+
+/// Carrega a configuração do disco
+/// Arquivo inexistente equivale à configuração padrão; arquivo inválido
+/// retorna erro para que o chamador decida qual configuração manter
+pub fn load_config() -> Result<AgentConfig, String> {
+    let path = get_config_path();
+    match fs::read_to_string(&path) {
+        Ok(content) => {
+            let config: AgentConfig = serde_json::from_str(&content)
+                .map_err(|e| format!("Configuração inválida em {:?}: {}", path, e))?;
+            validate(&config).map_err(|e| format!("Configuração inválida em {:?}: {}", path, e))?;
+            Ok(config)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(AgentConfig::default()),
+        Err(e) => Err(format!("Erro ao ler configuração {:?}: {}", path, e)),
+    }
+}
+
+/// Verifica regras que o serde não expressa (nomes únicos, um alvo por item)
+fn validate(config: &AgentConfig) -> Result<(), String> {
+    let mut names = std::collections::HashSet::new();
+    for collector in &config.custom_collectors {
+        if collector.name.trim().is_empty() {
+            return Err("coletor personalizado sem nome".to_string());
+        }
+        if !names.insert(collector.name.as_str()) {
+            return Err(format!("coletor duplicado: {}", collector.name));
+        }
+    }
+
+    let mut paths = std::collections::HashSet::new();
+    for folder in &config.folders {
+        if folder.path.trim().is_empty() {
+            return Err("pasta monitorada sem path".to_string());
+        }
+        if !paths.insert(folder.path()) {
+            return Err(format!("pasta duplicada em folders: {}", folder.path));
+        }
+    }
+
+    for name in config.collector_intervals.keys() {
+        if !COLLECTOR_NAMES.contains(&name.as_str()) {
+            return Err(format!(
+                "coletor desconhecido em collector_intervals: {} (use {})",
+                name,
+                COLLECTOR_NAMES.join(", ")
+            ));
+        }
+    }
+
+    let mut names = std::collections::HashSet::new();
+    for watch in &config.watchlist {
+        if watch.name.trim().is_empty() {
+            return Err("item da watchlist sem nome".to_string());
+        }
+        if !names.insert(watch.name.as_str()) {
+            return Err(format!("item duplicado na watchlist: {}", watch.name));
+        }
+        let targets = [&watch.process, &watch.cmdline, &watch.unit]
+            .iter()
+            .filter(|target| target.is_some())
+            .count();
+        if targets != 1 {
+            return Err(format!(
+                "item {} da watchlist deve ter exatamente um de process, cmdline ou unit",
+                watch.name
+            ));
+        }
+    }
+
+    if !(0..=19).contains(&config.budget.nice) {
+        return Err(format!(
+            "budget.nice fora do intervalo 0-19: {}",
+            config.budget.nice
+        ));
+    }
+
+    let mut sinks = Vec::new();
+    for sink in &config.sinks {
+        if sinks.contains(&sink) {
+            return Err(format!("saída duplicada em sinks: {:?}", sink));
+        }
+        sinks.push(sink);
+    }
+    Ok(())
+}
@@ -0,0 +1,339 @@
+use crate::config::{AgentConfig, ControlConfig};
+use crate::signals::AgentEvent;
+use chrono::{SecondsFormat, Utc};
+use dirs_next::home_dir;
+use serde::Serialize;
+use serde_json::{json, Value};
+use snapshot_schema::{AgentOverhead, LogError, Record, Snapshot};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::spawn;
+use std::time::{Duration, Instant};
+
+/// =========================================================================
+/// SOCKET DE CONTROLE
+/// =========================================================================
+/// Socket Unix local (permissão 0600) que aceita um comando por conexão, em
+/// uma linha, e responde com uma linha JSON (`{"ok": true, ...}` ou
+/// `{"ok": false, "error": "..."}`):
+///   snapshot   - coleta um snapshot imediatamente e o devolve
+///   status     - PID, versão, snapshots coletados e consumo do agente
+///   config     - configuração em uso (incluindo os valores padrão)
+///   last-error - último registro ERROR gravado pelo agente
+/// O subcomando `control <comando>` do próprio binário é o cliente.
+
+/// Espera pelo snapshot solicitado (o loop pode estar no meio de uma coleta)
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Tempo para o cliente enviar o comando
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Último ERROR gravado pelo agente (atualizado a cada registro de erro)
+static LAST_ERROR: Mutex<Option<LogError>> = Mutex::new(None);
+
+/// Resposta do loop principal a um snapshot solicitado
+pub type SnapshotReply = Sender<Result<Snapshot, String>>;
+
+/// Estado do agente consultado pelo socket, atualizado pelo loop principal
+pub struct ControlState {
+    pub status: AgentState,
+    pub config: AgentConfig,
+    started: Instant,
+}
+
+/// Resposta do comando status
+#[derive(Serialize, Clone)]
+pub struct AgentState {
+    pub pid: u32,
+    pub version: &'static str,
+    pub agent_id: String,
+    pub hostname: String,
+    pub started_at: String,
+    pub uptime_secs: u64,
+    pub snapshots: u64,                  // Coletados desde a inicialização
+    pub last_snapshot: Option<String>,   // Data/hora do último snapshot
+    pub log_path: PathBuf,               // Log ativo (formato configurado)
+    pub overhead: Option<AgentOverhead>, // Consumo medido no último ciclo
+}
+
+impl ControlState {
+    pub fn new(agent_id: &str, hostname: &str, config: &AgentConfig, log_path: PathBuf) -> Self {
+        Self {
+            status: AgentState {
+                pid: std::process::id(),
+                version: env!("CARGO_PKG_VERSION"),
+                agent_id: agent_id.to_string(),
+                hostname: hostname.to_string(),
+                started_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                uptime_secs: 0,
+                snapshots: 0,
+                last_snapshot: None,
+                log_path,
+                overhead: None,
+            },
+            config: config.clone(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Registra um snapshot concluído
+    pub fn snapshot_taken(&mut self, snapshot: &Snapshot) {
+        self.status.snapshots += 1;
+        self.status.last_snapshot = Some(snapshot.datetime.clone());
+        self.status.overhead = snapshot.overhead.clone();
+    }
+}
+
+/// Guarda o último erro para o comando last-error
+pub fn record_error(error: &LogError) {
+    *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some(error.clone());
+}
+
+/// Caminho do socket: `control.socket` ou ~/.snapshot_agent/control.sock
+pub fn socket_path(config: &ControlConfig) -> PathBuf {
+    config.socket.clone().unwrap_or_else(|| {
+        let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+        path.push(".snapshot_agent");
+        path.push("control.sock");
+        path
+    })
+}
+
+/// Socket de controle em atendimento
+pub struct ControlListener {
+    path: PathBuf,
+    stop: Arc<AtomicBool>, // Pede à thread do socket que encerre
+}
+
+impl ControlListener {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Para de aceitar conexões e remove o socket
+    /// A conexão feita aqui só acorda a thread bloqueada no accept
+    pub fn close(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = UnixStream::connect(&self.path);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Cria o socket e atende as conexões em segundo plano
+/// Cada conexão é tratada em uma thread própria; o snapshot é pedido ao loop
+/// principal pelo mesmo canal dos sinais
+pub fn spawn_control_listener(
+    path: &Path,
+    state: Arc<Mutex<ControlState>>,
+    tx: Sender<AgentEvent>,
+) -> Result<ControlListener, String> {
+    let listener = bind(path)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    spawn(move || {
+        for stream in listener.incoming() {
+            if stopped.load(Ordering::Relaxed) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let state = state.clone();
+            let tx = tx.clone();
+            spawn(move || handle_connection(stream, &state, &tx));
+        }
+    });
+    Ok(ControlListener {
+        path: path.to_path_buf(),
+        stop,
+    })
+}
+
+/// Cliente: envia um comando e devolve a resposta
+pub fn send_command(path: &Path, command: &str) -> Result<Value, String> {
+    let mut stream = UnixStream::connect(path)
+        .map_err(|e| format!("Erro ao conectar em {:?} (agente rodando?): {}", path, e))?;
+    writeln!(stream, "{}", command)
+        .and_then(|_| stream.shutdown(Shutdown::Write))
+        .map_err(|e| format!("Erro ao enviar comando: {}", e))?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("Erro ao ler resposta: {}", e))?;
+    serde_json::from_str(&line).map_err(|e| format!("Resposta inválida do agente: {}", e))
+}
+
+/// Cria o socket com permissão apenas para o dono
+/// Um socket órfão (agente anterior encerrado sem removê-lo) é substituído
+fn bind(path: &Path) -> Result<UnixListener, String> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!("Outro agente já atende em {:?}", path));
+        }
+        let _ = fs::remove_file(path);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {:?}: {}", dir, e))?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|e| format!("Erro ao criar socket de controle {:?}: {}", path, e))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Erro ao ajustar permissões de {:?}: {}", path, e))?;
+    Ok(listener)
+}
+
+/// Lê um comando, executa e responde com uma linha JSON
+fn handle_connection(stream: UnixStream, state: &Mutex<ControlState>, tx: &Sender<AgentEvent>) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line).is_err() {
+        return;
+    }
+
+    let result = match line.trim() {
+        "snapshot" => {
+            request_snapshot(tx).map(|snapshot| json!({ "snapshot": Record::Snapshot(snapshot) }))
+        }
+        "status" => {
+            let state = state.lock().unwrap_or_else(|e| e.into_inner());
+            let mut status = state.status.clone();
+            status.uptime_secs = state.started.elapsed().as_secs();
+            Ok(json!({ "status": status }))
+        }
+        "config" => {
+            let state = state.lock().unwrap_or_else(|e| e.into_inner());
+            Ok(json!({ "config": state.config }))
+        }
+        "last-error" => {
+            let error = LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            Ok(json!({ "last_error": error }))
+        }
+        other => Err(format!(
+            "Comando desconhecido: {:?} (use snapshot, status, config ou last-error)",
+            other
+        )),
+    };
+
+    let response = match result {
+        Ok(mut value) => {
+            value["ok"] = Value::Bool(true);
+            value
+        }
+        Err(e) => json!({ "ok": false, "error": e }),
+    };
+    let mut stream = stream;
+    let _ = writeln!(stream, "{}", response);
+}
+
+/// Pede um snapshot ao loop principal e aguarda a coleta
+fn request_snapshot(tx: &Sender<AgentEvent>) -> Result<Snapshot, String> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    tx.send(AgentEvent::Snapshot("control socket", Some(reply_tx)))
+        .map_err(|_| "Agente encerrando".to_string())?;
+    reply_rx
+        .recv_timeout(SNAPSHOT_TIMEOUT)
+        .map_err(|_| "Tempo esgotado aguardando o snapshot".to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::FileTypeExt;
+
+    #[test]
+    fn socket_answers_each_command() {
+        let dir = std::env::temp_dir().join(format!("control-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("control.sock");
+
+        // Socket órfão de um agente anterior: é substituído
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(send_command(&path, "status").is_err());
+
+        let config = AgentConfig::default();
+        let state = Arc::new(Mutex::new(ControlState::new(
+            "agente",
+            "vm1",
+            &config,
+            dir.join("snapshot.log"),
+        )));
+        let (tx, rx) = mpsc::channel();
+        let listener = spawn_control_listener(&path, state, tx.clone()).unwrap();
+        let metadata = fs::metadata(&path).unwrap();
+        assert!(metadata.file_type().is_socket());
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+
+        // Um segundo agente no mesmo caminho é recusado
+        let (other_tx, _) = mpsc::channel();
+        let state = Arc::new(Mutex::new(ControlState::new(
+            "outro",
+            "vm1",
+            &config,
+            dir.join("snapshot.log"),
+        )));
+        assert!(spawn_control_listener(&path, state, other_tx).is_err());
+
+        let status = send_command(&path, "status").unwrap();
+        assert_eq!(status["ok"], true);
+        assert_eq!(status["status"]["agent_id"], "agente");
+        assert_eq!(status["status"]["pid"], std::process::id());
+
+        let current = send_command(&path, "config").unwrap();
+        assert_eq!(current["config"]["interval_secs"], config.interval_secs);
+
+        record_error(&LogError {
+            schema_version: snapshot_schema::SCHEMA_VERSION,
+            agent_id: "agente".to_string(),
+            hostname: "vm1".to_string(),
+            datetime: "2024-05-01T12:00:00Z".to_string(),
+            timestamp: 1714564800,
+            error: "falha de teste".to_string(),
+        });
+        let last_error = send_command(&path, "last-error").unwrap();
+        assert_eq!(last_error["last_error"]["error"], "falha de teste");
+
+        let unknown = send_command(&path, "reboot").unwrap();
+        assert_eq!(unknown["ok"], false);
+        assert!(unknown["error"].as_str().unwrap().contains("reboot"));
+
+        // O snapshot é pedido ao loop principal, que aqui é esta thread
+        let client = {
+            let path = path.clone();
+            spawn(move || send_command(&path, "snapshot"))
+        };
+        match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+            AgentEvent::Snapshot(origin, Some(reply)) => {
+                assert_eq!(origin, "control socket");
+                let snapshot: Snapshot = serde_json::from_value(json!({
+                    "agent_id": "agente",
+                    "timestamp": 1714564800,
+                    "datetime": "2024-05-01T12:00:00Z",
+                    "total_memory": 1000,
+                    "used_memory": 500,
+                    "cpu_usage_percent": 10.0,
+                    "total_disk": 100,
+                    "used_disk": 25
+                }))
+                .unwrap();
+                reply.send(Ok(snapshot)).unwrap();
+            }
+            _ => panic!("esperado um pedido de snapshot"),
+        }
+        let response = client.join().unwrap().unwrap();
+        assert_eq!(response["ok"], true);
+        assert_eq!(response["snapshot"]["record_type"], "SNAPSHOT");
+        assert_eq!(response["snapshot"]["used_memory"], 500);
+
+        listener.close();
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,509 @@
+use crate::config::{expand_home, FileIntegrityConfig};
+use chrono::{SecondsFormat, Utc};
+use dirs_next::home_dir;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snapshot_schema::{FileChange, FileState, Record, SCHEMA_VERSION};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// =========================================================================
+/// MONITORAMENTO DE INTEGRIDADE DE ARQUIVOS (FIM)
+/// =========================================================================
+/// Mantém em ~/.snapshot_agent/fim_baseline.json o SHA-256, as permissões e
+/// o dono de cada arquivo em `file_integrity.paths` e grava FILE_CHANGED
+/// quando algum deles difere do baseline. O baseline é atualizado após cada
+/// verificação, então cada alteração é registrada uma única vez.
+///
+/// Na primeira verificação de um caminho (baseline novo ou caminho incluído
+/// na configuração) os arquivos entram no baseline sem gerar eventos.
+///
+/// Um arquivo do baseline que não pôde ser lido ou ficou além do limite de
+/// arquivos mantém o estado anterior; "deleted" exige que ele não exista.
+
+/// Limite de arquivos por verificação (diretórios muito grandes)
+const MAX_FILES: usize = 10_000;
+
+/// Baseline persistido entre execuções
+#[derive(Serialize, Deserialize, Default)]
+struct Baseline {
+    roots: BTreeSet<String>, // Caminhos configurados já incluídos no baseline
+    files: BTreeMap<String, FileState>,
+}
+
+/// Retorna o caminho do baseline
+/// Localizado em ~/.snapshot_agent/fim_baseline.json
+pub fn get_baseline_path() -> PathBuf {
+    let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".snapshot_agent");
+    path.push("fim_baseline.json");
+    path
+}
+
+/// Compara os caminhos configurados com o baseline
+pub struct FileMonitor {
+    baseline_path: PathBuf,
+    baseline: Option<Baseline>, // Carregado na primeira verificação
+    users: HashMap<u32, String>,
+    groups: HashMap<u32, String>,
+    unreadable: HashSet<String>, // Arquivos cujo erro de leitura já foi registrado
+    truncated: bool,             // Limite de arquivos atingido na última verificação
+    max_files: usize,            // Limite de arquivos por verificação
+}
+
+impl FileMonitor {
+    pub fn new() -> Self {
+        Self {
+            baseline_path: get_baseline_path(),
+            baseline: None,
+            users: read_names("/etc/passwd"),
+            groups: read_names("/etc/group"),
+            unreadable: HashSet::new(),
+            truncated: false,
+            max_files: MAX_FILES,
+        }
+    }
+
+    /// Verifica os caminhos e retorna as alterações e os erros
+    pub fn check(
+        &mut self,
+        config: &FileIntegrityConfig,
+        agent_id: &str,
+        hostname: &str,
+    ) -> (Vec<Record>, Vec<String>) {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        if config.paths.is_empty() {
+            return (records, errors);
+        }
+
+        let baseline_path = &self.baseline_path;
+        let baseline =
+            self.baseline
+                .get_or_insert_with(|| match fs::read_to_string(baseline_path) {
+                    Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                        errors.push(format!("Baseline inválido em {:?}: {}", baseline_path, e));
+                        Baseline::default()
+                    }),
+                    Err(_) => Baseline::default(),
+                });
+
+        // Estado atual de todos os arquivos, por caminho configurado
+        let mut current: BTreeMap<String, (String, FileState)> = BTreeMap::new();
+        let mut read_errors = Vec::new();
+        let mut truncated = false;
+        for root in &config.paths {
+            // Um arquivo além do limite indica que a lista foi cortada
+            let mut files = Vec::new();
+            collect_files(
+                &expand_home(root),
+                self.max_files + 1,
+                &mut files,
+                &mut read_errors,
+            );
+            for path in files {
+                if current.len() >= self.max_files {
+                    truncated = true;
+                    break;
+                }
+                let key = path.to_string_lossy().into_owned();
+                match file_state(&path, config.max_file_bytes, &self.users, &self.groups) {
+                    Ok((state, hash_error)) => {
+                        if let Some(e) = hash_error {
+                            read_errors.push((key.clone(), e));
+                        }
+                        current.insert(key, (root.clone(), state));
+                    }
+                    Err(e) => read_errors.push((key, e)),
+                }
+            }
+        }
+
+        // O limite é registrado quando passa a ser atingido, não a cada verificação
+        if truncated && !self.truncated {
+            errors.push(format!(
+                "FIM: limite de {} arquivos atingido; demais ignorados",
+                self.max_files
+            ));
+        }
+        self.truncated = truncated;
+
+        // Erros de leitura são registrados uma vez por arquivo; um arquivo
+        // que volta a ser lido sai da lista e um novo erro é registrado
+        let failed: HashSet<&String> = read_errors.iter().map(|(path, _)| path).collect();
+        self.unreadable.retain(|path| failed.contains(path));
+        for (path, error) in &read_errors {
+            if self.unreadable.insert(path.clone()) {
+                errors.push(format!("FIM: erro ao ler {}: {}", path, error));
+            }
+        }
+
+        let datetime = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let timestamp = Utc::now().timestamp().max(0) as u64;
+        let change =
+            |path: &str, changed: Vec<&str>, old: Option<&FileState>, new: Option<&FileState>| {
+                Record::FileChanged(FileChange {
+                    schema_version: SCHEMA_VERSION,
+                    agent_id: agent_id.to_string(),
+                    hostname: hostname.to_string(),
+                    datetime: datetime.clone(),
+                    timestamp,
+                    path: path.to_string(),
+                    changed: changed.into_iter().map(str::to_string).collect(),
+                    old: old.cloned(),
+                    new: new.cloned(),
+                })
+            };
+
+        // Criados e alterados (caminhos novos na configuração só entram no baseline)
+        for (path, (root, state)) in &current {
+            match baseline.files.get(path) {
+                Some(old) => {
+                    let changed = compare(old, state);
+                    if !changed.is_empty() {
+                        records.push(change(path, changed, Some(old), Some(state)));
+                    }
+                }
+                None if baseline.roots.contains(root) => {
+                    records.push(change(path, vec!["created"], None, Some(state)));
+                }
+                None => {}
+            }
+        }
+
+        // Removidos: estavam no baseline, sob um caminho ainda configurado, e
+        // não existem mais; os que não foram lidos mantêm o estado anterior
+        let roots: Vec<PathBuf> = config.paths.iter().map(|p| expand_home(p)).collect();
+        let mut carried = Vec::new();
+        for (path, old) in &baseline.files {
+            let monitored = roots.iter().any(|root| Path::new(path).starts_with(root));
+            if !monitored || current.contains_key(path) {
+                continue;
+            }
+            match fs::symlink_metadata(path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    records.push(change(path, vec!["deleted"], Some(old), None));
+                }
+                _ => carried.push((path.clone(), old.clone())),
+            }
+        }
+
+        // O baseline passa a refletir o estado atual
+        let new_baseline = Baseline {
+            roots: config.paths.iter().cloned().collect(),
+            files: current
+                .into_iter()
+                .map(|(path, (_, state))| (path, state))
+                .chain(carried)
+                .collect(),
+        };
+        let dirty = !records.is_empty()
+            || new_baseline.roots != baseline.roots
+            || new_baseline.files != baseline.files;
+        *baseline = new_baseline;
+        if dirty {
+            if let Err(e) = save_baseline(baseline_path, baseline) {
+                errors.push(e);
+            }
+        }
+
+        (records, errors)
+    }
+}
+
+/// Campos que diferem entre o baseline e o estado atual
+fn compare(old: &FileState, new: &FileState) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    let content_changed = match (&old.sha256, &new.sha256) {
+        (Some(old_hash), Some(new_hash)) => old_hash != new_hash,
+        // Sem hash (arquivo grande ou ilegível): tamanho e mtime
+        _ => old.size != new.size || old.mtime != new.mtime,
+    };
+    if content_changed {
+        changed.push("content");
+    }
+    if old.mode != new.mode {
+        changed.push("mode");
+    }
+    if old.uid != new.uid {
+        changed.push("owner");
+    }
+    if old.gid != new.gid {
+        changed.push("group");
+    }
+    changed
+}
+
+/// Lista os arquivos de um caminho (diretórios são percorridos
+/// recursivamente, sem seguir links simbólicos para diretórios)
+fn collect_files(
+    path: &Path,
+    limit: usize,
+    files: &mut Vec<PathBuf>,
+    errors: &mut Vec<(String, String)>,
+) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return; // Inexistente: removido ou ainda não criado
+    };
+    if !metadata.is_dir() {
+        files.push(path.to_path_buf());
+        return;
+    }
+
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            errors.push((path.to_string_lossy().into_owned(), e.to_string()));
+            return;
+        }
+    };
+    let mut children: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    children.sort();
+    for child in children {
+        if files.len() >= limit {
+            return;
+        }
+        let is_symlink = fs::symlink_metadata(&child)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false);
+        if is_symlink && child.is_dir() {
+            continue;
+        }
+        collect_files(&child, limit, files, errors);
+    }
+}
+
+/// Estado atual de um arquivo; o segundo valor é o erro ao calcular o hash
+fn file_state(
+    path: &Path,
+    max_file_bytes: u64,
+    users: &HashMap<u32, String>,
+    groups: &HashMap<u32, String>,
+) -> Result<(FileState, Option<String>), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let (sha256, hash_error) = if metadata.len() > max_file_bytes {
+        (None, None)
+    } else {
+        match hash_file(path) {
+            Ok(hash) => (Some(hash), None),
+            Err(e) => (None, Some(e.to_string())),
+        }
+    };
+
+    let name = |names: &HashMap<u32, String>, id: u32| {
+        names.get(&id).cloned().unwrap_or_else(|| id.to_string())
+    };
+    let state = FileState {
+        sha256,
+        size: metadata.len(),
+        mode: format!("{:04o}", metadata.mode() & 0o7777),
+        uid: metadata.uid(),
+        gid: metadata.gid(),
+        owner: name(users, metadata.uid()),
+        group: name(groups, metadata.gid()),
+        mtime: metadata.mtime().max(0) as u64,
+    };
+    Ok((state, hash_error))
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Nomes por id a partir de /etc/passwd ou /etc/group (nome:x:id:...)
+fn read_names(path: &str) -> HashMap<u32, String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let id = fields.nth(1)?.parse().ok()?;
+            Some((id, name.to_string()))
+        })
+        .collect()
+}
+
+/// Grava o baseline de forma atômica (arquivo temporário + rename)
+fn save_baseline(path: &Path, baseline: &Baseline) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(baseline).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {:?}: {}", dir, e))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| format!("Erro ao gravar baseline {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Caminho e tipo de alteração de cada FILE_CHANGED
+    fn changes(records: &[Record]) -> Vec<(String, Vec<String>)> {
+        records
+            .iter()
+            .map(|record| match record {
+                Record::FileChanged(change) => (
+                    Path::new(&change.path)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned(),
+                    change.changed.clone(),
+                ),
+                _ => panic!("registro inesperado"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_change_once() {
+        let dir = std::env::temp_dir().join(format!("fim-test-{}", std::process::id()));
+        let watched = dir.join("etc");
+        fs::create_dir_all(&watched).unwrap();
+        fs::write(watched.join("a.conf"), "a=1").unwrap();
+        fs::write(watched.join("b.conf"), "b=1").unwrap();
+
+        let config = FileIntegrityConfig {
+            paths: vec![watched.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let mut monitor = FileMonitor {
+            baseline_path: dir.join("fim_baseline.json"),
+            ..FileMonitor::new()
+        };
+
+        // Primeira verificação só monta o baseline
+        let (records, errors) = monitor.check(&config, "agente", "vm1");
+        assert!(records.is_empty() && errors.is_empty());
+        assert!(dir.join("fim_baseline.json").is_file());
+
+        fs::write(watched.join("a.conf"), "a=2").unwrap();
+        fs::set_permissions(watched.join("b.conf"), fs::Permissions::from_mode(0o600)).unwrap();
+        fs::write(watched.join("c.conf"), "c=1").unwrap();
+        let (records, _) = monitor.check(&config, "agente", "vm1");
+        assert_eq!(
+            changes(&records),
+            vec![
+                ("a.conf".to_string(), vec!["content".to_string()]),
+                ("b.conf".to_string(), vec!["mode".to_string()]),
+                ("c.conf".to_string(), vec!["created".to_string()]),
+            ]
+        );
+        assert!(monitor.check(&config, "agente", "vm1").0.is_empty());
+
+        fs::remove_file(watched.join("a.conf")).unwrap();
+        let (records, _) = monitor.check(&config, "agente", "vm1");
+        assert_eq!(
+            changes(&records),
+            vec![("a.conf".to_string(), vec!["deleted".to_string()])]
+        );
+
+        // Um monitor novo retoma o baseline salvo
+        let mut restarted = FileMonitor {
+            baseline_path: dir.join("fim_baseline.json"),
+            ..FileMonitor::new()
+        };
+        assert!(restarted.check(&config, "agente", "vm1").0.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn files_past_the_limit_keep_their_baseline() {
+        let dir = std::env::temp_dir().join(format!("fim-limit-test-{}", std::process::id()));
+        let watched = dir.join("etc");
+        fs::create_dir_all(&watched).unwrap();
+        for name in ["a.conf", "b.conf", "c.conf"] {
+            fs::write(watched.join(name), "x=1").unwrap();
+        }
+        let config = FileIntegrityConfig {
+            paths: vec![watched.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let mut monitor = FileMonitor {
+            baseline_path: dir.join("fim_baseline.json"),
+            ..FileMonitor::new()
+        };
+        assert!(monitor.check(&config, "agente", "vm1").0.is_empty());
+
+        // c.conf fica além do limite: não é "deleted" e continua no baseline
+        monitor.max_files = 2;
+        fs::write(watched.join("c.conf"), "x=2").unwrap();
+        let (records, errors) = monitor.check(&config, "agente", "vm1");
+        assert!(records.is_empty());
+        assert_eq!(errors.len(), 1);
+
+        // De volta ao limite normal, a alteração é comparada com o estado antigo
+        monitor.max_files = MAX_FILES;
+        let (records, _) = monitor.check(&config, "agente", "vm1");
+        assert_eq!(
+            changes(&records),
+            vec![("c.conf".to_string(), vec!["content".to_string()])]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_files_are_reported_again_after_recovering() {
+        let dir = std::env::temp_dir().join(format!("fim-unreadable-test-{}", std::process::id()));
+        let watched = dir.join("run");
+        fs::create_dir_all(&watched).unwrap();
+        let socket = watched.join("agent.sock");
+        let config = FileIntegrityConfig {
+            paths: vec![watched.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let mut monitor = FileMonitor {
+            baseline_path: dir.join("fim_baseline.json"),
+            ..FileMonitor::new()
+        };
+
+        // Um socket não pode ser aberto, nem pelo root: o erro sai uma vez
+        let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        assert_eq!(monitor.check(&config, "agente", "vm1").1.len(), 1);
+        assert!(monitor.check(&config, "agente", "vm1").1.is_empty());
+
+        drop(listener);
+        fs::remove_file(&socket).unwrap();
+        fs::write(&socket, "agora legível").unwrap();
+        assert!(monitor.check(&config, "agente", "vm1").1.is_empty());
+        assert!(monitor.unreadable.is_empty());
+
+        fs::remove_file(&socket).unwrap();
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        assert_eq!(monitor.check(&config, "agente", "vm1").1.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn large_files_compare_size_instead_of_hash() {
+        let old = FileState {
+            sha256: None,
+            size: 10,
+            mode: "0644".to_string(),
+            uid: 0,
+            gid: 0,
+            owner: "root".to_string(),
+            group: "root".to_string(),
+            mtime: 100,
+        };
+        assert!(compare(&old, &old.clone()).is_empty());
+        let grown = FileState {
+            size: 20,
+            uid: 1000,
+            ..old.clone()
+        };
+        assert_eq!(compare(&old, &grown), vec!["content", "owner"]);
+    }
+}
@@ -0,0 +1,55 @@
+use chrono::{SecondsFormat, TimeZone, Utc};
+use snapshot_schema::HostInventory;
+use std::fs;
+use sysinfo::{CpuExt, System, SystemExt};
+
+/// =========================================================================
+/// INVENTÁRIO DO HOST
+/// =========================================================================
+/// Informações estáticas enviadas no registro INVENTORY. O agent_id
+/// persistente fica em `snapshot_agent::identity`.
+
+/// Lê o PRETTY_NAME de /etc/os-release
+fn read_os_release() -> Option<String> {
+    let content = fs::read_to_string("/etc/os-release").ok()?;
+    content.lines().find_map(|line| {
+        line.strip_prefix("PRETTY_NAME=")
+            .map(|value| value.trim_matches('"').to_string())
+    })
+}
+
+/// Coleta as informações estáticas do host
+pub fn collect_inventory() -> HostInventory {
+    let mut sys = System::new();
+    sys.refresh_cpu();
+    sys.refresh_memory();
+
+    let boot_time = sys.boot_time();
+    let boot_datetime = Utc
+        .timestamp_opt(boot_time as i64, 0)
+        .single()
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default();
+
+    HostInventory {
+        os_release: read_os_release()
+            .or_else(|| sys.long_os_version())
+            .unwrap_or_else(|| "desconhecido".to_string()),
+        kernel: sys
+            .kernel_version()
+            .unwrap_or_else(|| "desconhecido".to_string()),
+        architecture: std::env::consts::ARCH.to_string(),
+        cpu_model: sys
+            .cpus()
+            .first()
+            .map(|cpu| cpu.brand().trim().to_string())
+            .unwrap_or_default(),
+        cpu_cores: sys.physical_core_count().unwrap_or(0),
+        cpu_threads: sys.cpus().len(),
+        total_memory: sys.total_memory(),
+        boot_time,
+        boot_datetime,
+        uptime_secs: sys.uptime(),
+        agent_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
@@ -0,0 +1,309 @@
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// =========================================================================
+/// ENCADEAMENTO DE HASHES DO LOG
+/// =========================================================================
+/// Com `integrity.hash_chain` ativo, cada registro recebe:
+///   prev_hash - SHA-256 do registro anterior (zeros no primeiro registro)
+///   hmac      - HMAC-SHA256 do registro, incluindo prev_hash (se houver chave)
+///
+/// Os hashes são calculados sobre a forma canônica do registro (JSON com as
+/// chaves em ordem), que é a mesma nos formatos json e compact. Remover,
+/// alterar ou reordenar registros quebra a cadeia; com HMAC, refazer a
+/// cadeia exige a chave.
+///
+/// Limitação: a cadeia não tem âncora externa. Cortar o fim do log, ou
+/// trocá-lo por um arquivo vazio, não deixa rastro: o que sobra continua
+/// íntegro e o agente recomeça a cadeia a partir de GENESIS_HASH. Detectar
+/// isso exige guardar o último hash fora da máquina (ex.: num sink remoto).
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// `prev_hash` do primeiro registro de um log vazio
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Estado da cadeia mantido pelo escritor do log
+pub struct HashChain {
+    pub prev_hash: String, // Hash do último registro gravado
+    key: Option<Vec<u8>>,  // Chave do HMAC
+}
+
+impl HashChain {
+    pub fn new(prev_hash: String, key: Option<Vec<u8>>) -> Self {
+        Self { prev_hash, key }
+    }
+
+    /// Acrescenta prev_hash (e hmac) ao registro e avança a cadeia
+    pub fn seal(&mut self, record: &mut Value) {
+        let Value::Object(fields) = record else {
+            return;
+        };
+        fields.remove("hmac");
+        fields.insert(
+            "prev_hash".to_string(),
+            Value::String(self.prev_hash.clone()),
+        );
+        if let Some(key) = &self.key {
+            let mac = compute_hmac(key, &canonical(record));
+            if let Value::Object(fields) = record {
+                fields.insert("hmac".to_string(), Value::String(mac));
+            }
+        }
+        self.prev_hash = record_hash(record);
+    }
+}
+
+/// Serialização canônica usada nos hashes
+fn canonical(record: &Value) -> String {
+    serde_json::to_string(record).expect("Erro ao serializar registro")
+}
+
+/// SHA-256 (hex) da forma canônica do registro
+pub fn record_hash(record: &Value) -> String {
+    hex::encode(Sha256::digest(canonical(record).as_bytes()))
+}
+
+fn compute_hmac(key: &[u8], content: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(content.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Lê a chave do HMAC (espaços e quebras de linha nas pontas são ignorados)
+pub fn load_key(path: &Path) -> Result<Vec<u8>, String> {
+    let content =
+        fs::read(path).map_err(|e| format!("Erro ao ler chave HMAC {:?}: {}", path, e))?;
+    let key = content.trim_ascii().to_vec();
+    if key.is_empty() {
+        return Err(format!("Chave HMAC vazia em {:?}", path));
+    }
+    Ok(key)
+}
+
+/// Ponto da cadeia onde a verificação falhou
+pub struct BrokenLink {
+    pub index: usize, // Posição do registro no log (a partir de 1)
+    pub datetime: Option<String>,
+    pub reason: String,
+}
+
+/// Resultado do subcomando verify
+pub struct VerifyReport {
+    pub records: usize,   // Registros lidos até o fim ou até a quebra
+    pub unchained: usize, // Registros anteriores à ativação da cadeia
+    pub chained: usize,   // Registros com prev_hash conferido
+    pub broken: Option<BrokenLink>,
+}
+
+impl VerifyReport {
+    /// A verificação só passa se nenhum elo quebrou e a cadeia existe
+    pub fn is_intact(&self) -> bool {
+        self.broken.is_none() && self.chained > 0
+    }
+}
+
+/// Percorre os registros conferindo prev_hash e, com chave, o hmac
+/// Para no primeiro elo quebrado
+/// Com `require_chain` (chave informada ou cadeia configurada), registros sem
+/// prev_hash também quebram a cadeia, mesmo antes do primeiro encadeado
+pub fn verify<I>(records: I, key: Option<&[u8]>, require_chain: bool) -> VerifyReport
+where
+    I: IntoIterator<Item = Result<Value, String>>,
+{
+    let mut report = VerifyReport {
+        records: 0,
+        unchained: 0,
+        chained: 0,
+        broken: None,
+    };
+    let mut previous: Option<String> = None;
+    let mut chain_started = false;
+
+    for (position, record) in records.into_iter().enumerate() {
+        let index = position + 1;
+        report.records = index;
+        let broken = |datetime: Option<String>, reason: String| {
+            Some(BrokenLink {
+                index,
+                datetime,
+                reason,
+            })
+        };
+
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                report.broken = broken(None, format!("registro ilegível: {}", e));
+                break;
+            }
+        };
+        let datetime = record
+            .get("datetime")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        match record.get("prev_hash").and_then(Value::as_str) {
+            None if chain_started || require_chain => {
+                report.broken = broken(datetime, "registro sem prev_hash".to_string());
+                break;
+            }
+            None => report.unchained += 1,
+            Some(prev_hash) => {
+                chain_started = true;
+                report.chained += 1;
+                let expected = previous.as_deref().unwrap_or(GENESIS_HASH);
+                if prev_hash != expected {
+                    let reason = if previous.is_none() {
+                        "prev_hash aponta para um registro ausente (início do log removido?)"
+                    } else {
+                        "prev_hash não confere com o registro anterior"
+                    };
+                    report.broken = broken(datetime, reason.to_string());
+                    break;
+                }
+                if let Some(key) = key {
+                    if let Err(reason) = check_hmac(&record, key) {
+                        report.broken = broken(datetime, reason);
+                        break;
+                    }
+                }
+            }
+        }
+        previous = Some(record_hash(&record));
+    }
+    report
+}
+
+/// Confere o hmac de um registro
+fn check_hmac(record: &Value, key: &[u8]) -> Result<(), String> {
+    let Some(mac) = record.get("hmac").and_then(Value::as_str) else {
+        return Err("registro sem hmac".to_string());
+    };
+    let mut unsigned = record.clone();
+    if let Value::Object(fields) = &mut unsigned {
+        fields.remove("hmac");
+    }
+    if compute_hmac(key, &canonical(&unsigned)) != mac {
+        return Err("hmac inválido (registro alterado ou chave diferente)".to_string());
+    }
+    Ok(())
+}
+
+/// Opções do subcomando verify
+pub struct VerifyOptions {
+    pub file: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+/// Interpreta os argumentos após `verify`
+pub fn parse_args(args: &[String]) -> Result<VerifyOptions, String> {
+    let mut options = VerifyOptions {
+        file: None,
+        key: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .map(PathBuf::from)
+                .ok_or_else(|| format!("{} exige um valor", name))
+        };
+        match arg.as_str() {
+            "--file" => options.file = Some(value("--file")?),
+            "--key" => options.key = Some(value("--key")?),
+            other => return Err(format!("Argumento desconhecido: {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+/// Imprime o resultado da verificação
+pub fn print_report(report: &VerifyReport, path: &Path, with_hmac: bool) {
+    println!("Log: {}", path.display());
+    println!(
+        "Registros verificados: {}{}",
+        report.records,
+        if with_hmac { " (com HMAC)" } else { "" }
+    );
+    if report.unchained > 0 {
+        println!(
+            "Registros sem encadeamento (anteriores à ativação): {}",
+            report.unchained
+        );
+    }
+    match &report.broken {
+        None if report.chained == 0 => println!("Nenhum registro encadeado"),
+        None => println!("Cadeia íntegra"),
+        Some(link) => println!(
+            "Cadeia quebrada no registro #{}{}: {}",
+            link.index,
+            link.datetime
+                .as_deref()
+                .map(|dt| format!(" ({})", dt))
+                .unwrap_or_default(),
+            link.reason
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sealed(chain: &mut HashChain, n: u64) -> Value {
+        let mut record = json!({"type": "snapshot", "timestamp": n});
+        chain.seal(&mut record);
+        record
+    }
+
+    #[test]
+    fn unchained_prefix_fails_when_chain_is_required() {
+        // A cadeia ativada depois continua a partir do último registro
+        let key = b"segredo".to_vec();
+        let first = json!({"type": "snapshot", "timestamp": 0});
+        let mut chain = HashChain::new(record_hash(&first), Some(key.clone()));
+        let records = vec![
+            Ok(first),
+            Ok(sealed(&mut chain, 1)),
+            Ok(sealed(&mut chain, 2)),
+        ];
+
+        let report = verify(records.clone(), None, false);
+        assert!(report.is_intact());
+        assert_eq!((report.unchained, report.chained), (1, 2));
+
+        let report = verify(records, Some(&key), true);
+        assert!(!report.is_intact());
+        assert_eq!(report.broken.unwrap().index, 1);
+    }
+
+    #[test]
+    fn truncation_is_not_detected() {
+        // Sem âncora externa, cortar o fim ou esvaziar o log passa na verificação
+        let key = b"segredo".to_vec();
+        let mut chain = HashChain::new(GENESIS_HASH.to_string(), Some(key.clone()));
+        let records: Vec<_> = (0..3).map(|n| Ok(sealed(&mut chain, n))).collect();
+
+        let truncated = verify(records[..2].to_vec(), Some(&key), true);
+        assert!(truncated.is_intact());
+        assert_eq!(truncated.chained, 2);
+
+        // Log trocado por um vazio: a cadeia recomeça em GENESIS_HASH
+        let mut restarted = HashChain::new(GENESIS_HASH.to_string(), Some(key.clone()));
+        let report = verify(vec![Ok(sealed(&mut restarted, 10))], Some(&key), true);
+        assert!(report.is_intact());
+    }
+
+    #[test]
+    fn log_without_chained_records_fails() {
+        let records = vec![Ok(json!({"type": "snapshot", "timestamp": 0}))];
+        let report = verify(records, None, false);
+        assert!(report.broken.is_none());
+        assert!(!report.is_intact());
+    }
+}
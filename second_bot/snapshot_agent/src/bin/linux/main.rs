@@ -0,0 +1,56 @@
+#![allow(clippy::empty_line_after_doc_comments)]
+
+// =========================================================================
+// PLATAFORMA
+// =========================================================================
+// O agente depende de APIs do Linux (/proc, /sys, ioprio, sockets Unix e
+// systemd), então seus módulos só são compilados no Linux; nos demais
+// sistemas o binário existe apenas para não quebrar `cargo build --workspace`.
+
+#[cfg(target_os = "linux")]
+mod agent;
+#[cfg(target_os = "linux")]
+mod auth;
+#[cfg(target_os = "linux")]
+mod budget;
+#[cfg(target_os = "linux")]
+mod compact;
+#[cfg(target_os = "linux")]
+mod config;
+#[cfg(target_os = "linux")]
+mod control;
+#[cfg(target_os = "linux")]
+mod fim;
+#[cfg(target_os = "linux")]
+mod identity;
+#[cfg(target_os = "linux")]
+mod integrity;
+#[cfg(target_os = "linux")]
+mod packages;
+#[cfg(target_os = "linux")]
+mod ports;
+#[cfg(target_os = "linux")]
+mod query;
+#[cfg(target_os = "linux")]
+mod signals;
+#[cfg(target_os = "linux")]
+mod simulate;
+#[cfg(target_os = "linux")]
+mod sinks;
+#[cfg(target_os = "linux")]
+mod systemd;
+#[cfg(target_os = "linux")]
+mod update;
+#[cfg(target_os = "linux")]
+mod watch;
+
+#[cfg(target_os = "linux")]
+fn main() {
+    agent::main();
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    eprintln!("snapshot_agent_linux só roda no Linux; use snapshot_agent_windows");
+    std::process::exit(1);
+}
@@ -0,0 +1,576 @@
+use crate::config::PackagesConfig;
+use chrono::{SecondsFormat, Utc};
+use dirs_next::home_dir;
+use serde::{Deserialize, Serialize};
+use snapshot_schema::{
+    Package, PackageChange, PackageInventory, Record, UnitChange, UnitState, SCHEMA_VERSION,
+};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// =========================================================================
+/// INVENTÁRIO DE PACOTES E UNIDADES SYSTEMD
+/// =========================================================================
+/// A cada `packages.interval_secs` o agente lê os pacotes instalados (banco
+/// do dpkg ou `rpm -qa`) e o estado das unidades de serviço do systemd, e
+/// compara com o inventário anterior (~/.snapshot_agent/packages_baseline.json):
+///   PACKAGE_CHANGED - pacote installed, removed, upgraded ou downgraded
+///   UNIT_CHANGED    - unidade que entrou em falha (failed) ou saiu dela
+///                     (recovered)
+/// A lista completa (PACKAGE_INVENTORY) é gravada na primeira execução e
+/// depois a cada `packages.full_interval_secs`, para que o histórico possa
+/// ser reconstruído sem depender de todos os diffs.
+
+/// Formato da consulta ao rpm: nome, epoch:versão-release e arquitetura
+const RPM_QUERY_FORMAT: &str = "%{NAME}\\t%{EPOCHNUM}:%{VERSION}-%{RELEASE}\\t%{ARCH}\\n";
+
+/// Retorna o caminho do último inventário conhecido
+/// Localizado em ~/.snapshot_agent/packages_baseline.json
+pub fn get_baseline_path() -> PathBuf {
+    let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".snapshot_agent");
+    path.push("packages_baseline.json");
+    path
+}
+
+/// Inventário persistido entre execuções
+#[derive(Serialize, Deserialize, Default)]
+struct Baseline {
+    inventory_timestamp: u64, // Última lista completa gravada no log
+    packages: Vec<Package>,
+    units: Vec<UnitState>,
+}
+
+/// Lê os pacotes instalados
+/// Retorna o gerenciador usado; sem dpkg nem rpm a lista fica vazia
+pub fn read_packages(config: &PackagesConfig) -> Result<(Option<String>, Vec<Package>), String> {
+    match fs::read_to_string(&config.dpkg_status) {
+        Ok(content) => return Ok((Some("dpkg".to_string()), parse_dpkg_status(&content))),
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("Erro ao ler {:?}: {}", config.dpkg_status, e));
+        }
+        Err(_) => {}
+    }
+
+    let output = match Command::new("rpm")
+        .args(["-qa", "--queryformat", RPM_QUERY_FORMAT])
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((None, Vec::new())),
+        Err(e) => return Err(format!("erro ao executar rpm: {}", e)),
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "rpm -qa falhou: {}",
+            stderr.lines().next().unwrap_or("").trim()
+        ));
+    }
+    Ok((
+        Some("rpm".to_string()),
+        parse_rpm_output(&String::from_utf8_lossy(&output.stdout)),
+    ))
+}
+
+/// Interpreta /var/lib/dpkg/status: um parágrafo por pacote, separados por
+/// linha em branco; apenas pacotes com `Status: install ok installed`
+pub fn parse_dpkg_status(content: &str) -> Vec<Package> {
+    let mut packages: Vec<Package> = content
+        .split("\n\n")
+        .filter_map(|stanza| {
+            let mut name = None;
+            let mut status = None;
+            let mut version = None;
+            let mut architecture = None;
+            for line in stanza.lines() {
+                // Linhas de continuação começam com espaço
+                let Some((field, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim();
+                match field {
+                    "Package" => name = Some(value),
+                    "Status" => status = Some(value),
+                    "Version" => version = Some(value),
+                    "Architecture" => architecture = Some(value),
+                    _ => {}
+                }
+            }
+            if status? != "install ok installed" {
+                return None;
+            }
+            Some(Package {
+                name: name?.to_string(),
+                version: version?.to_string(),
+                architecture: architecture.unwrap_or("").to_string(),
+            })
+        })
+        .collect();
+    packages.sort_by_key(package_key);
+    packages
+}
+
+/// Interpreta a saída de `rpm -qa` com RPM_QUERY_FORMAT
+/// Epoch zero é omitido, como no dpkg
+pub fn parse_rpm_output(content: &str) -> Vec<Package> {
+    let mut packages: Vec<Package> = content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let name = fields.next()?.trim();
+            let version = fields.next()?.trim();
+            let architecture = fields.next()?.trim();
+            if name.is_empty() || name == "gpg-pubkey" {
+                // Chaves importadas aparecem como pacotes e não têm arquitetura
+                return None;
+            }
+            Some(Package {
+                name: name.to_string(),
+                version: version.strip_prefix("0:").unwrap_or(version).to_string(),
+                architecture: architecture.to_string(),
+            })
+        })
+        .collect();
+    packages.sort_by_key(package_key);
+    packages
+}
+
+/// Lê o estado das unidades de serviço do systemd
+/// Fora de um sistema iniciado pelo systemd a lista fica vazia
+pub fn read_units() -> Result<Vec<UnitState>, String> {
+    if !Path::new("/run/systemd/system").exists() {
+        return Ok(Vec::new());
+    }
+    let output = Command::new("systemctl")
+        .args([
+            "list-units",
+            "--all",
+            "--type=service",
+            "--plain",
+            "--no-legend",
+            "--no-pager",
+        ])
+        .output()
+        .map_err(|e| format!("erro ao executar systemctl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "systemctl list-units falhou: {}",
+            stderr.lines().next().unwrap_or("").trim()
+        ));
+    }
+    Ok(parse_units(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Interpreta `systemctl list-units --plain --no-legend`
+/// Colunas: UNIT LOAD ACTIVE SUB DESCRIPTION (a descrição é descartada)
+pub fn parse_units(content: &str) -> Vec<UnitState> {
+    let mut units: Vec<UnitState> = content
+        .lines()
+        .filter_map(|line| {
+            // Versões antigas marcam unidades com problema com "●" mesmo com --plain
+            let line = line.trim_start().trim_start_matches('●').trim_start();
+            let mut fields = line.split_whitespace();
+            Some(UnitState {
+                name: fields.next()?.to_string(),
+                load: fields.next()?.to_string(),
+                active: fields.next()?.to_string(),
+                sub: fields.next()?.to_string(),
+            })
+        })
+        .collect();
+    units.sort_by(|a, b| a.name.cmp(&b.name));
+    units.dedup_by(|a, b| a.name == b.name);
+    units
+}
+
+/// Chave do pacote: o mesmo nome pode estar instalado em várias
+/// arquiteturas (multiarch do Debian, i686 + x86_64 no RPM)
+fn package_key(package: &Package) -> String {
+    format!("{}:{}", package.name, package.architecture)
+}
+
+/// Compara versões no formato `[epoch:]upstream[-revisão]` com as regras do
+/// dpkg (letras antes de não letras, `~` antes de tudo, números por valor)
+/// Também usada para o rpm, cujas regras diferem apenas em casos raros
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let split = |version: &str| {
+        let (epoch, rest) = match version.split_once(':') {
+            Some((epoch, rest)) if epoch.chars().all(|c| c.is_ascii_digit()) => {
+                (epoch.parse::<u64>().unwrap_or(0), rest.to_string())
+            }
+            _ => (0, version.to_string()),
+        };
+        let (upstream, revision) = match rest.rsplit_once('-') {
+            Some((upstream, revision)) => (upstream.to_string(), revision.to_string()),
+            None => (rest, String::new()),
+        };
+        (epoch, upstream, revision)
+    };
+    let (epoch_a, upstream_a, revision_a) = split(a);
+    let (epoch_b, upstream_b, revision_b) = split(b);
+    epoch_a
+        .cmp(&epoch_b)
+        .then_with(|| compare_fragment(&upstream_a, &upstream_b))
+        .then_with(|| compare_fragment(&revision_a, &revision_b))
+}
+
+/// Algoritmo verrevcmp do dpkg: alterna trechos não numéricos (comparados
+/// caractere a caractere) e numéricos (comparados por valor)
+fn compare_fragment(a: &str, b: &str) -> Ordering {
+    // Peso de cada caractere no trecho não numérico
+    let order = |c: Option<u8>| -> i32 {
+        match c {
+            None => 0,
+            Some(b'~') => -1,
+            Some(c) if c.is_ascii_digit() => 0,
+            Some(c) if c.is_ascii_alphabetic() => c as i32,
+            Some(c) => c as i32 + 256,
+        }
+    };
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        while (i < a.len() && !a[i].is_ascii_digit()) || (j < b.len() && !b[j].is_ascii_digit()) {
+            let ac = order(a.get(i).copied().filter(|c| !c.is_ascii_digit()));
+            let bc = order(b.get(j).copied().filter(|c| !c.is_ascii_digit()));
+            if ac != bc {
+                return ac.cmp(&bc);
+            }
+            i += 1;
+            j += 1;
+        }
+        while i < a.len() && a[i] == b'0' {
+            i += 1;
+        }
+        while j < b.len() && b[j] == b'0' {
+            j += 1;
+        }
+        let mut first_diff = Ordering::Equal;
+        while i < a.len() && a[i].is_ascii_digit() && j < b.len() && b[j].is_ascii_digit() {
+            if first_diff == Ordering::Equal {
+                first_diff = a[i].cmp(&b[j]);
+            }
+            i += 1;
+            j += 1;
+        }
+        if i < a.len() && a[i].is_ascii_digit() {
+            return Ordering::Greater;
+        }
+        if j < b.len() && b[j].is_ascii_digit() {
+            return Ordering::Less;
+        }
+        if first_diff != Ordering::Equal {
+            return first_diff;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Agenda os inventários e compara cada um com o anterior
+pub struct PackageTracker {
+    baseline: Option<Baseline>, // Carregado na primeira verificação
+    next_check: Instant,
+}
+
+impl PackageTracker {
+    pub fn new() -> Self {
+        Self {
+            baseline: None,
+            next_check: Instant::now(),
+        }
+    }
+
+    /// Faz o inventário se o intervalo venceu e gera os registros de mudança
+    /// Sem baseline salvo (primeira execução) apenas a lista completa é gravada
+    pub fn check(
+        &mut self,
+        config: &PackagesConfig,
+        agent_id: &str,
+        hostname: &str,
+    ) -> (Vec<Record>, Vec<String>) {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        if Instant::now() < self.next_check {
+            return (records, errors);
+        }
+        self.next_check = Instant::now() + Duration::from_secs(config.interval_secs.max(60));
+
+        // Falha na leitura: mantém o baseline para não gerar remoções falsas
+        let (manager, packages) = match read_packages(config) {
+            Ok(result) => result,
+            Err(e) => {
+                errors.push(e);
+                return (records, errors);
+            }
+        };
+        let units = match read_units() {
+            Ok(units) => units,
+            Err(e) => {
+                errors.push(e);
+                return (records, errors);
+            }
+        };
+
+        let baseline_path = get_baseline_path();
+        let first_run = self.baseline.is_none() && !baseline_path.exists();
+        let previous = self.baseline.get_or_insert_with(|| {
+            load_baseline(&baseline_path).unwrap_or_else(|e| {
+                if let Some(e) = e {
+                    errors.push(e);
+                }
+                Baseline::default()
+            })
+        });
+
+        let datetime = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let timestamp = Utc::now().timestamp().max(0) as u64;
+        if !first_run {
+            records.extend(
+                diff_packages(&previous.packages, &packages)
+                    .into_iter()
+                    .map(|(package, change, old_version, new_version)| {
+                        Record::PackageChanged(PackageChange {
+                            schema_version: SCHEMA_VERSION,
+                            agent_id: agent_id.to_string(),
+                            hostname: hostname.to_string(),
+                            datetime: datetime.clone(),
+                            timestamp,
+                            name: package.name.clone(),
+                            architecture: package.architecture.clone(),
+                            change: change.to_string(),
+                            old_version,
+                            new_version,
+                        })
+                    }),
+            );
+            records.extend(diff_units(&previous.units, &units).into_iter().map(
+                |(change, old, new)| {
+                    Record::UnitChanged(UnitChange {
+                        schema_version: SCHEMA_VERSION,
+                        agent_id: agent_id.to_string(),
+                        hostname: hostname.to_string(),
+                        datetime: datetime.clone(),
+                        timestamp,
+                        change: change.to_string(),
+                        old,
+                        new,
+                    })
+                },
+            ));
+        }
+
+        let full_due =
+            timestamp.saturating_sub(previous.inventory_timestamp) >= config.full_interval_secs;
+        let changed =
+            first_run || full_due || previous.packages != packages || previous.units != units;
+        if full_due {
+            records.push(Record::PackageInventory(PackageInventory {
+                schema_version: SCHEMA_VERSION,
+                agent_id: agent_id.to_string(),
+                hostname: hostname.to_string(),
+                datetime,
+                timestamp,
+                manager,
+                packages: packages.clone(),
+                units: units.clone(),
+            }));
+            previous.inventory_timestamp = timestamp;
+        }
+        previous.packages = packages;
+        previous.units = units;
+        if changed {
+            if let Err(e) = save_baseline(&baseline_path, previous) {
+                errors.push(e);
+            }
+        }
+        (records, errors)
+    }
+}
+
+/// Diferenças entre duas listas de pacotes:
+/// (pacote, mudança, versão anterior, versão atual)
+fn diff_packages<'a>(
+    previous: &'a [Package],
+    current: &'a [Package],
+) -> Vec<(&'a Package, &'static str, Option<String>, Option<String>)> {
+    let previous: BTreeMap<String, &Package> =
+        previous.iter().map(|p| (package_key(p), p)).collect();
+    let current: BTreeMap<String, &Package> = current.iter().map(|p| (package_key(p), p)).collect();
+    let mut changes = Vec::new();
+    for (key, package) in &current {
+        match previous.get(key) {
+            None => changes.push((*package, "installed", None, Some(package.version.clone()))),
+            Some(old) if old.version != package.version => {
+                let change = match compare_versions(&package.version, &old.version) {
+                    Ordering::Less => "downgraded",
+                    _ => "upgraded",
+                };
+                changes.push((
+                    *package,
+                    change,
+                    Some(old.version.clone()),
+                    Some(package.version.clone()),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, package) in &previous {
+        if !current.contains_key(key) {
+            changes.push((*package, "removed", Some(package.version.clone()), None));
+        }
+    }
+    changes
+}
+
+/// Unidades que entraram em falha ou saíram dela:
+/// (mudança, estado anterior, estado atual)
+/// Uma unidade em falha que some da lista (reset-failed, desinstalada) conta
+/// como recuperada
+fn diff_units(
+    previous: &[UnitState],
+    current: &[UnitState],
+) -> Vec<(&'static str, Option<UnitState>, Option<UnitState>)> {
+    let previous: BTreeMap<&str, &UnitState> =
+        previous.iter().map(|u| (u.name.as_str(), u)).collect();
+    let current: BTreeMap<&str, &UnitState> =
+        current.iter().map(|u| (u.name.as_str(), u)).collect();
+    let failed = |unit: Option<&&UnitState>| unit.is_some_and(|u| u.active == "failed");
+    let mut changes = Vec::new();
+    for (name, unit) in &current {
+        let old = previous.get(name);
+        if failed(Some(unit)) && !failed(old) {
+            changes.push(("failed", old.map(|u| (*u).clone()), Some((*unit).clone())));
+        } else if !failed(Some(unit)) && failed(old) {
+            changes.push((
+                "recovered",
+                old.map(|u| (*u).clone()),
+                Some((*unit).clone()),
+            ));
+        }
+    }
+    for (name, unit) in &previous {
+        if failed(Some(unit)) && !current.contains_key(name) {
+            changes.push(("recovered", Some((*unit).clone()), None));
+        }
+    }
+    changes
+}
+
+/// Lê o baseline; `Err(None)` se ainda não existe
+fn load_baseline(path: &Path) -> Result<Baseline, Option<String>> {
+    let content = fs::read_to_string(path).map_err(|_| None)?;
+    serde_json::from_str(&content)
+        .map_err(|e| Some(format!("Baseline de pacotes inválido em {:?}: {}", path, e)))
+}
+
+/// Grava o baseline de forma atômica (arquivo temporário + rename)
+fn save_baseline(path: &Path, baseline: &Baseline) -> Result<(), String> {
+    let json = serde_json::to_string(baseline).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {:?}: {}", dir, e))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| format!("Erro ao gravar baseline {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str, architecture: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            architecture: architecture.to_string(),
+        }
+    }
+
+    #[test]
+    fn dpkg_status_lists_installed_packages() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/root/var/lib/dpkg/status");
+        let packages = parse_dpkg_status(&fs::read_to_string(path).unwrap());
+        // Pacotes removidos ficam de fora; multiarch gera uma entrada por arquitetura
+        assert_eq!(
+            packages,
+            vec![
+                package("libc6", "2.39-0ubuntu8.3", "amd64"),
+                package("libc6", "2.39-0ubuntu8.3", "i386"),
+                package("openssh-server", "1:9.6p1-3ubuntu13.5", "amd64"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rpm_output_skips_keys_and_zero_epoch() {
+        let output = "bash\t0:5.2.26-3.fc40\tx86_64\n\
+                      gpg-pubkey\t0:a15b79cc-63d04c2c\t(none)\n\
+                      kernel-core\t0:6.8.5-301.fc40\tx86_64\n\
+                      java\t1:21.0.3-1\tnoarch\n";
+        assert_eq!(
+            parse_rpm_output(output),
+            vec![
+                package("bash", "5.2.26-3.fc40", "x86_64"),
+                package("java", "1:21.0.3-1", "noarch"),
+                package("kernel-core", "6.8.5-301.fc40", "x86_64"),
+            ]
+        );
+    }
+
+    #[test]
+    fn systemctl_units_are_sorted_and_deduplicated() {
+        let output = "ssh.service loaded active running OpenBSD Secure Shell server\n\
+                      ● cron.service loaded failed failed Regular background program\n\
+                      ssh.service loaded active running OpenBSD Secure Shell server\n\
+                      incompleta.service loaded\n";
+        let units = parse_units(output);
+        let names: Vec<(&str, &str)> = units
+            .iter()
+            .map(|unit| (unit.name.as_str(), unit.active.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("cron.service", "failed"), ("ssh.service", "active")]
+        );
+    }
+
+    #[test]
+    fn versions_follow_dpkg_rules() {
+        let ordered = [
+            ("1.0~rc1", "1.0"),
+            ("1.0", "1.0-1"),
+            ("1.0-1", "1.0-2"),
+            ("1.2", "1.10"),
+            ("1.0a", "1.0+"),
+            ("2.39-0ubuntu8.3", "2.39-0ubuntu8.10"),
+            ("9.9", "1:1.0"),
+        ];
+        for (older, newer) in ordered {
+            assert_eq!(
+                compare_versions(older, newer),
+                Ordering::Less,
+                "{} < {}",
+                older,
+                newer
+            );
+            assert_eq!(
+                compare_versions(newer, older),
+                Ordering::Greater,
+                "{} > {}",
+                newer,
+                older
+            );
+        }
+        assert_eq!(compare_versions("1.01", "1.1"), Ordering::Equal);
+        assert_eq!(compare_versions("0:1.0", "1.0"), Ordering::Equal);
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::{SecondsFormat, Utc};
+use dirs_next::home_dir;
+use snapshot_schema::{ListeningSocket, PortEvent, Record, SCHEMA_VERSION};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// =========================================================================
+/// PORTAS EM ESCUTA
+/// =========================================================================
+/// A lista de sockets em escuta (coletor `ports` da biblioteca) entra em
+/// `Snapshot.listening`; a cada snapshot ela é comparada com a anterior
+/// (persistida em ~/.snapshot_agent/ports_baseline.json) e cada diferença
+/// gera um PORT_OPENED ou PORT_CLOSED.
+
+/// Retorna o caminho da última lista de portas conhecida
+/// Localizado em ~/.snapshot_agent/ports_baseline.json
+pub fn get_baseline_path() -> PathBuf {
+    let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".snapshot_agent");
+    path.push("ports_baseline.json");
+    path
+}
+
+/// Compara as portas de cada snapshot com as do anterior
+pub struct PortTracker {
+    known: Option<BTreeMap<String, ListeningSocket>>, // Carregado na primeira verificação
+}
+
+impl PortTracker {
+    pub fn new() -> Self {
+        Self { known: None }
+    }
+
+    /// Gera PORT_OPENED/PORT_CLOSED para as diferenças e atualiza o baseline
+    /// Sem baseline salvo (primeira execução) a lista atual é apenas gravada
+    pub fn check(
+        &mut self,
+        current: &[ListeningSocket],
+        agent_id: &str,
+        hostname: &str,
+    ) -> (Vec<Record>, Vec<String>) {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        let baseline_path = get_baseline_path();
+        let known = self.known.get_or_insert_with(|| {
+            load_baseline(&baseline_path).unwrap_or_else(|e| {
+                if let Some(e) = e {
+                    errors.push(e);
+                }
+                current
+                    .iter()
+                    .map(|socket| (socket.key(), socket.clone()))
+                    .collect()
+            })
+        });
+
+        let current: BTreeMap<String, ListeningSocket> = current
+            .iter()
+            .map(|socket| (socket.key(), socket.clone()))
+            .collect();
+        let datetime = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let timestamp = Utc::now().timestamp().max(0) as u64;
+        let event = |socket: &ListeningSocket| PortEvent {
+            schema_version: SCHEMA_VERSION,
+            agent_id: agent_id.to_string(),
+            hostname: hostname.to_string(),
+            datetime: datetime.clone(),
+            timestamp,
+            socket: socket.clone(),
+        };
+        for (key, socket) in &current {
+            if !known.contains_key(key) {
+                records.push(Record::PortOpened(event(socket)));
+            }
+        }
+        for (key, socket) in known.iter() {
+            if !current.contains_key(key) {
+                records.push(Record::PortClosed(event(socket)));
+            }
+        }
+
+        // O baseline também guarda o dono mais recente de cada porta
+        let changed = !records.is_empty() || !baseline_path.exists() || *known != current;
+        *known = current;
+        if changed {
+            if let Err(e) = save_baseline(&baseline_path, known) {
+                errors.push(e);
+            }
+        }
+        (records, errors)
+    }
+}
+
+/// Lê o baseline; `Err(None)` se ainda não existe
+fn load_baseline(path: &Path) -> Result<BTreeMap<String, ListeningSocket>, Option<String>> {
+    let content = fs::read_to_string(path).map_err(|_| None)?;
+    let sockets: Vec<ListeningSocket> = serde_json::from_str(&content)
+        .map_err(|e| Some(format!("Baseline de portas inválido em {:?}: {}", path, e)))?;
+    Ok(sockets
+        .into_iter()
+        .map(|socket| (socket.key(), socket))
+        .collect())
+}
+
+/// Grava o baseline de forma atômica (arquivo temporário + rename)
+fn save_baseline(path: &Path, sockets: &BTreeMap<String, ListeningSocket>) -> Result<(), String> {
+    let list: Vec<&ListeningSocket> = sockets.values().collect();
+    let json = serde_json::to_string_pretty(&list).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {:?}: {}", dir, e))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json)
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| format!("Erro ao gravar baseline {:?}: {}", path, e))
+}
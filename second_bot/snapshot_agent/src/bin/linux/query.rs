@@ -0,0 +1,522 @@
+use crate::compact::{self, CompactDecoder};
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use snapshot_schema::{AgentStatus, Record};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// =========================================================================
+/// CONSULTA LOCAL DO LOG
+/// =========================================================================
+/// Subcomando `query`: resume os registros do log local em um intervalo de
+/// tempo, sem depender do central_monitor.
+///
+///   snapshot_agent query [--since T] [--until T] [--file CAMINHO] [--json]
+///
+/// T aceita timestamp UNIX, data (2024-05-01), RFC3339 ou um valor relativo
+/// ao momento atual (30m, 6h, 2d).
+
+/// Opções do subcomando query
+pub struct QueryOptions {
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub file: Option<PathBuf>,
+    pub json: bool,
+}
+
+/// Estatísticas de uma métrica no intervalo
+#[derive(Serialize)]
+pub struct MetricSummary {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+    pub p95: f64,
+}
+
+/// Erro registrado no intervalo
+#[derive(Serialize)]
+pub struct ErrorEntry {
+    pub datetime: String,
+    pub error: String,
+}
+
+/// Resumo do intervalo consultado
+#[derive(Serialize)]
+pub struct QuerySummary {
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub snapshots: usize,
+    pub metrics: BTreeMap<&'static str, MetricSummary>,
+    pub starts: usize,
+    pub unclean_starts: usize, // STARTED sem STOPPED anterior (queda ou kill -9)
+    pub stops: BTreeMap<String, usize>, // Paradas por motivo
+    pub process_down: BTreeMap<String, usize>, // PROCESS_DOWN por item da watchlist
+    pub logins: usize,         // SESSION_OPENED
+    pub ssh_failures: u64,     // Soma dos AUTH_FAILURES
+    pub sudo_failures: u64,
+    pub file_changes: BTreeMap<String, usize>, // FILE_CHANGED por arquivo
+    pub ports_opened: BTreeMap<String, usize>, // PORT_OPENED por porta
+    pub package_changes: BTreeMap<String, usize>, // PACKAGE_CHANGED por tipo de mudança
+    pub units_failed: BTreeMap<String, usize>, // UNIT_CHANGED (failed) por unidade
+    pub errors: Vec<ErrorEntry>,
+}
+
+/// Interpreta os argumentos após `query`
+pub fn parse_args(args: &[String]) -> Result<QueryOptions, String> {
+    let now = Utc::now().timestamp().max(0) as u64;
+    let mut options = QueryOptions {
+        since: None,
+        until: None,
+        file: None,
+        json: false,
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} exige um valor", name))
+        };
+        match arg.as_str() {
+            "--since" => options.since = Some(parse_time(&value("--since")?, now)?),
+            "--until" => options.until = Some(parse_time(&value("--until")?, now)?),
+            "--file" => options.file = Some(PathBuf::from(value("--file")?)),
+            "--json" => options.json = true,
+            other => return Err(format!("Argumento desconhecido: {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+/// Converte um argumento de tempo em timestamp UNIX
+pub fn parse_time(value: &str, now: u64) -> Result<u64, String> {
+    if let Ok(timestamp) = value.parse::<u64>() {
+        return Ok(timestamp);
+    }
+
+    // Valor relativo: 90s, 30m, 6h, 2d
+    let multiplier = match value.chars().last() {
+        Some('s') => Some(1),
+        Some('m') => Some(60),
+        Some('h') => Some(3600),
+        Some('d') => Some(86400),
+        _ => None,
+    };
+    if let Some(multiplier) = multiplier {
+        if let Ok(amount) = value[..value.len() - 1].parse::<u64>() {
+            return Ok(now.saturating_sub(amount.saturating_mul(multiplier)));
+        }
+    }
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.timestamp().max(0) as u64);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).expect("Meia-noite válida");
+        return Ok(Utc.from_utc_datetime(&midnight).timestamp().max(0) as u64);
+    }
+
+    Err(format!("Tempo inválido: {}", value))
+}
+
+/// Lê os registros de um log como `Value`, detectando o formato pelo cabeçalho
+/// Linhas ou frames ilegíveis aparecem como `Err` na sequência
+pub fn read_values(path: &Path) -> Result<Vec<Result<Value, String>>, String> {
+    let mut file = File::open(path).map_err(|e| format!("Erro ao abrir {:?}: {}", path, e))?;
+
+    let mut magic = [0u8; 8];
+    let is_compact = file.read_exact(&mut magic).is_ok() && &magic == compact::MAGIC;
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| format!("Erro ao ler {:?}: {}", path, e))?;
+
+    if is_compact {
+        let mut values = Vec::new();
+        for value in CompactDecoder::new(BufReader::new(file))? {
+            // Depois de um frame inválido o restante não pode ser reconstruído
+            let stop = value.is_err();
+            values.push(value);
+            if stop {
+                break;
+            }
+        }
+        Ok(values)
+    } else {
+        Ok(BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(&line).map_err(|e| e.to_string()))
+            .collect())
+    }
+}
+
+/// Lê todos os registros de um log, detectando o formato pelo cabeçalho
+/// Linhas ou frames ilegíveis são ignorados
+pub fn read_records(path: &Path) -> Result<Vec<Record>, String> {
+    Ok(read_values(path)?
+        .into_iter()
+        .filter_map(|value| Record::from_value(value.ok()?).ok())
+        .collect())
+}
+
+/// Calcula mín/méd/máx/p95 (p95 pelo método nearest-rank)
+fn summarize(mut values: Vec<f64>) -> Option<MetricSummary> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = ((values.len() as f64) * 0.95).ceil() as usize;
+    Some(MetricSummary {
+        min: values[0],
+        avg: values.iter().sum::<f64>() / values.len() as f64,
+        max: values[values.len() - 1],
+        p95: values[rank.clamp(1, values.len()) - 1],
+    })
+}
+
+/// Percentual de uso, tratando total zero
+fn percent(used: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        used as f64 / total as f64 * 100.0
+    }
+}
+
+/// Gera o resumo dos registros dentro do intervalo
+pub fn summarize_records(records: &[Record], options: &QueryOptions) -> QuerySummary {
+    let in_range = |timestamp: u64| {
+        options.since.is_none_or(|since| timestamp >= since)
+            && options.until.is_none_or(|until| timestamp <= until)
+    };
+    let format_ts = |timestamp: u64| {
+        Utc.timestamp_opt(timestamp as i64, 0)
+            .single()
+            .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true))
+    };
+
+    let mut cpu = Vec::new();
+    let mut memory = Vec::new();
+    let mut disk = Vec::new();
+    let mut swap = Vec::new();
+    let mut memory_pressure = Vec::new();
+    let mut agent_cpu = Vec::new();
+    let mut starts = 0;
+    let mut unclean_starts = 0;
+    let mut stops = BTreeMap::new();
+    let mut process_down = BTreeMap::new();
+    let mut logins = 0;
+    let mut ssh_failures = 0;
+    let mut sudo_failures = 0;
+    let mut file_changes = BTreeMap::new();
+    let mut ports_opened = BTreeMap::new();
+    let mut package_changes = BTreeMap::new();
+    let mut units_failed = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    // O último status é acompanhado em todo o log para detectar quedas
+    let mut last_status: Option<AgentStatus> = None;
+    for record in records {
+        let selected = in_range(record.timestamp());
+        match record {
+            Record::Snapshot(snapshot) if selected => {
+                // Dentro de um cgroup limitado valem os valores do cgroup
+                let (used_memory, total_memory) = snapshot.effective_memory();
+                cpu.push(snapshot.effective_cpu_percent() as f64);
+                memory.push(percent(used_memory, total_memory));
+                disk.push(percent(snapshot.used_disk, snapshot.total_disk));
+                if let Some(health) = &snapshot.health {
+                    if health.swap_total > 0 {
+                        swap.push(percent(health.swap_used, health.swap_total));
+                    }
+                    let memory_psi = health.pressure.as_ref().and_then(|p| p.memory.as_ref());
+                    if let Some(stall) = memory_psi {
+                        memory_pressure.push(stall.some.avg10 as f64);
+                    }
+                }
+                if let Some(overhead) = &snapshot.overhead {
+                    agent_cpu.push(overhead.cpu_percent as f64);
+                }
+            }
+            Record::ServiceStatus(status) => {
+                if selected {
+                    match status.status {
+                        AgentStatus::Started => {
+                            starts += 1;
+                            if last_status.is_some_and(|s| s != AgentStatus::Stopped) {
+                                unclean_starts += 1;
+                            }
+                        }
+                        AgentStatus::Stopped => {
+                            let reason = status.reason.clone().unwrap_or_default();
+                            *stops.entry(reason).or_insert(0) += 1;
+                        }
+                        _ => {}
+                    }
+                }
+                if status.status != AgentStatus::Reloaded {
+                    last_status = Some(status.status);
+                }
+            }
+            Record::ProcessDown(event) if selected => {
+                *process_down.entry(event.name.clone()).or_insert(0) += 1;
+            }
+            Record::SessionOpened(_) if selected => logins += 1,
+            Record::AuthFailures(failures) if selected => {
+                ssh_failures += failures.ssh_failures;
+                sudo_failures += failures.sudo_failures;
+            }
+            Record::FileChanged(change) if selected => {
+                *file_changes.entry(change.path.clone()).or_insert(0) += 1;
+            }
+            Record::PortOpened(event) if selected => {
+                let name = match &event.socket.process {
+                    Some(process) => format!("{} ({})", event.socket.key(), process),
+                    None => event.socket.key(),
+                };
+                *ports_opened.entry(name).or_insert(0) += 1;
+            }
+            Record::PackageChanged(change) if selected => {
+                *package_changes.entry(change.change.clone()).or_insert(0) += 1;
+            }
+            Record::UnitChanged(change) if selected && change.change == "failed" => {
+                if let Some(unit) = &change.new {
+                    *units_failed.entry(unit.name.clone()).or_insert(0) += 1;
+                }
+            }
+            Record::Error(error) if selected => errors.push(ErrorEntry {
+                datetime: error.datetime.clone(),
+                error: error.error.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    let mut metrics = BTreeMap::new();
+    for (name, values) in [
+        ("cpu_percent", cpu),
+        ("memory_percent", memory),
+        ("disk_percent", disk),
+        ("swap_percent", swap),
+        ("memory_pressure_avg10", memory_pressure),
+        ("agent_cpu_percent", agent_cpu),
+    ] {
+        if let Some(summary) = summarize(values) {
+            metrics.insert(name, summary);
+        }
+    }
+
+    QuerySummary {
+        since: options.since.and_then(format_ts),
+        until: options.until.and_then(format_ts),
+        snapshots: records
+            .iter()
+            .filter(|r| matches!(r, Record::Snapshot(_)) && in_range(r.timestamp()))
+            .count(),
+        metrics,
+        starts,
+        unclean_starts,
+        stops,
+        process_down,
+        logins,
+        ssh_failures,
+        sudo_failures,
+        file_changes,
+        ports_opened,
+        package_changes,
+        units_failed,
+        errors,
+    }
+}
+
+/// Imprime o resumo em formato legível
+pub fn print_summary(summary: &QuerySummary) {
+    println!(
+        "Período: {} até {}",
+        summary.since.as_deref().unwrap_or("início do log"),
+        summary.until.as_deref().unwrap_or("agora")
+    );
+    println!("Snapshots: {}", summary.snapshots);
+
+    if !summary.metrics.is_empty() {
+        println!();
+        println!(
+            "{:<22} {:>8} {:>8} {:>8} {:>8}",
+            "Métrica", "mín", "méd", "máx", "p95"
+        );
+        for (name, m) in &summary.metrics {
+            println!(
+                "{:<22} {:>8.1} {:>8.1} {:>8.1} {:>8.1}",
+                name, m.min, m.avg, m.max, m.p95
+            );
+        }
+    }
+
+    println!();
+    println!(
+        "Inícios: {} ({} sem STOPPED anterior)",
+        summary.starts, summary.unclean_starts
+    );
+    let stops: usize = summary.stops.values().sum();
+    println!("Paradas: {}", stops);
+    for (reason, count) in &summary.stops {
+        let reason = if reason.is_empty() {
+            "sem motivo"
+        } else {
+            reason
+        };
+        println!("  • {}: {}", reason, count);
+    }
+
+    if !summary.process_down.is_empty() {
+        println!("Quedas de processos monitorados:");
+        for (name, count) in &summary.process_down {
+            println!("  • {}: {}", name, count);
+        }
+    }
+
+    println!(
+        "Logins: {} • falhas de autenticação: {} SSH, {} sudo",
+        summary.logins, summary.ssh_failures, summary.sudo_failures
+    );
+
+    if !summary.file_changes.is_empty() {
+        println!("Arquivos alterados:");
+        for (path, count) in &summary.file_changes {
+            println!("  • {}: {}", path, count);
+        }
+    }
+
+    if !summary.ports_opened.is_empty() {
+        println!("Portas abertas:");
+        for (port, count) in &summary.ports_opened {
+            println!("  • {}: {}", port, count);
+        }
+    }
+
+    if !summary.package_changes.is_empty() {
+        println!("Pacotes alterados:");
+        for (change, count) in &summary.package_changes {
+            println!("  • {}: {}", change, count);
+        }
+    }
+
+    if !summary.units_failed.is_empty() {
+        println!("Unidades em falha:");
+        for (unit, count) in &summary.units_failed {
+            println!("  • {}: {}", unit, count);
+        }
+    }
+
+    println!("Erros: {}", summary.errors.len());
+    for error in summary.errors.iter().rev().take(10) {
+        println!("  • {} {}", error.datetime, error.error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn fixture_log() -> Vec<Record> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/logs/query.log");
+        read_records(&path).unwrap()
+    }
+
+    fn range(since: Option<u64>, until: Option<u64>) -> QueryOptions {
+        QueryOptions {
+            since,
+            until,
+            file: None,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn time_arguments() {
+        assert_eq!(parse_time("1699990000", NOW), Ok(1_699_990_000));
+        assert_eq!(parse_time("90s", NOW), Ok(NOW - 90));
+        assert_eq!(parse_time("30m", NOW), Ok(NOW - 1800));
+        assert_eq!(parse_time("6h", NOW), Ok(NOW - 6 * 3600));
+        assert_eq!(parse_time("2d", NOW), Ok(NOW - 2 * 86400));
+        // Valores relativos maiores que o tempo decorrido param em zero
+        assert_eq!(parse_time("99999999999d", NOW), Ok(0));
+        assert_eq!(parse_time("2024-05-01", NOW), Ok(1_714_521_600));
+        assert_eq!(
+            parse_time("2024-05-01T12:00:00-03:00", NOW),
+            Ok(1_714_575_600)
+        );
+        for invalid in ["ontem", "5x", "m", "2024-13-01", ""] {
+            assert!(parse_time(invalid, NOW).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn arguments_need_values() {
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let options = parse_args(&args(&["--since", "1000", "--until", "2000", "--json"])).unwrap();
+        assert_eq!((options.since, options.until), (Some(1000), Some(2000)));
+        assert!(options.json);
+        assert!(parse_args(&args(&["--since"])).is_err());
+        assert!(parse_args(&args(&["--verbose"])).is_err());
+    }
+
+    #[test]
+    fn p95_uses_nearest_rank() {
+        assert!(summarize(Vec::new()).is_none());
+
+        let single = summarize(vec![7.0]).unwrap();
+        assert_eq!((single.min, single.p95, single.max), (7.0, 7.0, 7.0));
+
+        // 20 valores: posição ceil(0.95 * 20) = 19
+        let values: Vec<f64> = (1..=20).rev().map(f64::from).collect();
+        let summary = summarize(values).unwrap();
+        assert_eq!(summary.p95, 19.0);
+        assert_eq!(summary.avg, 10.5);
+
+        // 4 valores: posição ceil(3.8) = 4, o máximo
+        let summary = summarize(vec![10.0, 90.0, 20.0, 40.0]).unwrap();
+        assert_eq!((summary.min, summary.p95, summary.avg), (10.0, 90.0, 40.0));
+    }
+
+    #[test]
+    fn whole_log_summary() {
+        let records = fixture_log();
+        // A linha corrompida é ignorada
+        assert_eq!(records.len(), 10);
+
+        let summary = summarize_records(&records, &range(None, None));
+        assert_eq!(summary.snapshots, 4);
+        assert_eq!(summary.starts, 3);
+        // O terceiro STARTED não tem STOPPED antes (RELOADED não conta)
+        assert_eq!(summary.unclean_starts, 1);
+        assert_eq!(summary.stops.get("SIGTERM"), Some(&1));
+        assert_eq!(summary.errors.len(), 1);
+
+        let cpu = &summary.metrics["cpu_percent"];
+        assert_eq!((cpu.min, cpu.max, cpu.p95), (10.0, 90.0, 90.0));
+        let memory = &summary.metrics["memory_percent"];
+        assert_eq!((memory.min, memory.max), (50.0, 80.0));
+    }
+
+    #[test]
+    fn restarts_are_counted_in_the_range() {
+        let records = fixture_log();
+
+        let before_crash = summarize_records(&records, &range(None, Some(NOW + 1999)));
+        assert_eq!((before_crash.starts, before_crash.unclean_starts), (1, 0));
+        assert_eq!(before_crash.snapshots, 2);
+
+        // O status anterior ao intervalo também é considerado
+        let after = summarize_records(&records, &range(Some(NOW + 2500), None));
+        assert_eq!((after.starts, after.unclean_starts), (1, 1));
+        assert!(after.stops.is_empty());
+        assert_eq!(after.since.as_deref(), Some("2023-11-14T22:55:00Z"));
+    }
+}
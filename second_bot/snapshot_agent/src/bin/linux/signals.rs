@@ -0,0 +1,51 @@
+use crate::control::SnapshotReply;
+use signal_hook::consts::signal::{SIGHUP, SIGINT, SIGTERM, SIGUSR1};
+use signal_hook::iterator::Signals;
+use std::sync::mpsc::Sender;
+use std::thread::spawn;
+
+/// =========================================================================
+/// TRATAMENTO DE SINAIS
+/// =========================================================================
+/// Os sinais são recebidos em uma thread dedicada e repassados ao loop
+/// principal como eventos, evitando trabalho dentro do handler de sinal
+
+/// Eventos entregues ao loop principal do agente
+pub enum AgentEvent {
+    Terminate(&'static str), // Encerramento solicitado (nome do sinal)
+    Reload,                  // Recarregar configuração (SIGHUP)
+    // Snapshot imediato (SIGUSR1 ou socket de controle), com a origem e,
+    // se alguém aguarda o resultado, o canal da resposta
+    Snapshot(&'static str, Option<SnapshotReply>),
+}
+
+/// Converte o número do sinal em seu nome para registro no log
+fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        SIGTERM => "SIGTERM",
+        SIGINT => "SIGINT",
+        SIGHUP => "SIGHUP",
+        SIGUSR1 => "SIGUSR1",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Instala os handlers de SIGTERM, SIGINT, SIGHUP e SIGUSR1
+/// Cada sinal recebido é enviado pelo canal como um `AgentEvent`
+pub fn spawn_signal_listener(tx: Sender<AgentEvent>) {
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP, SIGUSR1])
+        .expect("Erro ao configurar handlers de sinais");
+
+    spawn(move || {
+        for signal in signals.forever() {
+            let event = match signal {
+                SIGHUP => AgentEvent::Reload,
+                SIGUSR1 => AgentEvent::Snapshot(signal_name(signal), None),
+                other => AgentEvent::Terminate(signal_name(other)),
+            };
+            if tx.send(event).is_err() {
+                break; // Loop principal já encerrou
+            }
+        }
+    });
+}
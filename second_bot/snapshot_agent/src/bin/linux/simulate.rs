@@ -0,0 +1,502 @@
+use chrono::{SecondsFormat, TimeZone, Utc};
+use dirs_next::home_dir;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use snapshot_schema::{
+    AgentOverhead, AgentStatus, DiskIo, HostInventory, Inventory, ListeningSocket, Pressure,
+    PressureAverages, PressureStall, Record, ServiceStatus, Snapshot, SystemHealth, SCHEMA_VERSION,
+};
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// =========================================================================
+/// SIMULADOR
+/// =========================================================================
+/// Subcomando `simulate`: grava registros sintéticos (STARTED, INVENTORY,
+/// SNAPSHOT, STOPPED) com o mesmo formato do agente real, em tempo acelerado,
+/// para desenvolver painéis e alertas sem depender de VMs.
+///
+///   snapshot_agent simulate [--output CAMINHO] [--profile PERFIL]
+///       [--duration D] [--interval D] [--start T] [--speed N]
+///       [--spikes N] [--restarts N] [--disk-fill D] [--seed N]
+///       [--hostname NOME]
+///
+/// Sem `--speed` todo o período é gerado de uma vez, terminando no momento
+/// atual; com `--speed N` os registros são gravados à medida que o tempo
+/// simulado passa, N vezes mais rápido que o real.
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Perfil de carga da VM simulada
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Profile {
+    Idle,    // CPU ~3%, memória ~25%
+    Steady,  // CPU ~35%, memória ~55%
+    Busy,    // CPU ~80%, memória ~80%
+    Diurnal, // Ciclo diário: pico às 14h, vale de madrugada
+}
+
+impl Profile {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "idle" => Ok(Profile::Idle),
+            "steady" => Ok(Profile::Steady),
+            "busy" => Ok(Profile::Busy),
+            "diurnal" => Ok(Profile::Diurnal),
+            other => Err(format!(
+                "Perfil desconhecido: {} (use idle, steady, busy ou diurnal)",
+                other
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Profile::Idle => "idle",
+            Profile::Steady => "steady",
+            Profile::Busy => "busy",
+            Profile::Diurnal => "diurnal",
+        }
+    }
+
+    /// CPU e memória (%) em torno das quais o valor oscila no instante dado
+    fn targets(&self, timestamp: u64) -> (f64, f64) {
+        match self {
+            Profile::Idle => (3.0, 25.0),
+            Profile::Steady => (35.0, 55.0),
+            Profile::Busy => (80.0, 80.0),
+            Profile::Diurnal => {
+                // Hora do dia em UTC; cosseno com máximo às 14h
+                let hour = (timestamp % 86400) as f64 / 3600.0;
+                let load = (1.0 + ((hour - 14.0) / 24.0 * 2.0 * PI).cos()) / 2.0;
+                (10.0 + 60.0 * load, 40.0 + 25.0 * load)
+            }
+        }
+    }
+
+    /// Memória total da VM simulada
+    fn total_memory(&self) -> u64 {
+        match self {
+            Profile::Idle => 2 * GIB,
+            Profile::Steady | Profile::Diurnal => 8 * GIB,
+            Profile::Busy => 16 * GIB,
+        }
+    }
+}
+
+/// Opções do subcomando simulate
+pub struct SimulateOptions {
+    pub output: Option<PathBuf>, // None: saída padrão (`--output -`)
+    pub profile: Profile,
+    pub duration_secs: u64, // Período simulado
+    pub interval_secs: u64, // Intervalo entre snapshots simulados
+    pub start: Option<u64>, // Padrão: agora - duração (ou agora, com --speed)
+    pub speed: f64,         // 0: sem espera
+    pub spikes_per_day: f64,
+    pub restarts_per_day: f64,
+    pub disk_fill_secs: Option<u64>, // Tempo até o disco encher
+    pub seed: Option<u64>,
+    pub hostname: Option<String>,
+}
+
+/// Retorna o caminho padrão do log simulado
+/// Localizado em ~/.snapshot_agent/simulated.log
+pub fn get_default_output() -> PathBuf {
+    let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".snapshot_agent");
+    path.push("simulated.log");
+    path
+}
+
+/// Interpreta os argumentos após `simulate`
+pub fn parse_args(args: &[String]) -> Result<SimulateOptions, String> {
+    let mut options = SimulateOptions {
+        output: Some(get_default_output()),
+        profile: Profile::Steady,
+        duration_secs: 86400,
+        interval_secs: 30,
+        start: None,
+        speed: 0.0,
+        spikes_per_day: 2.0,
+        restarts_per_day: 1.0,
+        disk_fill_secs: None,
+        seed: None,
+        hostname: None,
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("{} exige um valor", name))
+        };
+        let number = |name: &str, value: String| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite() && *n >= 0.0)
+                .ok_or_else(|| format!("{} inválido: {}", name, value))
+        };
+        match arg.as_str() {
+            "--output" => {
+                let path = value("--output")?;
+                options.output = (path != "-").then(|| PathBuf::from(path));
+            }
+            "--profile" => options.profile = Profile::parse(&value("--profile")?)?,
+            "--duration" => options.duration_secs = parse_duration(&value("--duration")?)?,
+            "--interval" => options.interval_secs = parse_duration(&value("--interval")?)?.max(1),
+            "--start" => {
+                let now = Utc::now().timestamp().max(0) as u64;
+                options.start = Some(crate::query::parse_time(&value("--start")?, now)?);
+            }
+            "--speed" => options.speed = number("--speed", value("--speed")?)?,
+            "--spikes" => options.spikes_per_day = number("--spikes", value("--spikes")?)?,
+            "--restarts" => options.restarts_per_day = number("--restarts", value("--restarts")?)?,
+            "--disk-fill" => options.disk_fill_secs = Some(parse_duration(&value("--disk-fill")?)?),
+            "--seed" => {
+                let seed = value("--seed")?;
+                options.seed = Some(
+                    seed.parse()
+                        .map_err(|_| format!("--seed inválido: {}", seed))?,
+                );
+            }
+            "--hostname" => options.hostname = Some(value("--hostname")?),
+            other => return Err(format!("Argumento desconhecido: {}", other)),
+        }
+    }
+    Ok(options)
+}
+
+/// Converte uma duração (90s, 30m, 6h, 2d ou segundos) em segundos
+fn parse_duration(value: &str) -> Result<u64, String> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(secs);
+    }
+    let multiplier = match value.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 3600,
+        Some('d') => 86400,
+        _ => return Err(format!("Duração inválida: {}", value)),
+    };
+    value[..value.len() - 1]
+        .parse::<u64>()
+        .map(|amount| amount.saturating_mul(multiplier))
+        .map_err(|_| format!("Duração inválida: {}", value))
+}
+
+/// Estado da VM simulada entre snapshots
+struct Machine {
+    rng: StdRng,
+    profile: Profile,
+    agent_id: String,
+    hostname: String,
+    cpu: f64,         // % atual (antes de picos)
+    memory: f64,      // % atual
+    disk: f64,        // % atual
+    disk_rate: f64,   // Crescimento do disco por segundo (pontos percentuais)
+    spike_until: u64, // Fim do pico de CPU em andamento
+    boot_time: u64,   // Boot simulado (para o INVENTORY)
+    pid: u32,         // PID do serviço simulado (muda a cada reinício)
+}
+
+impl Machine {
+    fn new(options: &SimulateOptions, start: u64) -> Self {
+        let mut rng = match options.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let agent_id = uuid::Builder::from_random_bytes(rng.gen())
+            .into_uuid()
+            .to_string();
+        let hostname = options
+            .hostname
+            .clone()
+            .unwrap_or_else(|| format!("sim-{}", options.profile.name()));
+        let (cpu, memory) = options.profile.targets(start);
+        let disk = rng.gen_range(30.0..50.0);
+        let disk_rate = options
+            .disk_fill_secs
+            .map(|secs| (100.0 - disk) / secs.max(1) as f64)
+            .unwrap_or(0.0);
+        let boot_time = start.saturating_sub(rng.gen_range(3600..30 * 86400));
+        let pid = rng.gen_range(300..5000);
+        Self {
+            rng,
+            profile: options.profile,
+            agent_id,
+            hostname,
+            cpu,
+            memory,
+            disk,
+            disk_rate,
+            spike_until: 0,
+            boot_time,
+            pid,
+        }
+    }
+
+    /// Amostra de uma normal padrão (Box-Muller)
+    fn gauss(&mut self) -> f64 {
+        let u1: f64 = self.rng.gen_range(f64::EPSILON..1.0);
+        let u2: f64 = self.rng.gen();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+
+    /// Avança o estado até `timestamp` e gera o snapshot
+    /// CPU e memória seguem um passeio aleatório puxado para o alvo do
+    /// perfil; o disco cresce devagar (ou até encher, com --disk-fill)
+    fn snapshot(&mut self, timestamp: u64, interval: u64, spikes_per_day: f64) -> Snapshot {
+        let (cpu_target, memory_target) = self.profile.targets(timestamp);
+        self.cpu += 0.3 * (cpu_target - self.cpu) + 4.0 * self.gauss();
+        self.memory += 0.1 * (memory_target - self.memory) + 0.8 * self.gauss();
+        self.cpu = self.cpu.clamp(0.5, 100.0);
+        self.memory = self.memory.clamp(5.0, 97.0);
+
+        if timestamp >= self.spike_until
+            && self
+                .rng
+                .gen_bool((spikes_per_day * interval as f64 / 86400.0).min(1.0))
+        {
+            self.spike_until = timestamp + self.rng.gen_range(120..600);
+        }
+        let spiking = timestamp < self.spike_until;
+        let cpu = if spiking {
+            self.rng.gen_range(92.0..100.0)
+        } else {
+            self.cpu
+        };
+
+        // Sem --disk-fill o disco ainda cresce um pouco (logs, cache)
+        let growth = if self.disk_rate > 0.0 {
+            self.disk_rate * interval as f64
+        } else {
+            0.0005 * interval as f64 / 60.0
+        };
+        self.disk = (self.disk + growth * self.rng.gen_range(0.5..1.5)).min(100.0);
+
+        let total_memory = self.profile.total_memory();
+        let total_disk = 50 * GIB;
+        let stall = |value: f64| PressureAverages {
+            avg10: value as f32,
+            avg60: (value * 0.7) as f32,
+            avg300: (value * 0.4) as f32,
+        };
+        let cpu_pressure = ((cpu - 70.0).max(0.0) / 1.5).min(40.0);
+        let memory_pressure = ((self.memory - 85.0).max(0.0) * 2.0).min(40.0);
+        let swap_total = 2 * GIB;
+        let swap_used = (swap_total as f64 * ((self.memory - 70.0).max(0.0) / 30.0)) as u64;
+        let writes = 5.0 + cpu * 0.8 + self.gauss().abs() * 5.0;
+        let mut disks = BTreeMap::new();
+        disks.insert(
+            "sda".to_string(),
+            DiskIo {
+                reads_per_sec: writes * 0.4,
+                writes_per_sec: writes,
+                read_bytes_per_sec: writes * 0.4 * 16384.0,
+                write_bytes_per_sec: writes * 32768.0,
+                busy_percent: (writes / 4.0).min(100.0) as f32,
+            },
+        );
+        let processes = 120 + (cpu / 2.0) as u64 + self.rng.gen_range(0..10);
+
+        Snapshot {
+            schema_version: SCHEMA_VERSION,
+            agent_id: self.agent_id.clone(),
+            hostname: self.hostname.clone(),
+            timestamp,
+            datetime: format_datetime(timestamp),
+            total_memory,
+            used_memory: (total_memory as f64 * self.memory / 100.0) as u64,
+            cpu_usage_percent: cpu as f32,
+            total_disk,
+            used_disk: (total_disk as f64 * self.disk / 100.0) as u64,
+            folder_files: Vec::new(),
+            folder_total: None,
+            folders: Vec::new(),
+            custom: BTreeMap::new(),
+            sessions: Vec::new(),
+            cgroup: None,
+            health: Some(SystemHealth {
+                swap_total,
+                swap_used,
+                pressure: Some(Pressure {
+                    cpu: Some(PressureStall {
+                        some: stall(cpu_pressure),
+                        full: None,
+                    }),
+                    memory: Some(PressureStall {
+                        some: stall(memory_pressure),
+                        full: Some(stall(memory_pressure / 2.0)),
+                    }),
+                    io: Some(PressureStall {
+                        some: stall((writes / 20.0).min(30.0)),
+                        full: Some(stall((writes / 40.0).min(15.0))),
+                    }),
+                }),
+                open_fds: 1500 + processes * 8,
+                max_fds: 1_048_576,
+                processes,
+                threads: processes * 3,
+                zombies: u64::from(self.rng.gen_bool(0.02)),
+                disks,
+            }),
+            overhead: Some(AgentOverhead {
+                cpu_percent: self.rng.gen_range(0.1..0.6),
+                cycle_cpu_ms: self.rng.gen_range(20..60),
+                rss_bytes: self.rng.gen_range(9..14) * 1024 * 1024,
+                throttled: false,
+                skipped: Vec::new(),
+            }),
+            listening: vec![ListeningSocket {
+                protocol: "tcp".to_string(),
+                address: "0.0.0.0".to_string(),
+                port: 22,
+                uid: 0,
+                pid: Some(self.pid),
+                process: Some("sshd".to_string()),
+            }],
+        }
+    }
+
+    fn service_status(&self, timestamp: u64, status: AgentStatus, reason: &str) -> Record {
+        Record::ServiceStatus(ServiceStatus {
+            schema_version: SCHEMA_VERSION,
+            agent_id: self.agent_id.clone(),
+            hostname: self.hostname.clone(),
+            datetime: format_datetime(timestamp),
+            timestamp,
+            status,
+            reason: Some(reason.to_string()),
+        })
+    }
+
+    fn inventory(&self, timestamp: u64) -> Record {
+        let cores = (self.profile.total_memory() / GIB / 2).max(1) as usize;
+        Record::Inventory(Inventory {
+            schema_version: SCHEMA_VERSION,
+            agent_id: self.agent_id.clone(),
+            hostname: self.hostname.clone(),
+            datetime: format_datetime(timestamp),
+            timestamp,
+            inventory: HostInventory {
+                os_release: "Simulated Linux (snapshot_agent simulate)".to_string(),
+                kernel: "6.1.0-sim".to_string(),
+                architecture: "x86_64".to_string(),
+                cpu_model: "Simulated CPU".to_string(),
+                cpu_cores: cores,
+                cpu_threads: cores * 2,
+                total_memory: self.profile.total_memory(),
+                boot_time: self.boot_time,
+                boot_datetime: format_datetime(self.boot_time),
+                uptime_secs: timestamp.saturating_sub(self.boot_time),
+                agent_version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+        })
+    }
+}
+
+fn format_datetime(timestamp: u64) -> String {
+    Utc.timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+/// Destino dos registros simulados
+struct Output {
+    file: Option<File>, // None: saída padrão
+    records: u64,
+}
+
+impl Output {
+    fn write(&mut self, record: Record) -> Result<(), String> {
+        let line = format!("{}\n", record.to_json());
+        let result = match &mut self.file {
+            Some(file) => file.write_all(line.as_bytes()),
+            None => io::stdout().write_all(line.as_bytes()),
+        };
+        self.records += 1;
+        result.map_err(|e| format!("Erro ao gravar registro simulado: {}", e))
+    }
+}
+
+/// Gera o período simulado
+/// Reinícios gravam STOPPED (ou nada, simulando uma queda), um intervalo sem
+/// snapshots e um novo STARTED + INVENTORY
+pub fn run(options: &SimulateOptions) -> Result<u64, String> {
+    let now = Utc::now().timestamp().max(0) as u64;
+    let start = options.start.unwrap_or(if options.speed > 0.0 {
+        now
+    } else {
+        now.saturating_sub(options.duration_secs)
+    });
+    let end = start.saturating_add(options.duration_secs);
+    let interval = options.interval_secs.max(1);
+
+    let file = match &options.output {
+        Some(path) => {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {:?}: {}", dir, e))?;
+            }
+            Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("Erro ao abrir {:?}: {}", path, e))?,
+            )
+        }
+        None => None,
+    };
+    let mut output = Output { file, records: 0 };
+    let mut machine = Machine::new(options, start);
+    let restart_chance = (options.restarts_per_day * interval as f64 / 86400.0).min(1.0);
+
+    output.write(machine.service_status(start, AgentStatus::Started, "simulator"))?;
+    output.write(machine.inventory(start))?;
+    let mut timestamp = start;
+    let mut last = start;
+    while timestamp <= end {
+        let snapshot = machine.snapshot(timestamp, interval, options.spikes_per_day);
+        output.write(Record::Snapshot(snapshot))?;
+
+        let mut next = timestamp + interval;
+        if machine.rng.gen_bool(restart_chance) {
+            // Um em cada quatro reinícios é uma queda (sem STOPPED)
+            if !machine.rng.gen_bool(0.25) {
+                output.write(machine.service_status(
+                    timestamp + 1,
+                    AgentStatus::Stopped,
+                    "SIGTERM",
+                ))?;
+            }
+            let restarted = timestamp + machine.rng.gen_range(interval..interval * 4 + 2);
+            machine.pid = machine.rng.gen_range(300..50000);
+            output.write(machine.service_status(restarted, AgentStatus::Started, "simulator"))?;
+            output.write(machine.inventory(restarted))?;
+            next = restarted + 1;
+        }
+        last = next - 1;
+
+        if options.speed > 0.0 {
+            // Quem acompanha o arquivo (tail, central_monitor) vê cada registro
+            let _ = match &mut output.file {
+                Some(file) => file.flush(),
+                None => io::stdout().flush(),
+            };
+            sleep(Duration::from_secs_f64(
+                next.saturating_sub(timestamp) as f64 / options.speed,
+            ));
+        }
+        timestamp = next;
+    }
+    output.write(machine.service_status(end.max(last), AgentStatus::Stopped, "simulator"))?;
+    Ok(output.records)
+}
@@ -0,0 +1,586 @@
+use crate::config::SinkConfig;
+use chrono::Utc;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, Write};
+use std::mem;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+
+/// =========================================================================
+/// SAÍDAS DOS REGISTROS (SINKS)
+/// =========================================================================
+/// Além do arquivo de log, cada registro pode ser enviado a outras saídas
+/// configuradas em `sinks`: saída padrão, syslog local (RFC 5424 pelo socket
+/// Unix), journald (protocolo nativo) e StatsD (UDP). Cada saída falha de
+/// forma independente: o erro é registrado e a coleta continua.
+
+/// Destino de registros
+pub trait Sink: Send {
+    /// Nome usado nas mensagens de erro
+    fn name(&self) -> String;
+
+    /// Envia um registro (`json` é a serialização de `record`)
+    fn write(&mut self, record: &Value, json: &str) -> Result<(), String>;
+}
+
+/// Cria as saídas configuradas (exceto o arquivo, mantido pelo escritor do log)
+/// Saídas que não puderem ser criadas são omitidas e o erro é devolvido
+pub fn build(configs: &[SinkConfig]) -> (Vec<Box<dyn Sink>>, Vec<String>) {
+    let mut sinks: Vec<Box<dyn Sink>> = Vec::new();
+    let mut errors = Vec::new();
+    for config in configs {
+        let sink: Result<Box<dyn Sink>, String> = match config {
+            SinkConfig::File => continue,
+            SinkConfig::Stdout => Ok(Box::new(StdoutSink)),
+            SinkConfig::Syslog {
+                socket,
+                facility,
+                app_name,
+            } => facility_code(facility).map(|facility| {
+                Box::new(SyslogSink {
+                    socket: socket.clone(),
+                    facility,
+                    app_name: app_name.clone(),
+                }) as Box<dyn Sink>
+            }),
+            SinkConfig::Journald { socket } => Ok(Box::new(JournaldSink {
+                socket: socket.clone(),
+            })),
+            SinkConfig::Statsd { address, prefix } => {
+                StatsdSink::new(address, prefix).map(|sink| Box::new(sink) as Box<dyn Sink>)
+            }
+        };
+        match sink {
+            Ok(sink) => sinks.push(sink),
+            Err(e) => errors.push(e),
+        }
+    }
+    (sinks, errors)
+}
+
+/// Campo de texto do registro
+fn field<'a>(record: &'a Value, name: &str) -> Option<&'a str> {
+    record.get(name).and_then(Value::as_str)
+}
+
+/// Severidade syslog de um registro: erros e eventos de alerta se destacam
+fn severity(record: &Value) -> u8 {
+    match field(record, "record_type").unwrap_or("") {
+        // err
+        "ERROR" => 3,
+        "UNIT_CHANGED" if field(record, "change") == Some("failed") => 4,
+        // warning
+        "PROCESS_DOWN" | "AUTH_FAILURES" | "FILE_CHANGED" | "PORT_OPENED" => 4,
+        // notice
+        "SERVICE_STATUS" | "SESSION_OPENED" | "SESSION_CLOSED" | "PROCESS_UP" | "PORT_CLOSED"
+        | "PACKAGE_CHANGED" | "UNIT_CHANGED" => 5,
+        // info
+        _ => 6,
+    }
+}
+
+// -------------------------------------------------------------------------
+// SAÍDA PADRÃO
+// -------------------------------------------------------------------------
+
+/// Uma linha JSON por registro na saída padrão (containers, pipes)
+/// O agente em execução escreve seus diagnósticos na saída de erro, então a
+/// saída padrão contém apenas registros
+struct StdoutSink;
+
+impl Sink for StdoutSink {
+    fn name(&self) -> String {
+        "stdout".to_string()
+    }
+
+    fn write(&mut self, _record: &Value, json: &str) -> Result<(), String> {
+        let mut out = io::stdout().lock();
+        writeln!(out, "{}", json)
+            .and_then(|_| out.flush())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// -------------------------------------------------------------------------
+// SYSLOG (RFC 5424)
+// -------------------------------------------------------------------------
+
+/// Tamanho máximo de uma mensagem syslog (padrão do rsyslog); o excedente
+/// é cortado, já que partes de um JSON não seriam úteis separadas
+const SYSLOG_MAX_MESSAGE: usize = 8192;
+
+/// Mensagens RFC 5424 enviadas ao socket do syslog local (/dev/log)
+struct SyslogSink {
+    socket: PathBuf,
+    facility: u8,
+    app_name: String,
+}
+
+/// Código da facility syslog pelo nome
+fn facility_code(name: &str) -> Result<u8, String> {
+    let code = match name {
+        "user" => 1,
+        "daemon" => 3,
+        "auth" => 4,
+        "syslog" => 5,
+        "local0" => 16,
+        "local1" => 17,
+        "local2" => 18,
+        "local3" => 19,
+        "local4" => 20,
+        "local5" => 21,
+        "local6" => 22,
+        "local7" => 23,
+        other => return Err(format!("Facility syslog desconhecida: {}", other)),
+    };
+    Ok(code)
+}
+
+impl Sink for SyslogSink {
+    fn name(&self) -> String {
+        format!("syslog ({})", self.socket.display())
+    }
+
+    /// <PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID - MSG
+    fn write(&mut self, record: &Value, json: &str) -> Result<(), String> {
+        let nil = |value: Option<&str>| match value {
+            Some(value) if !value.is_empty() => value.replace(' ', "_"),
+            _ => "-".to_string(),
+        };
+        let mut message = format!(
+            "<{}>1 {} {} {} {} {} - {}",
+            self.facility * 8 + severity(record),
+            Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            nil(field(record, "hostname")),
+            nil(Some(&self.app_name)),
+            std::process::id(),
+            nil(field(record, "record_type")),
+            json
+        );
+        truncate_at_char(&mut message, SYSLOG_MAX_MESSAGE);
+        let socket = UnixDatagram::unbound().map_err(|e| e.to_string())?;
+        socket
+            .send_to(message.as_bytes(), &self.socket)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// Corta o texto em até `max` bytes sem partir um caractere UTF-8
+fn truncate_at_char(text: &mut String, max: usize) {
+    if text.len() <= max {
+        return;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+// -------------------------------------------------------------------------
+// JOURNALD
+// -------------------------------------------------------------------------
+
+/// Protocolo nativo do journald: campos `CHAVE=valor` em um datagrama
+/// Registros maiores que o limite do socket vão em um memfd selado, passado
+/// por SCM_RIGHTS (o mesmo caminho do sd_journal_send)
+struct JournaldSink {
+    socket: PathBuf,
+}
+
+impl Sink for JournaldSink {
+    fn name(&self) -> String {
+        format!("journald ({})", self.socket.display())
+    }
+
+    fn write(&mut self, record: &Value, json: &str) -> Result<(), String> {
+        let mut datagram = Vec::new();
+        let mut add = |key: &str, value: &str| {
+            if value.contains('\n') {
+                // Valores com quebra de linha: nome, tamanho (u64 LE) e bytes
+                datagram.extend_from_slice(key.as_bytes());
+                datagram.push(b'\n');
+                datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+                datagram.extend_from_slice(value.as_bytes());
+            } else {
+                datagram.extend_from_slice(format!("{}={}", key, value).as_bytes());
+            }
+            datagram.push(b'\n');
+        };
+        add("MESSAGE", json);
+        add("PRIORITY", &severity(record).to_string());
+        add("SYSLOG_IDENTIFIER", "snapshot_agent");
+        for (key, name) in [
+            ("SNAPSHOT_RECORD_TYPE", "record_type"),
+            ("SNAPSHOT_AGENT_ID", "agent_id"),
+        ] {
+            if let Some(value) = field(record, name) {
+                add(key, value);
+            }
+        }
+
+        let socket = UnixDatagram::unbound().map_err(|e| e.to_string())?;
+        match socket.send_to(&datagram, &self.socket) {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EMSGSIZE | libc::ENOBUFS)) => socket
+                .connect(&self.socket)
+                .and_then(|_| send_memfd(&socket, &datagram))
+                .map_err(|e| format!("registro grande demais (memfd): {}", e)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Grava o conteúdo em um memfd selado e envia o descritor ao socket
+/// conectado, em um datagrama vazio
+fn send_memfd(socket: &UnixDatagram, payload: &[u8]) -> io::Result<()> {
+    // SAFETY: o nome é uma string C terminada em zero
+    let fd = unsafe {
+        libc::memfd_create(
+            c"snapshot_agent".as_ptr(),
+            libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: o descritor acabou de ser criado e passa a pertencer ao File
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    file.write_all(payload)?;
+    // O journald só aceita o memfd selado (conteúdo imutável)
+    let seals = libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+    // SAFETY: fcntl sobre um descritor válido
+    if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    send_fd(socket, file.as_raw_fd())
+}
+
+/// Envia um descritor (SCM_RIGHTS) sem dados
+fn send_fd(socket: &UnixDatagram, fd: RawFd) -> io::Result<()> {
+    // SAFETY: CMSG_SPACE apenas calcula um tamanho
+    let space = unsafe { libc::CMSG_SPACE(mem::size_of::<RawFd>() as u32) } as usize;
+    // u64 garante o alinhamento exigido pelo cmsghdr
+    let mut control = vec![0u64; space.div_ceil(mem::size_of::<u64>())];
+    // SAFETY: msghdr é uma struct C simples; zerada, não tem nome nem dados
+    let mut message: libc::msghdr = unsafe { mem::zeroed() };
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = space as _;
+    // SAFETY: o buffer de controle tem espaço para um cmsghdr com um descritor
+    unsafe {
+        let header = libc::CMSG_FIRSTHDR(&message);
+        (*header).cmsg_level = libc::SOL_SOCKET;
+        (*header).cmsg_type = libc::SCM_RIGHTS;
+        (*header).cmsg_len = libc::CMSG_LEN(mem::size_of::<RawFd>() as u32) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(header).cast::<RawFd>(), fd);
+    }
+    // SAFETY: message aponta para buffers válidos até o fim da chamada
+    if unsafe { libc::sendmsg(socket.as_raw_fd(), &message, 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// -------------------------------------------------------------------------
+// STATSD (UDP)
+// -------------------------------------------------------------------------
+
+/// Tamanho máximo de um pacote StatsD (cabe no MTU de redes comuns)
+const STATSD_MAX_PACKET: usize = 1400;
+
+/// Métricas dos snapshots como gauges e um contador por tipo de registro
+struct StatsdSink {
+    socket: UdpSocket,
+    address: SocketAddr,
+    prefix: String,
+}
+
+impl StatsdSink {
+    fn new(address: &str, prefix: &str) -> Result<Self, String> {
+        let resolved = address
+            .to_socket_addrs()
+            .map_err(|e| format!("Endereço StatsD inválido {}: {}", address, e))?
+            .next()
+            .ok_or_else(|| format!("Endereço StatsD sem resolução: {}", address))?;
+        let bind = if resolved.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket =
+            UdpSocket::bind(bind).map_err(|e| format!("Erro ao abrir socket UDP: {}", e))?;
+        Ok(Self {
+            socket,
+            address: resolved,
+            prefix: prefix.trim_end_matches('.').to_string(),
+        })
+    }
+
+    /// Linhas `nome:valor|tipo` de um registro
+    fn metrics(&self, record: &Value) -> Vec<String> {
+        // Pontos no hostname criariam níveis extras na hierarquia
+        let host = field(record, "hostname")
+            .unwrap_or("unknown")
+            .replace('.', "_");
+        let base = format!("{}.{}", self.prefix, host);
+        let record_type = field(record, "record_type").unwrap_or("UNKNOWN");
+        let mut lines = vec![format!(
+            "{}.records.{}:1|c",
+            base,
+            record_type.to_lowercase()
+        )];
+        if record_type != "SNAPSHOT" {
+            return lines;
+        }
+
+        let gauges = [
+            ("memory.total", "/total_memory"),
+            ("memory.used", "/used_memory"),
+            ("cpu.percent", "/cpu_usage_percent"),
+            ("disk.total", "/total_disk"),
+            ("disk.used", "/used_disk"),
+            ("swap.used", "/health/swap_used"),
+            ("fds.open", "/health/open_fds"),
+            ("processes", "/health/processes"),
+            ("threads", "/health/threads"),
+            ("zombies", "/health/zombies"),
+            ("pressure.cpu", "/health/pressure/cpu/some/avg10"),
+            ("pressure.memory", "/health/pressure/memory/some/avg10"),
+            ("pressure.io", "/health/pressure/io/some/avg10"),
+            ("cgroup.memory.used", "/cgroup/memory_usage"),
+            ("cgroup.cpu.percent", "/cgroup/cpu_usage_percent"),
+        ];
+        for (name, pointer) in gauges {
+            if let Some(value) = record.pointer(pointer).and_then(Value::as_f64) {
+                lines.push(format!("{}.{}:{}|g", base, name, value));
+            }
+        }
+        lines
+    }
+
+    fn send(&self, packet: &str) -> Result<(), String> {
+        self.socket
+            .send_to(packet.as_bytes(), self.address)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Sink for StatsdSink {
+    fn name(&self) -> String {
+        format!("statsd ({})", self.address)
+    }
+
+    fn write(&mut self, record: &Value, _json: &str) -> Result<(), String> {
+        // Várias métricas por pacote, separadas por quebra de linha
+        let mut packet = String::new();
+        for line in self.metrics(record) {
+            if !packet.is_empty() && packet.len() + line.len() + 1 > STATSD_MAX_PACKET {
+                self.send(&packet)?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            self.send(&packet)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::os::unix::fs::FileExt;
+    use std::path::Path;
+    use std::time::Duration;
+
+    /// Socket Unix de datagramas em um diretório temporário
+    fn bound_socket(name: &str) -> (UnixDatagram, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("sinks-{}-test-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("socket");
+        let socket = UnixDatagram::bind(&path).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        (socket, path)
+    }
+
+    fn receive(socket: &UnixDatagram) -> String {
+        let mut buffer = vec![0u8; 65536];
+        let size = socket.recv(&mut buffer).unwrap();
+        String::from_utf8(buffer[..size].to_vec()).unwrap()
+    }
+
+    fn cleanup(path: &Path) {
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn severity_follows_the_record_type() {
+        let record = |record_type: &str| json!({"record_type": record_type});
+        assert_eq!(severity(&record("ERROR")), 3);
+        assert_eq!(severity(&record("PROCESS_DOWN")), 4);
+        assert_eq!(severity(&record("PORT_OPENED")), 4);
+        assert_eq!(severity(&record("SESSION_OPENED")), 5);
+        assert_eq!(severity(&record("UNIT_CHANGED")), 5);
+        assert_eq!(
+            severity(&json!({"record_type": "UNIT_CHANGED", "change": "failed"})),
+            4
+        );
+        assert_eq!(severity(&record("SNAPSHOT")), 6);
+        assert_eq!(severity(&json!({})), 6);
+    }
+
+    #[test]
+    fn syslog_sends_rfc5424_messages() {
+        let (server, path) = bound_socket("syslog");
+        let mut sink = SyslogSink {
+            socket: path.clone(),
+            facility: facility_code("daemon").unwrap(),
+            app_name: "snapshot agent".to_string(),
+        };
+        let record = json!({"record_type": "ERROR", "hostname": "vm1"});
+        sink.write(&record, r#"{"record_type":"ERROR"}"#).unwrap();
+
+        // <PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID - MSG
+        let message = receive(&server);
+        let fields: Vec<&str> = message.splitn(8, ' ').collect();
+        assert_eq!(fields[0], "<27>1"); // daemon (3) * 8 + err (3)
+        assert!(chrono::DateTime::parse_from_rfc3339(fields[1]).is_ok());
+        assert_eq!(fields[2], "vm1");
+        assert_eq!(fields[3], "snapshot_agent");
+        assert_eq!(fields[4], std::process::id().to_string());
+        assert_eq!(fields[5], "ERROR");
+        assert_eq!(fields[6], "-");
+        assert_eq!(fields[7], r#"{"record_type":"ERROR"}"#);
+
+        // Mensagens grandes são cortadas no limite
+        let long = format!("\"{}\"", "é".repeat(SYSLOG_MAX_MESSAGE));
+        sink.write(&json!({}), &long).unwrap();
+        let message = receive(&server);
+        assert!(message.len() <= SYSLOG_MAX_MESSAGE);
+        assert!(message.starts_with("<30>1 ")); // daemon * 8 + info
+        cleanup(&path);
+    }
+
+    /// Campos `CHAVE=valor` de um datagrama do journald (sem valores binários)
+    fn journal_fields(datagram: &[u8]) -> Vec<(String, String)> {
+        String::from_utf8_lossy(datagram)
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn journald_sends_native_fields() {
+        let (server, path) = bound_socket("journal");
+        let mut sink = JournaldSink {
+            socket: path.clone(),
+        };
+        let record = json!({"record_type": "PROCESS_DOWN", "agent_id": "abc"});
+        sink.write(&record, "{}").unwrap();
+        let fields = journal_fields(receive(&server).as_bytes());
+        let expected = [
+            ("MESSAGE", "{}"),
+            ("PRIORITY", "4"),
+            ("SYSLOG_IDENTIFIER", "snapshot_agent"),
+            ("SNAPSHOT_RECORD_TYPE", "PROCESS_DOWN"),
+            ("SNAPSHOT_AGENT_ID", "abc"),
+        ];
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(fields, expected);
+        cleanup(&path);
+    }
+
+    /// Recebe um datagrama vazio com um descritor (SCM_RIGHTS)
+    fn receive_fd(socket: &UnixDatagram) -> File {
+        let space = unsafe { libc::CMSG_SPACE(mem::size_of::<RawFd>() as u32) } as usize;
+        let mut control = vec![0u64; space.div_ceil(mem::size_of::<u64>())];
+        let mut message: libc::msghdr = unsafe { mem::zeroed() };
+        message.msg_control = control.as_mut_ptr().cast();
+        message.msg_controllen = space as _;
+        let received = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut message, 0) };
+        assert_eq!(received, 0);
+        unsafe {
+            let header = libc::CMSG_FIRSTHDR(&message);
+            assert!(!header.is_null());
+            assert_eq!((*header).cmsg_type, libc::SCM_RIGHTS);
+            let fd = std::ptr::read_unaligned(libc::CMSG_DATA(header).cast::<RawFd>());
+            File::from_raw_fd(fd)
+        }
+    }
+
+    #[test]
+    fn journald_sends_large_records_through_memfd() {
+        let (server, path) = bound_socket("journal-large");
+        let mut sink = JournaldSink {
+            socket: path.clone(),
+        };
+        // Bem acima do buffer de envio padrão de um socket Unix
+        let json = format!("\"{}\"", "x".repeat(4 * 1024 * 1024));
+        sink.write(&json!({"record_type": "SNAPSHOT"}), &json)
+            .unwrap();
+
+        let memfd = receive_fd(&server);
+        let size = memfd.metadata().unwrap().len() as usize;
+        let mut content = vec![0u8; size];
+        memfd.read_exact_at(&mut content, 0).unwrap();
+        let fields = journal_fields(&content);
+        assert_eq!(fields[0], ("MESSAGE".to_string(), json));
+        assert_eq!(fields[1], ("PRIORITY".to_string(), "6".to_string()));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn statsd_sends_counters_and_snapshot_gauges() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        let mut sink = StatsdSink::new(&address, "snapshot.").unwrap();
+
+        let snapshot = json!({
+            "record_type": "SNAPSHOT",
+            "hostname": "vm1.lab",
+            "total_memory": 1000,
+            "used_memory": 600,
+            "cpu_usage_percent": 12.5,
+            "health": {"pressure": {"cpu": {"some": {"avg10": 0.5}}}}
+        });
+        sink.write(&snapshot, "").unwrap();
+        let mut buffer = [0u8; STATSD_MAX_PACKET];
+        let size = server.recv(&mut buffer).unwrap();
+        let packet = String::from_utf8(buffer[..size].to_vec()).unwrap();
+        assert_eq!(
+            packet.lines().collect::<Vec<_>>(),
+            vec![
+                "snapshot.vm1_lab.records.snapshot:1|c",
+                "snapshot.vm1_lab.memory.total:1000|g",
+                "snapshot.vm1_lab.memory.used:600|g",
+                "snapshot.vm1_lab.cpu.percent:12.5|g",
+                "snapshot.vm1_lab.pressure.cpu:0.5|g",
+            ]
+        );
+
+        // Outros registros geram apenas o contador
+        sink.write(&json!({"record_type": "ERROR", "hostname": "vm1"}), "")
+            .unwrap();
+        let size = server.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..size], b"snapshot.vm1.records.error:1|c");
+    }
+}
@@ -0,0 +1,51 @@
+use sd_notify::NotifyState;
+use std::time::Duration;
+
+/// =========================================================================
+/// INTEGRAÇÃO COM SYSTEMD (sd_notify)
+/// =========================================================================
+/// Permite que o serviço use Type=notify e WatchdogSec=. Fora do systemd
+/// (NOTIFY_SOCKET ausente) todas as chamadas são inofensivas.
+
+/// Informa ao systemd que o agente terminou a inicialização
+pub fn notify_ready() {
+    let _ = sd_notify::notify(false, &[NotifyState::Ready]);
+}
+
+/// Informa ao systemd que a configuração está sendo recarregada
+pub fn notify_reloading() {
+    match NotifyState::monotonic_usec_now() {
+        Ok(now) => {
+            let _ = sd_notify::notify(false, &[NotifyState::Reloading, now]);
+        }
+        Err(_) => {
+            let _ = sd_notify::notify(false, &[NotifyState::Reloading]);
+        }
+    }
+}
+
+/// Informa ao systemd que o agente está encerrando
+pub fn notify_stopping() {
+    let _ = sd_notify::notify(false, &[NotifyState::Stopping]);
+}
+
+/// Atualiza a linha de status exibida por `systemctl status`
+pub fn notify_status(status: &str) {
+    let _ = sd_notify::notify(false, &[NotifyState::Status(status)]);
+}
+
+/// Envia o ping do watchdog
+pub fn ping_watchdog() {
+    let _ = sd_notify::notify(false, &[NotifyState::Watchdog]);
+}
+
+/// Retorna o intervalo entre pings do watchdog, se habilitado
+/// Usa metade do WatchdogSec configurado, como recomendado pelo systemd
+pub fn watchdog_interval() -> Option<Duration> {
+    let mut usec = 0;
+    if sd_notify::watchdog_enabled(false, &mut usec) && usec > 0 {
+        Some(Duration::from_micros(usec / 2))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,571 @@
+use crate::config::UpdateConfig;
+use chrono::{SecondsFormat, Utc};
+use dirs_next::home_dir;
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snapshot_agent::command;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread::{self, sleep, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// =========================================================================
+/// ATUALIZAÇÃO DO AGENTE
+/// =========================================================================
+/// A fonte (`update.source` ou `--source`) é um diretório local ou uma URL
+/// base (http/https, baixada com curl) com os arquivos:
+///   snapshot_agent_linux         - binário novo
+///   snapshot_agent_linux.sha256  - SHA-256 do binário (formato do sha256sum)
+///   snapshot_agent_linux.minisig - assinatura minisign (exigida com public_key)
+///
+/// Uma fonte remota exige `update.public_key`: o .sha256 vem do mesmo
+/// servidor que o binário e não protege contra quem controla a fonte (ou,
+/// em http://, a rede). Só um diretório local dispensa a assinatura.
+///
+/// Se o hash publicado for igual ao do binário instalado, nada é feito. Senão
+/// o binário é baixado, conferido, testado (`--version`) e trocado por rename
+/// no mesmo diretório; a versão anterior fica em `<binário>.previous`.
+///
+/// O marcador ~/.snapshot_agent/update_pending.json fica até o primeiro
+/// snapshot do binário novo. Se esse snapshot falhar, ou o binário novo
+/// iniciar MAX_ATTEMPTS vezes sem concluí-lo, a versão anterior é restaurada.
+
+/// Nome dos arquivos publicados na fonte
+const ARTIFACT: &str = "snapshot_agent_linux";
+
+/// Inicializações do binário novo sem confirmação antes do rollback
+const MAX_ATTEMPTS: u32 = 2;
+
+/// Tempo máximo de cada download (segundos)
+const DOWNLOAD_TIMEOUT_SECS: u64 = 300;
+
+/// Atualização instalada aguardando o primeiro snapshot
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PendingUpdate {
+    pub binary: PathBuf,   // Caminho do binário instalado
+    pub previous: PathBuf, // Cópia da versão anterior
+    pub sha256: String,    // Hash do binário novo
+    pub installed_at: String,
+    pub attempts: u32, // Inicializações do binário novo sem confirmação
+}
+
+/// Resultado de uma verificação de atualização
+pub enum UpdateOutcome {
+    UpToDate(String), // Hash do binário instalado
+    Installed(PendingUpdate),
+}
+
+/// Situação encontrada na inicialização do agente
+pub enum StartupState {
+    Normal,
+    Pending(PendingUpdate), // Binário novo: confirmar após o primeiro snapshot
+    RolledBack(String),     // Falhas repetidas: versão anterior restaurada
+    RollbackFailed(String), // Falhas repetidas, mas a restauração falhou
+}
+
+/// Verificação periódica em segundo plano (`update.check_interval_secs`)
+/// O download e o reinício não bloqueiam a coleta
+pub struct UpdateChecker {
+    handle: Option<JoinHandle<Result<UpdateOutcome, String>>>,
+    next_check: Instant,
+    last_error: Option<String>, // Evita repetir o mesmo erro a cada verificação
+}
+
+impl UpdateChecker {
+    pub fn new() -> Self {
+        Self {
+            handle: None,
+            next_check: Instant::now(),
+            last_error: None,
+        }
+    }
+
+    /// Inicia uma verificação quando chega a hora e devolve o resultado da
+    /// anterior, se já terminou; erros iguais ao anterior são omitidos
+    pub fn poll(&mut self, config: &UpdateConfig) -> Option<Result<UpdateOutcome, String>> {
+        if self
+            .handle
+            .as_ref()
+            .is_some_and(|handle| handle.is_finished())
+        {
+            let result = self
+                .handle
+                .take()
+                .and_then(|handle| handle.join().ok())
+                .unwrap_or_else(|| Err("Verificação de atualização interrompida".to_string()));
+            return match result {
+                Err(e) if self.last_error.as_ref() == Some(&e) => None,
+                Err(e) => {
+                    self.last_error = Some(e.clone());
+                    Some(Err(e))
+                }
+                Ok(outcome) => {
+                    self.last_error = None;
+                    Some(Ok(outcome))
+                }
+            };
+        }
+
+        if self.handle.is_some()
+            || config.check_interval_secs == 0
+            || config.source.is_none()
+            || Instant::now() < self.next_check
+        {
+            return None;
+        }
+        self.next_check = Instant::now() + Duration::from_secs(config.check_interval_secs);
+        let config = config.clone();
+        self.handle = Some(thread::spawn(move || {
+            let binary = current_binary()?;
+            let outcome = install(&config, None, &binary)?;
+            if let UpdateOutcome::Installed(_) = &outcome {
+                // O gerenciador de serviços encerra este processo e inicia o novo
+                restart(&config)?;
+            }
+            Ok(outcome)
+        }));
+        None
+    }
+}
+
+/// Retorna o caminho do marcador de atualização pendente
+/// Localizado em ~/.snapshot_agent/update_pending.json
+pub fn get_marker_path() -> PathBuf {
+    let mut path = home_dir().expect("Não foi possível obter a home do usuário");
+    path.push(".snapshot_agent");
+    path.push("update_pending.json");
+    path
+}
+
+/// Caminho do binário em execução
+/// Se o arquivo já foi trocado, o kernel acrescenta " (deleted)" ao link
+pub fn current_binary() -> Result<PathBuf, String> {
+    let path = std::env::current_exe()
+        .map_err(|e| format!("Não foi possível localizar o binário: {}", e))?;
+    let text = path.to_string_lossy();
+    Ok(match text.strip_suffix(" (deleted)") {
+        Some(original) => PathBuf::from(original),
+        None => path,
+    })
+}
+
+/// Procura e instala uma versão nova
+pub fn install(
+    config: &UpdateConfig,
+    source: Option<&str>,
+    binary: &Path,
+) -> Result<UpdateOutcome, String> {
+    install_with_marker(config, source, binary, &get_marker_path())
+}
+
+fn install_with_marker(
+    config: &UpdateConfig,
+    source: Option<&str>,
+    binary: &Path,
+    marker: &Path,
+) -> Result<UpdateOutcome, String> {
+    let source = source
+        .or(config.source.as_deref())
+        .ok_or("update.source não configurado (use --source)")?;
+    if is_remote(source) && config.public_key.is_none() {
+        return Err(format!(
+            "Fonte remota {} exige update.public_key (assinatura minisign)",
+            source
+        ));
+    }
+
+    let published = parse_sha256(&fetch(source, &format!("{}.sha256", ARTIFACT))?)?;
+    let current = sha256_file(binary)?;
+    if published == current {
+        return Ok(UpdateOutcome::UpToDate(current));
+    }
+
+    let data = fetch(source, ARTIFACT)?;
+    let downloaded = hex::encode(Sha256::digest(&data));
+    if downloaded != published {
+        return Err(format!(
+            "SHA-256 do binário baixado ({}) não confere com o publicado ({})",
+            downloaded, published
+        ));
+    }
+    if let Some(public_key) = &config.public_key {
+        let signature = fetch(source, &format!("{}.minisig", ARTIFACT))?;
+        verify_signature(public_key, &data, &signature)?;
+    }
+
+    // O arquivo novo fica no mesmo diretório para que o rename seja atômico
+    let file_name = binary
+        .file_name()
+        .ok_or_else(|| format!("Caminho de binário inválido: {:?}", binary))?
+        .to_string_lossy()
+        .into_owned();
+    let staged = binary.with_file_name(format!(".{}.new", file_name));
+    let previous = binary.with_file_name(format!("{}.previous", file_name));
+    fs::write(&staged, &data)
+        .and_then(|_| fs::set_permissions(&staged, fs::Permissions::from_mode(0o755)))
+        .map_err(|e| format!("Erro ao gravar {:?}: {}", staged, e))?;
+    if let Err(e) = check_runs(&staged) {
+        let _ = fs::remove_file(&staged);
+        return Err(e);
+    }
+
+    fs::copy(binary, &previous)
+        .map_err(|e| format!("Erro ao copiar a versão atual para {:?}: {}", previous, e))?;
+    let pending = PendingUpdate {
+        binary: binary.to_path_buf(),
+        previous,
+        sha256: published,
+        installed_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        attempts: 0,
+    };
+    save_marker(marker, &pending)?;
+    if let Err(e) = fs::rename(&staged, binary) {
+        let _ = fs::remove_file(marker);
+        let _ = fs::remove_file(&staged);
+        return Err(format!("Erro ao substituir {:?}: {}", binary, e));
+    }
+    Ok(UpdateOutcome::Installed(pending))
+}
+
+/// Executa o comando que reinicia o serviço
+pub fn restart(config: &UpdateConfig) -> Result<(), String> {
+    let output = command::run_shell(&config.restart_command, Duration::from_secs(60), false)?;
+    match output.failure() {
+        Some(failure) => Err(format!("Reinício após atualização falhou: {}", failure)),
+        None => Ok(()),
+    }
+}
+
+/// Verifica o marcador na inicialização do agente
+/// Cada inicialização do binário novo conta uma tentativa; acima do limite
+/// a versão anterior é restaurada
+pub fn check_pending() -> StartupState {
+    check_pending_at(&get_marker_path())
+}
+
+fn check_pending_at(marker: &Path) -> StartupState {
+    let Some(mut pending) = load_marker(marker) else {
+        return StartupState::Normal;
+    };
+    // Binário trocado por outro meio (deploy): o marcador não vale mais
+    if sha256_file(&pending.binary).ok().as_deref() != Some(pending.sha256.as_str()) {
+        let _ = fs::remove_file(marker);
+        return StartupState::Normal;
+    }
+
+    pending.attempts += 1;
+    if pending.attempts > MAX_ATTEMPTS {
+        let reason = format!(
+            "versão {} não concluiu o primeiro snapshot em {} inicializações",
+            short(&pending.sha256),
+            MAX_ATTEMPTS
+        );
+        return match rollback_with_marker(&pending, marker) {
+            Ok(()) => StartupState::RolledBack(reason),
+            Err(e) => {
+                // Sem o marcador a versão nova segue rodando, sem novas tentativas
+                let _ = fs::remove_file(marker);
+                StartupState::RollbackFailed(format!("{}; {}", reason, e))
+            }
+        };
+    }
+    if let Err(e) = save_marker(marker, &pending) {
+        eprintln!("{}", e);
+    }
+    StartupState::Pending(pending)
+}
+
+/// Confirma a versão nova após o primeiro snapshot
+pub fn confirm() -> Result<(), String> {
+    let path = get_marker_path();
+    fs::remove_file(&path).map_err(|e| format!("Erro ao remover {:?}: {}", path, e))
+}
+
+/// Restaura a versão anterior e remove o marcador
+/// A cópia `.previous` é mantida; a troca também é feita por rename
+pub fn rollback(pending: &PendingUpdate) -> Result<(), String> {
+    rollback_with_marker(pending, &get_marker_path())
+}
+
+fn rollback_with_marker(pending: &PendingUpdate, marker: &Path) -> Result<(), String> {
+    let file_name = pending
+        .binary
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let staged = pending
+        .binary
+        .with_file_name(format!(".{}.rollback", file_name));
+    fs::copy(&pending.previous, &staged)
+        .and_then(|_| fs::rename(&staged, &pending.binary))
+        .map_err(|e| format!("Erro ao restaurar {:?}: {}", pending.previous, e))?;
+    let _ = fs::remove_file(marker);
+    Ok(())
+}
+
+/// Substitui o processo atual pelo binário instalado, com os mesmos argumentos
+/// (mantém o PID, então o gerenciador de serviços não percebe a troca)
+pub fn reexec(binary: &Path) -> io::Error {
+    Command::new(binary)
+        .args(std::env::args_os().skip(1))
+        .exec()
+}
+
+/// Aguarda o binário novo confirmar o primeiro snapshot
+/// Retorna `Ok(true)` se confirmou, `Ok(false)` se o próprio agente
+/// restaurou a versão anterior e `Err` no timeout
+pub fn wait_confirmation(pending: &PendingUpdate, timeout: Duration) -> Result<bool, String> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if !get_marker_path().exists() {
+            let installed = sha256_file(&pending.binary)?;
+            return Ok(installed == pending.sha256);
+        }
+        sleep(Duration::from_secs(1));
+    }
+    Err(format!(
+        "a versão nova não concluiu o primeiro snapshot em {}s",
+        timeout.as_secs()
+    ))
+}
+
+/// Primeiros caracteres de um hash, para mensagens
+pub fn short(hash: &str) -> &str {
+    &hash[..hash.len().min(12)]
+}
+
+/// Fonte baixada pela rede (URL) em vez de um diretório local
+fn is_remote(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Lê um arquivo da fonte (diretório local ou URL base)
+fn fetch(source: &str, name: &str) -> Result<Vec<u8>, String> {
+    if is_remote(source) {
+        let url = format!("{}/{}", source.trim_end_matches('/'), name);
+        let output = Command::new("curl")
+            .args(["-fsSL", "--max-time", &DOWNLOAD_TIMEOUT_SECS.to_string()])
+            .arg(&url)
+            .output()
+            .map_err(|e| format!("Erro ao executar curl: {}", e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("Erro ao baixar {}: {}", url, stderr.trim()));
+        }
+        Ok(output.stdout)
+    } else {
+        let path = Path::new(source).join(name);
+        fs::read(&path).map_err(|e| format!("Erro ao ler {:?}: {}", path, e))
+    }
+}
+
+/// Hash do arquivo .sha256 ("HASH  nome" ou apenas "HASH")
+fn parse_sha256(content: &[u8]) -> Result<String, String> {
+    let text = String::from_utf8_lossy(content);
+    let hash = text.split_whitespace().next().unwrap_or("").to_lowercase();
+    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("Arquivo {}.sha256 inválido", ARTIFACT));
+    }
+    Ok(hash)
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let data = fs::read(path).map_err(|e| format!("Erro ao ler {:?}: {}", path, e))?;
+    Ok(hex::encode(Sha256::digest(&data)))
+}
+
+/// Confere a assinatura minisign do binário
+fn verify_signature(public_key: &str, data: &[u8], signature: &[u8]) -> Result<(), String> {
+    let key = PublicKey::from_base64(public_key.trim())
+        .map_err(|e| format!("update.public_key inválida: {}", e))?;
+    let signature = Signature::decode(&String::from_utf8_lossy(signature))
+        .map_err(|e| format!("Assinatura {}.minisig inválida: {}", ARTIFACT, e))?;
+    key.verify(data, &signature, false)
+        .map_err(|e| format!("Assinatura do binário não confere: {}", e))
+}
+
+/// Garante que o binário baixado executa nesta máquina (arquitetura, libc)
+fn check_runs(binary: &Path) -> Result<(), String> {
+    let quoted = format!("'{}'", binary.to_string_lossy().replace('\'', r"'\''"));
+    let output = command::run_shell(
+        &format!("{} --version", quoted),
+        Duration::from_secs(10),
+        true,
+    )?;
+    if let Some(failure) = output.failure() {
+        return Err(format!("O binário novo não executa: {}", failure));
+    }
+    if !output.stdout.starts_with("snapshot_agent") {
+        return Err("O binário novo não respondeu a --version como o agente".to_string());
+    }
+    Ok(())
+}
+
+fn load_marker(path: &Path) -> Option<PendingUpdate> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_marker(path: &Path, pending: &PendingUpdate) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(pending).map_err(|e| e.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Erro ao criar {:?}: {}", dir, e))?;
+    }
+    fs::write(path, json).map_err(|e| format!("Erro ao gravar {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Par de teste do minisign-verify (assinatura de b"test")
+    const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1633700835\tfile:test\tprehashed
+wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("update-{}-test-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Binário falso que responde a --version como o agente
+    fn fake_agent(version: &str) -> Vec<u8> {
+        format!("#!/bin/sh\necho snapshot_agent {}\n", version).into_bytes()
+    }
+
+    /// Publica um binário em uma fonte local
+    fn publish(source: &Path, data: &[u8]) {
+        fs::create_dir_all(source).unwrap();
+        fs::write(source.join(ARTIFACT), data).unwrap();
+        let hash = hex::encode(Sha256::digest(data));
+        fs::write(
+            source.join(format!("{}.sha256", ARTIFACT)),
+            format!("{}  {}\n", hash, ARTIFACT),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn parses_sha256_files() {
+        let hash = "a".repeat(64);
+        let with_name = format!("{}  snapshot_agent_linux\n", hash);
+        assert_eq!(parse_sha256(with_name.as_bytes()).unwrap(), hash);
+        assert_eq!(parse_sha256(hash.to_uppercase().as_bytes()).unwrap(), hash);
+        assert!(parse_sha256(b"").is_err());
+        assert!(parse_sha256("a".repeat(63).as_bytes()).is_err());
+        assert!(parse_sha256("g".repeat(64).as_bytes()).is_err());
+    }
+
+    #[test]
+    fn checks_minisign_signatures() {
+        assert!(verify_signature(PUBLIC_KEY, b"test", SIGNATURE.as_bytes()).is_ok());
+        assert!(verify_signature(PUBLIC_KEY, b"Test", SIGNATURE.as_bytes()).is_err());
+        assert!(verify_signature("chave", b"test", SIGNATURE.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn remote_sources_require_a_public_key() {
+        let dir = temp_dir("remote");
+        let binary = dir.join("snapshot_agent");
+        fs::write(&binary, fake_agent("1")).unwrap();
+        let config = UpdateConfig::default();
+        // Recusado antes de qualquer download
+        for source in [
+            "http://exemplo.invalid/agent",
+            "https://exemplo.invalid/agent",
+        ] {
+            let result = install_with_marker(&config, Some(source), &binary, &dir.join("m"));
+            let error = result.err().unwrap();
+            assert!(error.contains("update.public_key"), "{}", error);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn public_key_requires_the_signature_file() {
+        let dir = temp_dir("unsigned");
+        let source = dir.join("source");
+        publish(&source, &fake_agent("2"));
+        let binary = dir.join("snapshot_agent");
+        fs::write(&binary, fake_agent("1")).unwrap();
+        let config = UpdateConfig {
+            public_key: Some(PUBLIC_KEY.to_string()),
+            ..Default::default()
+        };
+        let source = source.to_string_lossy().into_owned();
+        let error = install_with_marker(&config, Some(&source), &binary, &dir.join("m"))
+            .err()
+            .unwrap();
+        assert!(error.contains(".minisig"), "{}", error);
+        // Nada foi trocado
+        assert_eq!(fs::read(&binary).unwrap(), fake_agent("1"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pending_update_rolls_back_after_repeated_failures() {
+        let dir = temp_dir("rollback");
+        let source = dir.join("source");
+        publish(&source, &fake_agent("2"));
+        let binary = dir.join("snapshot_agent");
+        fs::write(&binary, fake_agent("1")).unwrap();
+        let marker = dir.join("update_pending.json");
+        let source = source.to_string_lossy().into_owned();
+        let config = UpdateConfig::default();
+
+        let UpdateOutcome::Installed(pending) =
+            install_with_marker(&config, Some(&source), &binary, &marker).unwrap()
+        else {
+            panic!("atualização não instalada");
+        };
+        assert_eq!(fs::read(&binary).unwrap(), fake_agent("2"));
+        assert_eq!(fs::read(&pending.previous).unwrap(), fake_agent("1"));
+        assert!(matches!(
+            install_with_marker(&config, Some(&source), &binary, &marker).unwrap(),
+            UpdateOutcome::UpToDate(_)
+        ));
+
+        // Cada inicialização sem confirmação conta uma tentativa
+        for attempt in 1..=MAX_ATTEMPTS {
+            match check_pending_at(&marker) {
+                StartupState::Pending(pending) => assert_eq!(pending.attempts, attempt),
+                _ => panic!("esperado Pending na tentativa {}", attempt),
+            }
+        }
+        assert!(matches!(
+            check_pending_at(&marker),
+            StartupState::RolledBack(_)
+        ));
+        assert_eq!(fs::read(&binary).unwrap(), fake_agent("1"));
+        assert!(!marker.exists());
+        assert!(matches!(check_pending_at(&marker), StartupState::Normal));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn marker_is_dropped_when_the_binary_changes() {
+        let dir = temp_dir("replaced");
+        let source = dir.join("source");
+        publish(&source, &fake_agent("2"));
+        let binary = dir.join("snapshot_agent");
+        fs::write(&binary, fake_agent("1")).unwrap();
+        let marker = dir.join("update_pending.json");
+        let source = source.to_string_lossy().into_owned();
+        install_with_marker(&UpdateConfig::default(), Some(&source), &binary, &marker).unwrap();
+
+        // Um deploy trocou o binário: o marcador não vale mais
+        fs::write(&binary, fake_agent("3")).unwrap();
+        assert!(matches!(check_pending_at(&marker), StartupState::Normal));
+        assert!(!marker.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,429 @@
+use crate::config::WatchConfig;
+use chrono::{SecondsFormat, Utc};
+use snapshot_agent::command;
+use snapshot_schema::{ProcessEvent, Record, RestartResult, SCHEMA_VERSION};
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// =========================================================================
+/// LISTA DE PROCESSOS MONITORADOS (WATCHLIST)
+/// =========================================================================
+/// A cada intervalo verifica se os itens de `watchlist` estão rodando e gera
+/// PROCESS_DOWN/PROCESS_UP apenas nas mudanças de estado. Um item que já está
+/// parado na primeira verificação gera PROCESS_DOWN; um item rodando não gera
+/// evento. Se os PIDs mudarem entre duas verificações (o processo morreu e
+/// foi reiniciado por outro mecanismo), são gravados DOWN e UP em sequência.
+
+/// Processo encontrado em /proc
+struct ProcInfo {
+    pid: u32,
+    comm: String,
+    argv0: String,
+    cmdline: String,
+    started_at: Option<u64>,
+}
+
+/// Situação de um item em uma verificação
+struct Observation {
+    running: bool,
+    pids: Vec<u32>,
+    started_at: Option<u64>,
+}
+
+/// Estado guardado entre verificações
+struct WatchState {
+    running: bool,
+    pids: Vec<u32>,
+    started_at: Option<u64>,
+    last_seen: Option<u64>,
+}
+
+/// Acompanha o estado dos itens da watchlist entre verificações
+pub struct Watcher {
+    states: HashMap<String, WatchState>,
+    last_errors: HashMap<String, String>, // Evita repetir o mesmo erro a cada intervalo
+}
+
+impl Watcher {
+    pub fn new() -> Self {
+        Self {
+            states: HashMap::new(),
+            last_errors: HashMap::new(),
+        }
+    }
+
+    /// Verifica todos os itens e retorna os eventos e as mensagens de erro
+    pub fn check(
+        &mut self,
+        items: &[WatchConfig],
+        agent_id: &str,
+        hostname: &str,
+    ) -> (Vec<Record>, Vec<String>) {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+
+        // Itens removidos da configuração (SIGHUP) deixam de ser acompanhados
+        self.states
+            .retain(|name, _| items.iter().any(|item| &item.name == name));
+        self.last_errors
+            .retain(|name, _| items.iter().any(|item| &item.name == name));
+        if items.is_empty() {
+            return (records, errors);
+        }
+
+        let needs_proc = items
+            .iter()
+            .any(|i| i.process.is_some() || i.cmdline.is_some());
+        let processes = if needs_proc {
+            list_processes()
+        } else {
+            Vec::new()
+        };
+
+        for item in items {
+            let observation = match observe(item, &processes) {
+                Ok(observation) => {
+                    self.last_errors.remove(&item.name);
+                    observation
+                }
+                Err(e) => {
+                    if self.last_errors.get(&item.name) != Some(&e) {
+                        errors.push(format!("Watchlist {}: {}", item.name, e));
+                        self.last_errors.insert(item.name.clone(), e);
+                    }
+                    continue;
+                }
+            };
+
+            let now = unix_now();
+            let event = |pids: &[u32], started_at, last_seen| ProcessEvent {
+                schema_version: SCHEMA_VERSION,
+                agent_id: agent_id.to_string(),
+                hostname: hostname.to_string(),
+                datetime: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                timestamp: now,
+                name: item.name.clone(),
+                target: describe_target(item),
+                pids: pids.to_vec(),
+                started_at,
+                last_seen,
+                restart: None,
+            };
+
+            let previous = self.states.get(&item.name);
+            match (previous, observation.running) {
+                // Caiu (ou já estava parado na primeira verificação)
+                (None, false) | (Some(WatchState { running: true, .. }), false) => {
+                    let mut down = match previous {
+                        Some(state) => event(&state.pids, state.started_at, state.last_seen),
+                        None => event(&[], None, None),
+                    };
+                    if let Some(restart) = &item.restart_command {
+                        let (result, error) = run_restart(item, restart);
+                        down.restart = result;
+                        errors.extend(error);
+                    }
+                    records.push(Record::ProcessDown(down));
+                }
+                // Voltou
+                (Some(WatchState { running: false, .. }), true) => {
+                    records.push(Record::ProcessUp(event(
+                        &observation.pids,
+                        observation.started_at,
+                        None,
+                    )));
+                }
+                // Continua rodando, mas com outros PIDs: morreu e foi reiniciado
+                (Some(state), true)
+                    if !state.pids.is_empty()
+                        && !observation.pids.is_empty()
+                        && !state.pids.iter().any(|pid| observation.pids.contains(pid)) =>
+                {
+                    records.push(Record::ProcessDown(event(
+                        &state.pids,
+                        state.started_at,
+                        state.last_seen,
+                    )));
+                    records.push(Record::ProcessUp(event(
+                        &observation.pids,
+                        observation.started_at,
+                        None,
+                    )));
+                }
+                _ => {}
+            }
+
+            let last_seen = if observation.running {
+                Some(now)
+            } else {
+                previous.and_then(|state| state.last_seen)
+            };
+            self.states.insert(
+                item.name.clone(),
+                WatchState {
+                    running: observation.running,
+                    pids: observation.pids,
+                    started_at: observation.started_at,
+                    last_seen,
+                },
+            );
+        }
+
+        (records, errors)
+    }
+}
+
+/// Descrição do alvo gravada nos eventos
+fn describe_target(item: &WatchConfig) -> String {
+    if let Some(process) = &item.process {
+        format!("process:{}", process)
+    } else if let Some(cmdline) = &item.cmdline {
+        format!("cmdline:{}", cmdline)
+    } else if let Some(unit) = &item.unit {
+        let scope = if item.user_unit { "user-unit" } else { "unit" };
+        format!("{}:{}", scope, unit)
+    } else {
+        String::new()
+    }
+}
+
+/// Verifica um item da watchlist
+fn observe(item: &WatchConfig, processes: &[ProcInfo]) -> Result<Observation, String> {
+    if let Some(unit) = &item.unit {
+        return observe_unit(unit, item.user_unit);
+    }
+
+    let matches: Vec<&ProcInfo> = processes
+        .iter()
+        .filter(|p| match (&item.process, &item.cmdline) {
+            (Some(name), _) => &p.comm == name || &p.argv0 == name,
+            (None, Some(pattern)) => p.cmdline.contains(pattern.as_str()),
+            (None, None) => false,
+        })
+        .collect();
+
+    let mut pids: Vec<u32> = matches.iter().map(|p| p.pid).collect();
+    pids.sort_unstable();
+    Ok(Observation {
+        running: !pids.is_empty(),
+        pids,
+        started_at: matches.iter().filter_map(|p| p.started_at).min(),
+    })
+}
+
+/// Estado de uma unidade systemd (ActiveState e MainPID)
+fn observe_unit(unit: &str, user: bool) -> Result<Observation, String> {
+    let mut command = Command::new("systemctl");
+    if user {
+        command.arg("--user");
+    }
+    let output = command
+        .args(["show", "--property=ActiveState,MainPID", "--", unit])
+        .output()
+        .map_err(|e| format!("erro ao executar systemctl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "systemctl show falhou: {}",
+            stderr.lines().next().unwrap_or("").trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let property = |name: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    let running = matches!(property("ActiveState").as_str(), "active" | "reloading");
+    let pids: Vec<u32> = match property("MainPID").parse::<u32>() {
+        Ok(pid) if pid > 0 => vec![pid],
+        _ => Vec::new(),
+    };
+    let started_at = pids.first().and_then(|pid| process_start_time(*pid));
+    Ok(Observation {
+        running,
+        pids,
+        started_at,
+    })
+}
+
+/// Executa o comando de reinício de um item
+/// Retorna o resultado (se o comando iniciou) e a mensagem de falha
+fn run_restart(item: &WatchConfig, restart: &str) -> (Option<RestartResult>, Option<String>) {
+    let timeout = Duration::from_secs(item.restart_timeout_secs);
+    match command::run_shell(restart, timeout, false) {
+        Ok(output) => {
+            let error = output
+                .failure()
+                .map(|failure| format!("Watchlist {}: reinício falhou: {}", item.name, failure));
+            let result = RestartResult {
+                exit_code: output.exit_code,
+                duration_ms: output.duration_ms,
+                timed_out: output.timed_out,
+            };
+            (Some(result), error)
+        }
+        Err(e) => (
+            None,
+            Some(format!("Watchlist {}: reinício falhou: {}", item.name, e)),
+        ),
+    }
+}
+
+/// Lista os processos em /proc (exceto o próprio agente)
+fn list_processes() -> Vec<ProcInfo> {
+    let own_pid = std::process::id();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| *pid != own_pid)
+        .filter_map(|pid| {
+            // Processos que terminaram durante a leitura são ignorados
+            let comm = fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+            let raw = fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+            let args: Vec<String> = raw
+                .split(|b| *b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect();
+            let argv0 = args
+                .first()
+                .map(|arg| arg.rsplit('/').next().unwrap_or(arg).to_string())
+                .unwrap_or_default();
+            Some(ProcInfo {
+                pid,
+                comm: comm.trim_end().to_string(),
+                argv0,
+                cmdline: args.join(" "),
+                started_at: process_start_time(pid),
+            })
+        })
+        .collect()
+}
+
+/// Timestamp UNIX de início de um processo (campo starttime de /proc/<pid>/stat)
+fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // O nome do processo (campo 2) pode conter espaços; os campos seguintes
+    // começam após o último ')'
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let start_ticks: u64 = fields.get(19)?.parse().ok()?;
+    let (boot_time, ticks) = *CLOCK.get_or_init(read_clock);
+    Some(boot_time? + start_ticks / ticks)
+}
+
+/// Timestamp UNIX do boot e ticks de relógio por segundo (constantes)
+static CLOCK: OnceLock<(Option<u64>, u64)> = OnceLock::new();
+
+fn read_clock() -> (Option<u64>, u64) {
+    // Linha btime de /proc/stat
+    let boot_time = fs::read_to_string("/proc/stat").ok().and_then(|stat| {
+        stat.lines()
+            .find_map(|line| line.strip_prefix("btime ")?.trim().parse().ok())
+    });
+    // SAFETY: sysconf apenas consulta um valor de configuração
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    (boot_time, if ticks > 0 { ticks as u64 } else { 100 })
+}
+
+fn unix_now() -> u64 {
+    Utc::now().timestamp().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(process: Option<&str>, cmdline: Option<&str>) -> WatchConfig {
+        WatchConfig {
+            name: "web".to_string(),
+            process: process.map(str::to_string),
+            cmdline: cmdline.map(str::to_string),
+            unit: None,
+            user_unit: false,
+            restart_command: None,
+            restart_timeout_secs: 30,
+        }
+    }
+
+    fn proc_info(pid: u32, comm: &str, cmdline: &str, started_at: u64) -> ProcInfo {
+        let argv0 = cmdline.split(' ').next().unwrap_or("");
+        ProcInfo {
+            pid,
+            comm: comm.to_string(),
+            argv0: argv0.rsplit('/').next().unwrap_or(argv0).to_string(),
+            cmdline: cmdline.to_string(),
+            started_at: Some(started_at),
+        }
+    }
+
+    fn processes() -> Vec<ProcInfo> {
+        vec![
+            proc_info(30, "nginx", "nginx: worker process", 200),
+            proc_info(20, "nginx", "/usr/sbin/nginx -g daemon off;", 100),
+            proc_info(40, "python3", "/usr/bin/python3 /opt/app/server.py", 300),
+            proc_info(50, "bash", "/bin/bash", 50),
+        ]
+    }
+
+    #[test]
+    fn targets_are_described_by_kind() {
+        assert_eq!(describe_target(&item(Some("nginx"), None)), "process:nginx");
+        assert_eq!(
+            describe_target(&item(None, Some("server.py"))),
+            "cmdline:server.py"
+        );
+        let unit = WatchConfig {
+            unit: Some("app.service".to_string()),
+            user_unit: true,
+            ..item(None, None)
+        };
+        assert_eq!(describe_target(&unit), "user-unit:app.service");
+    }
+
+    #[test]
+    fn processes_match_by_name_or_cmdline() {
+        let by_name = observe(&item(Some("nginx"), None), &processes()).unwrap();
+        assert!(by_name.running);
+        assert_eq!(by_name.pids, vec![20, 30]);
+        assert_eq!(by_name.started_at, Some(100));
+
+        // argv0 também identifica o processo (comm é truncado em 15 bytes)
+        let by_argv0 = observe(&item(Some("python3"), None), &processes()).unwrap();
+        assert_eq!(by_argv0.pids, vec![40]);
+
+        let by_cmdline = observe(&item(None, Some("/opt/app/server.py")), &processes()).unwrap();
+        assert_eq!(by_cmdline.pids, vec![40]);
+
+        let missing = observe(&item(Some("redis-server"), None), &processes()).unwrap();
+        assert!(!missing.running && missing.pids.is_empty());
+    }
+
+    #[test]
+    fn events_only_on_state_changes() {
+        let items = [item(Some("processo-que-nao-existe-no-teste"), None)];
+        let mut watcher = Watcher::new();
+
+        // Parado já na primeira verificação: um único PROCESS_DOWN
+        let (records, errors) = watcher.check(&items, "agente", "vm1");
+        assert!(errors.is_empty());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record_type(), "PROCESS_DOWN");
+        assert!(watcher.check(&items, "agente", "vm1").0.is_empty());
+
+        // Item removido da configuração deixa de ser acompanhado
+        watcher.check(&[], "agente", "vm1");
+        assert!(watcher.states.is_empty());
+    }
+}
@@ -0,0 +1,383 @@
+use std::any::Any;
+use std::marker::PhantomData;
+use std::panic;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// =========================================================================
+/// COLETORES E AGENDADOR
+/// =========================================================================
+/// Um `Collector` lê uma fonte de dados (sysinfo, /proc, comandos) e devolve
+/// um valor tipado. O `Scheduler` guarda os coletores, cada um com o próprio
+/// intervalo; a cada chamada de `run_due` os que venceram rodam em paralelo,
+/// um por thread, e o último valor de cada um fica disponível pelo `Handle`
+/// devolvido no registro.
+///
+/// O agendador não tem relógio próprio: quem chama `run_due` (o loop de
+/// snapshots) define a granularidade. Um coletor com intervalo de 5 minutos
+/// em um agente que coleta a cada 30 segundos roda a cada dez snapshots e,
+/// nos demais, o snapshot reaproveita o último valor.
+
+/// Folga na comparação com o vencimento: um coletor de 60s chamado a cada
+/// 30s roda a cada duas rodadas mesmo com atrasos de milissegundos no loop
+const DUE_SLACK: Duration = Duration::from_secs(1);
+
+/// Fonte de dados com valor tipado
+pub trait Collector: Send {
+    /// Valor produzido por uma coleta
+    type Output: Send + 'static;
+
+    /// Identificador usado nos erros, na configuração e no orçamento do agente
+    fn name(&self) -> &'static str;
+
+    /// Intervalo padrão entre coletas; zero roda em toda rodada do agendador
+    fn interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Lê a fonte de dados
+    fn collect(&mut self) -> Result<Self::Output, String>;
+}
+
+/// Falha de um coletor em uma rodada
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollectorError {
+    pub collector: &'static str,
+    pub error: String,
+}
+
+/// Referência tipada ao último valor de um coletor registrado
+pub struct Handle<T> {
+    index: usize,
+    output: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+/// Coletor com o tipo apagado, para guardar coletores diferentes juntos
+trait Task: Send {
+    fn name(&self) -> &'static str;
+    fn run(&mut self) -> Result<(), String>;
+    fn clear(&mut self);
+    fn output(&self) -> Option<&dyn Any>;
+}
+
+/// Coletor e o último valor produzido
+struct Scheduled<C: Collector> {
+    collector: C,
+    latest: Option<C::Output>,
+}
+
+impl<C: Collector> Task for Scheduled<C> {
+    fn name(&self) -> &'static str {
+        self.collector.name()
+    }
+
+    /// Uma coleta com erro descarta o valor anterior: o snapshot não deve
+    /// mostrar um dado antigo como se fosse atual
+    fn run(&mut self) -> Result<(), String> {
+        let result = self.collector.collect();
+        match result {
+            Ok(output) => {
+                self.latest = Some(output);
+                Ok(())
+            }
+            Err(e) => {
+                self.latest = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.latest = None;
+    }
+
+    fn output(&self) -> Option<&dyn Any> {
+        self.latest.as_ref().map(|output| output as &dyn Any)
+    }
+}
+
+/// Coletor registrado e o seu agendamento
+struct Entry {
+    task: Box<dyn Task>,
+    interval: Duration,
+    next_run: Option<Instant>, // None: roda na próxima rodada
+    fresh: bool,               // Coletado na última rodada
+}
+
+impl Entry {
+    fn new<C: Collector + 'static>(collector: C, interval: Duration) -> Self {
+        Self {
+            task: Box::new(Scheduled {
+                collector,
+                latest: None,
+            }),
+            interval,
+            next_run: None,
+            fresh: false,
+        }
+    }
+}
+
+/// Executa os coletores registrados, cada um no próprio intervalo
+#[derive(Default)]
+pub struct Scheduler {
+    entries: Vec<Option<Entry>>, // None: coletor removido (os handles não mudam)
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra um coletor com o intervalo padrão dele
+    pub fn add<C: Collector + 'static>(&mut self, collector: C) -> Handle<C::Output> {
+        let interval = collector.interval();
+        self.add_every(collector, interval)
+    }
+
+    /// Registra um coletor com um intervalo específico (ex.: da configuração)
+    pub fn add_every<C: Collector + 'static>(
+        &mut self,
+        collector: C,
+        interval: Duration,
+    ) -> Handle<C::Output> {
+        self.entries.push(Some(Entry::new(collector, interval)));
+        Handle {
+            index: self.entries.len() - 1,
+            output: PhantomData,
+        }
+    }
+
+    /// Troca o coletor de um registro (ex.: configuração recarregada)
+    /// O valor anterior é descartado e o novo coletor roda na próxima rodada
+    pub fn replace<C: Collector + 'static>(
+        &mut self,
+        handle: &Handle<C::Output>,
+        collector: C,
+        interval: Duration,
+    ) {
+        if let Some(slot) = self.entries.get_mut(handle.index) {
+            *slot = Some(Entry::new(collector, interval));
+        }
+    }
+
+    /// Muda o intervalo de um coletor mantendo o coletor e o seu estado
+    /// Com um intervalo diferente, o coletor roda na próxima rodada
+    pub fn set_interval<T>(&mut self, handle: &Handle<T>, interval: Duration) {
+        if let Some(Some(entry)) = self.entries.get_mut(handle.index) {
+            if entry.interval != interval {
+                entry.interval = interval;
+                entry.next_run = None;
+            }
+        }
+    }
+
+    /// Remove um coletor; o handle deixa de ter valor
+    pub fn remove<T>(&mut self, handle: Handle<T>) {
+        if let Some(slot) = self.entries.get_mut(handle.index) {
+            *slot = None;
+        }
+    }
+
+    /// Nome do coletor de um registro (`None` se removido)
+    pub fn name<T>(&self, handle: &Handle<T>) -> Option<&'static str> {
+        Some(self.entries.get(handle.index)?.as_ref()?.task.name())
+    }
+
+    /// Nomes dos coletores registrados, na ordem de registro
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.task.name())
+            .collect()
+    }
+
+    /// Executa em paralelo os coletores vencidos e devolve as falhas
+    ///
+    /// Coletores em `skip` não rodam e ficam sem valor (o snapshot mostra o
+    /// padrão); voltam a rodar na primeira rodada em que não forem omitidos.
+    /// Um pânico em um coletor é propagado depois que todos terminam.
+    pub fn run_due(&mut self, skip: &[String]) -> Vec<CollectorError> {
+        let now = Instant::now();
+        let mut errors = Vec::new();
+        thread::scope(|scope| {
+            let mut handles = Vec::new();
+            for entry in self.entries.iter_mut().flatten() {
+                let name = entry.task.name();
+                entry.fresh = false;
+                if skip.iter().any(|skipped| skipped == name) {
+                    entry.task.clear();
+                    entry.next_run = None;
+                    continue;
+                }
+                if entry.next_run.is_some_and(|next| next > now + DUE_SLACK) {
+                    continue;
+                }
+                entry.next_run = Some(now + entry.interval);
+                entry.fresh = true;
+                let task = &mut entry.task;
+                handles.push((name, scope.spawn(move || task.run())));
+            }
+            for (name, handle) in handles {
+                match handle.join() {
+                    Ok(Ok(())) => {}
+                    Ok(Err(error)) => errors.push(CollectorError {
+                        collector: name,
+                        error,
+                    }),
+                    Err(payload) => panic::resume_unwind(payload),
+                }
+            }
+        });
+        errors
+    }
+
+    /// Último valor do coletor (`None` antes da primeira coleta, após uma
+    /// falha ou enquanto o coletor é omitido)
+    pub fn latest<T: 'static>(&self, handle: &Handle<T>) -> Option<&T> {
+        self.entries
+            .get(handle.index)?
+            .as_ref()?
+            .task
+            .output()?
+            .downcast_ref::<T>()
+    }
+
+    /// Se o coletor rodou na última rodada (e não apenas guardou o valor
+    /// de uma rodada anterior)
+    pub fn is_fresh<T>(&self, handle: &Handle<T>) -> bool {
+        self.entries
+            .get(handle.index)
+            .is_some_and(|entry| entry.as_ref().is_some_and(|entry| entry.fresh))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Conta as coletas; falha quando `fail` está ativo
+    struct Counter {
+        name: &'static str,
+        interval: Duration,
+        count: u32,
+        fail: bool,
+    }
+
+    impl Counter {
+        fn new(name: &'static str, interval: Duration) -> Self {
+            Self {
+                name,
+                interval,
+                count: 0,
+                fail: false,
+            }
+        }
+    }
+
+    impl Collector for Counter {
+        type Output = u32;
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn interval(&self) -> Duration {
+            self.interval
+        }
+
+        fn collect(&mut self) -> Result<u32, String> {
+            if self.fail {
+                return Err(format!("{} falhou", self.name));
+            }
+            self.count += 1;
+            Ok(self.count)
+        }
+    }
+
+    #[test]
+    fn runs_each_collector_at_its_own_interval() {
+        let mut scheduler = Scheduler::new();
+        let every_round = scheduler.add(Counter::new("rapido", Duration::ZERO));
+        let hourly = scheduler.add(Counter::new("lento", Duration::from_secs(3600)));
+
+        assert!(scheduler.run_due(&[]).is_empty());
+        assert!(scheduler.run_due(&[]).is_empty());
+
+        assert_eq!(scheduler.latest(&every_round), Some(&2));
+        assert!(scheduler.is_fresh(&every_round));
+        // O coletor lento rodou só na primeira rodada e mantém o valor
+        assert_eq!(scheduler.latest(&hourly), Some(&1));
+        assert!(!scheduler.is_fresh(&hourly));
+    }
+
+    #[test]
+    fn skipped_collectors_lose_their_value() {
+        let mut scheduler = Scheduler::new();
+        let handle = scheduler.add(Counter::new("caro", Duration::from_secs(3600)));
+        scheduler.run_due(&[]);
+        assert_eq!(scheduler.latest(&handle), Some(&1));
+
+        scheduler.run_due(&["caro".to_string()]);
+        assert_eq!(scheduler.latest(&handle), None);
+
+        // Volta a rodar assim que deixa de ser omitido, mesmo antes do intervalo
+        scheduler.run_due(&[]);
+        assert_eq!(scheduler.latest(&handle), Some(&2));
+    }
+
+    #[test]
+    fn reconfiguring_keeps_the_collector_state() {
+        let mut scheduler = Scheduler::new();
+        let kept = scheduler.add(Counter::new("mantido", Duration::from_secs(3600)));
+        let replaced = scheduler.add(Counter::new("trocado", Duration::ZERO));
+        let removed = scheduler.add(Counter::new("removido", Duration::ZERO));
+        scheduler.run_due(&[]);
+
+        // Novo intervalo: roda na próxima rodada, continuando a contagem
+        scheduler.set_interval(&kept, Duration::from_secs(60));
+        scheduler.replace(
+            &replaced,
+            Counter::new("novo", Duration::ZERO),
+            Duration::ZERO,
+        );
+        scheduler.remove(removed);
+        assert_eq!(scheduler.latest(&replaced), None);
+        assert_eq!(scheduler.latest(&removed), None);
+
+        scheduler.run_due(&[]);
+        assert_eq!(scheduler.latest(&kept), Some(&2));
+        assert_eq!(scheduler.latest(&replaced), Some(&1));
+        assert_eq!(scheduler.names(), vec!["mantido", "novo"]);
+    }
+
+    #[test]
+    fn errors_are_reported_and_clear_the_value() {
+        let mut scheduler = Scheduler::new();
+        let mut failing = Counter::new("quebrado", Duration::ZERO);
+        failing.fail = true;
+        let handle = scheduler.add(failing);
+        let ok = scheduler.add(Counter::new("ok", Duration::ZERO));
+
+        let errors = scheduler.run_due(&[]);
+        assert_eq!(
+            errors,
+            vec![CollectorError {
+                collector: "quebrado",
+                error: "quebrado falhou".to_string(),
+            }]
+        );
+        assert_eq!(scheduler.latest(&handle), None);
+        assert_eq!(scheduler.latest(&ok), Some(&1));
+        assert_eq!(scheduler.names(), vec!["quebrado", "ok"]);
+    }
+}
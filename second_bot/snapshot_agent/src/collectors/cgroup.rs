@@ -0,0 +1,441 @@
+use crate::collector::Collector;
+use snapshot_schema::{CgroupStats, ResourceScope};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use sysinfo::{System, SystemExt};
+
+/// =========================================================================
+/// LIMITES DO CGROUP (CONTAINERS E SLICES DO SYSTEMD)
+/// =========================================================================
+/// Dentro de um container ou de uma slice com limites, a memória e a CPU do
+/// host não refletem o que o agente pode usar. Lê o cgroup do próprio
+/// processo (v2 unificado ou v1 por controlador) em /sys/fs/cgroup:
+///   v2 - memory.max/memory.current, cpu.max/cpu.stat, pids.max/pids.current
+///   v1 - memory.limit_in_bytes/memory.usage_in_bytes, cpu.cfs_quota_us,
+///        cpuacct.usage, pids.max/pids.current
+///
+/// Um recurso fica no escopo `cgroup` quando o limite é menor que o do host.
+/// Os limites de memória e CPU valem também para os cgroups ancestrais (ex.:
+/// a slice do usuário acima do serviço); vale o menor até o ponto de montagem.
+
+/// Ponto de montagem dos cgroups, relativo à raiz
+const CGROUP_MOUNT: &str = "sys/fs/cgroup";
+
+/// Limites e uso do cgroup do agente
+/// `None` se o sistema não expõe cgroups
+pub struct CgroupCollector {
+    root: PathBuf,
+    host_memory: u64,                 // Limites acima disso valem como "sem limite"
+    host_cores: u64,                  // Capacidade quando não há cota de CPU
+    last_cpu: Option<(Instant, u64)>, // Última leitura do tempo de CPU (para o percentual)
+}
+
+impl CgroupCollector {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_memory();
+        Self {
+            root: PathBuf::from("/"),
+            host_memory: system.total_memory(),
+            host_cores: host_cores(),
+            last_cpu: None,
+        }
+    }
+
+    /// Lê /proc e /sys abaixo de `root` em vez de /
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Memória e núcleos do host usados para decidir o escopo de cada recurso
+    pub fn with_host(mut self, memory: u64, cores: u64) -> Self {
+        self.host_memory = memory;
+        self.host_cores = cores.max(1);
+        self
+    }
+
+    /// Percentual de uso desde a leitura anterior, relativo à capacidade em núcleos
+    fn cpu_percent(&mut self, usage_us: u64, capacity_cores: f64) -> Option<f32> {
+        let now = Instant::now();
+        let (at, previous_usage) = self.last_cpu.replace((now, usage_us))?;
+        let elapsed_us = now.duration_since(at).as_micros() as f64;
+        if elapsed_us <= 0.0 || capacity_cores <= 0.0 || usage_us < previous_usage {
+            return None;
+        }
+        let percent = (usage_us - previous_usage) as f64 / (elapsed_us * capacity_cores) * 100.0;
+        Some(percent.min(100.0) as f32)
+    }
+}
+
+impl Default for CgroupCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector for CgroupCollector {
+    type Output = Option<CgroupStats>;
+
+    fn name(&self) -> &'static str {
+        "cgroup"
+    }
+
+    fn collect(&mut self) -> Result<Option<CgroupStats>, String> {
+        let Ok(membership) = fs::read_to_string(self.root.join("proc/self/cgroup")) else {
+            return Ok(None);
+        };
+        let mount = self.root.join(CGROUP_MOUNT);
+        let unified = mount.join("cgroup.controllers").exists();
+        let reader = if unified {
+            CgroupReader::v2(mount, &membership)
+        } else {
+            CgroupReader::v1(mount, &membership)
+        };
+        let Some(reader) = reader else {
+            return Ok(None);
+        };
+
+        let host_cores = self.host_cores;
+        let memory_limit = reader
+            .memory_limit()
+            .filter(|limit| *limit < self.host_memory);
+        let cpu_limit_cores = reader.cpu_limit_cores();
+        let cpu_usage_percent = reader.cpu_usage_us().and_then(|usage| {
+            let capacity = cpu_limit_cores.unwrap_or(host_cores as f64);
+            self.cpu_percent(usage, capacity)
+        });
+        let scope = |limited: bool| {
+            if limited {
+                ResourceScope::Cgroup
+            } else {
+                ResourceScope::Host
+            }
+        };
+
+        Ok(Some(CgroupStats {
+            version: if unified { 2 } else { 1 },
+            path: reader.path.clone(),
+            container: detect_container(&self.root, &membership),
+            memory_scope: scope(memory_limit.is_some()),
+            memory_limit,
+            memory_usage: reader.memory_usage().unwrap_or(0),
+            cpu_scope: scope(cpu_limit_cores.is_some_and(|cores| cores < host_cores as f64)),
+            cpu_limit_cores,
+            cpu_usage_percent,
+            pids_current: reader.read_u64("pids", "pids.current"),
+            pids_max: reader.read_limit("pids", "pids.max"),
+        }))
+    }
+}
+
+/// Diretórios do cgroup do processo, por versão
+struct CgroupReader {
+    mount: PathBuf, // Ponto de montagem (/sys/fs/cgroup)
+    unified: bool,
+    path: String,                    // Caminho informado em /proc/self/cgroup
+    v1_paths: Vec<(String, String)>, // (controladores, caminho) no cgroup v1
+}
+
+impl CgroupReader {
+    /// cgroup v2: linha `0::/caminho`
+    fn v2(mount: PathBuf, membership: &str) -> Option<Self> {
+        let path = membership
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))?
+            .to_string();
+        Some(Self {
+            mount,
+            unified: true,
+            path,
+            v1_paths: Vec::new(),
+        })
+    }
+
+    /// cgroup v1: linhas `N:controladores:/caminho`; o caminho exibido é o
+    /// do controlador de memória
+    fn v1(mount: PathBuf, membership: &str) -> Option<Self> {
+        let v1_paths: Vec<(String, String)> = membership
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ':');
+                let (_, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+                (!controllers.is_empty()).then(|| (controllers.to_string(), path.to_string()))
+            })
+            .collect();
+        let path = v1_paths
+            .iter()
+            .find(|(controllers, _)| controllers.split(',').any(|c| c == "memory"))
+            .or_else(|| v1_paths.first())
+            .map(|(_, path)| path.clone())?;
+        Some(Self {
+            mount,
+            unified: false,
+            path,
+            v1_paths,
+        })
+    }
+
+    /// Diretório onde ficam os arquivos de um controlador
+    ///
+    /// Sem namespace de cgroup, o caminho de /proc/self/cgroup é o do host e
+    /// pode não existir dentro do container, onde o próprio cgroup aparece
+    /// na raiz do ponto de montagem
+    fn dir(&self, controller: &str) -> Option<PathBuf> {
+        let (mount, path) = if self.unified {
+            (self.mount.clone(), self.path.as_str())
+        } else {
+            let (_, path) = self
+                .v1_paths
+                .iter()
+                .find(|(controllers, _)| controllers.split(',').any(|c| c == controller))?;
+            (self.mount.join(controller), path.as_str())
+        };
+        let full = mount.join(path.trim_start_matches('/'));
+        if full.is_dir() {
+            Some(full)
+        } else if mount.is_dir() {
+            Some(mount)
+        } else {
+            None
+        }
+    }
+
+    /// Diretório do controlador seguido dos ancestrais até o ponto de montagem
+    fn hierarchy(&self, controller: &str) -> Vec<PathBuf> {
+        let mount = if self.unified {
+            self.mount.clone()
+        } else {
+            self.mount.join(controller)
+        };
+        let Some(dir) = self.dir(controller) else {
+            return Vec::new();
+        };
+        dir.ancestors()
+            .take_while(|ancestor| ancestor.starts_with(&mount))
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    fn read(&self, controller: &str, file: &str) -> Option<String> {
+        read_trimmed(&self.dir(controller)?.join(file))
+    }
+
+    fn read_u64(&self, controller: &str, file: &str) -> Option<u64> {
+        self.read(controller, file)?.parse().ok()
+    }
+
+    fn read_limit(&self, controller: &str, file: &str) -> Option<u64> {
+        parse_limit(&self.read(controller, file)?)
+    }
+
+    /// Menor limite de memória entre o cgroup e os ancestrais
+    fn memory_limit(&self) -> Option<u64> {
+        let file = if self.unified {
+            "memory.max"
+        } else {
+            "memory.limit_in_bytes"
+        };
+        self.hierarchy("memory")
+            .iter()
+            .filter_map(|dir| parse_limit(&read_trimmed(&dir.join(file))?))
+            .min()
+    }
+
+    /// Uso de memória sem o cache de arquivos inativo (mesmo critério do
+    /// `docker stats`), que o kernel libera sob pressão
+    fn memory_usage(&self) -> Option<u64> {
+        let (usage_file, inactive_key) = if self.unified {
+            ("memory.current", "inactive_file")
+        } else {
+            ("memory.usage_in_bytes", "total_inactive_file")
+        };
+        let usage = self.read_u64("memory", usage_file)?;
+        let inactive = self
+            .read("memory", "memory.stat")
+            .and_then(|stat| stat_value(&stat, inactive_key))
+            .unwrap_or(0);
+        Some(usage.saturating_sub(inactive))
+    }
+
+    /// Menor cota de CPU em núcleos (quota / period) entre o cgroup e os
+    /// ancestrais
+    fn cpu_limit_cores(&self) -> Option<f64> {
+        self.hierarchy("cpu")
+            .iter()
+            .filter_map(|dir| self.cpu_quota_at(dir))
+            .reduce(f64::min)
+    }
+
+    /// Cota de CPU de um diretório do cgroup
+    fn cpu_quota_at(&self, dir: &Path) -> Option<f64> {
+        let (quota, period) = if self.unified {
+            // cpu.max: "QUOTA PERIOD" ou "max PERIOD"
+            let content = read_trimmed(&dir.join("cpu.max"))?;
+            let mut fields = content.split_whitespace();
+            let quota: i64 = fields.next()?.parse().ok()?;
+            let period: i64 = fields.next()?.parse().ok()?;
+            (quota, period)
+        } else {
+            let quota = read_trimmed(&dir.join("cpu.cfs_quota_us"))?.parse().ok()?;
+            let period = read_trimmed(&dir.join("cpu.cfs_period_us"))?.parse().ok()?;
+            (quota, period)
+        };
+        (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+    }
+
+    /// Tempo de CPU acumulado do cgroup (microssegundos)
+    fn cpu_usage_us(&self) -> Option<u64> {
+        if self.unified {
+            stat_value(&self.read("cpu", "cpu.stat")?, "usage_usec")
+        } else {
+            // cpuacct.usage em nanossegundos
+            self.read_u64("cpuacct", "cpuacct.usage")
+                .map(|ns| ns / 1000)
+        }
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    Some(content.trim().to_string())
+}
+
+/// Limite em que `max` (v2) ou valores perto de i64::MAX (v1) significam
+/// ilimitado
+fn parse_limit(content: &str) -> Option<u64> {
+    let value: u64 = content.parse().ok()?;
+    (value < i64::MAX as u64 / 2).then_some(value)
+}
+
+/// Valor de uma chave em arquivos `chave valor` (memory.stat, cpu.stat)
+fn stat_value(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(' ')?;
+        if name != key {
+            return None;
+        }
+        value.trim().parse().ok()
+    })
+}
+
+/// Núcleos online do host
+fn host_cores() -> u64 {
+    // SAFETY: sysconf apenas consulta um valor de configuração
+    let cores = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+    if cores > 0 {
+        cores as u64
+    } else {
+        1
+    }
+}
+
+/// Identifica o runtime de container pelos marcadores conhecidos
+fn detect_container(root: &Path, membership: &str) -> Option<String> {
+    if root.join(".dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if root.join("run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    // Variável `container` do init (systemd-nspawn, lxc, podman)
+    if let Ok(environ) = fs::read(root.join("proc/1/environ")) {
+        let value = environ
+            .split(|b| *b == 0)
+            .find_map(|var| var.strip_prefix(b"container="));
+        if let Some(value) = value {
+            return Some(String::from_utf8_lossy(value).into_owned());
+        }
+    }
+    for (marker, runtime) in [
+        ("kubepods", "kubernetes"),
+        ("docker", "docker"),
+        ("libpod", "podman"),
+        ("lxc", "lxc"),
+    ] {
+        if membership.contains(marker) {
+            return Some(runtime.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::fixture_root;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn reads_cgroup_v2_limits_and_usage() {
+        let mut collector = CgroupCollector::new()
+            .with_root(fixture_root())
+            .with_host(16 * GIB, 8);
+        let stats = collector.collect().unwrap().unwrap();
+        assert_eq!(stats.version, 2);
+        assert_eq!(stats.path, "/system.slice/app.service");
+        assert_eq!(stats.memory_scope, ResourceScope::Cgroup);
+        assert_eq!(stats.memory_limit, Some(512 * 1024 * 1024));
+        // memory.current menos o inactive_file de memory.stat
+        assert_eq!(stats.memory_usage, 300_000_000 - 100_000_000);
+        assert_eq!(stats.cpu_limit_cores, Some(1.5));
+        assert_eq!(stats.cpu_scope, ResourceScope::Cgroup);
+        assert_eq!(stats.pids_current, Some(12));
+        assert_eq!(stats.pids_max, None); // "max"
+        assert_eq!(stats.container.as_deref(), Some("podman"));
+        // Percentual de CPU só a partir da segunda leitura
+        assert_eq!(stats.cpu_usage_percent, None);
+    }
+
+    #[test]
+    fn limits_above_the_host_are_host_scoped() {
+        let mut collector = CgroupCollector::new()
+            .with_root(fixture_root())
+            .with_host(256 * 1024 * 1024, 1);
+        let stats = collector.collect().unwrap().unwrap();
+        assert_eq!(stats.memory_scope, ResourceScope::Host);
+        assert_eq!(stats.memory_limit, None);
+        assert_eq!(stats.cpu_scope, ResourceScope::Host);
+    }
+
+    #[test]
+    fn computes_cpu_percent_between_readings() {
+        let mut collector = CgroupCollector::new()
+            .with_root(fixture_root())
+            .with_host(16 * GIB, 8);
+        // cpu.stat tem usage_usec 3000000; 1.5 núcleos por 2 s = 3000000 µs
+        let two_seconds_ago = Instant::now() - std::time::Duration::from_secs(2);
+        collector.last_cpu = Some((two_seconds_ago, 1_500_000));
+        let stats = collector.collect().unwrap().unwrap();
+        let percent = stats.cpu_usage_percent.unwrap();
+        assert!((percent - 50.0).abs() < 1.0, "{}", percent);
+    }
+
+    #[test]
+    fn nested_slices_use_the_smallest_ancestor_limit() {
+        // agent.service sem limite, dentro de user-1000.slice (256 MiB, 0.5
+        // núcleo), dentro de user.slice (1 GiB, 2 núcleos)
+        let root = fixture_root().with_file_name("nested_cgroup");
+        let mut collector = CgroupCollector::new()
+            .with_root(root)
+            .with_host(16 * GIB, 8);
+        let stats = collector.collect().unwrap().unwrap();
+        assert_eq!(
+            stats.path,
+            "/user.slice/user-1000.slice/user@1000.service/app.slice/agent.service"
+        );
+        assert_eq!(stats.memory_limit, Some(256 * 1024 * 1024));
+        assert_eq!(stats.memory_scope, ResourceScope::Cgroup);
+        assert_eq!(stats.cpu_limit_cores, Some(0.5));
+        assert_eq!(stats.cpu_scope, ResourceScope::Cgroup);
+        assert_eq!(stats.memory_usage, 50_000_000);
+    }
+
+    #[test]
+    fn no_cgroup_membership_means_none() {
+        let mut collector = CgroupCollector::new().with_root(fixture_root().join("nao-existe"));
+        assert_eq!(collector.collect().unwrap(), None);
+    }
+}
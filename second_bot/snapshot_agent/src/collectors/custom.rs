@@ -0,0 +1,197 @@
+use crate::collector::Collector;
+use crate::command;
+use serde::{Deserialize, Serialize};
+use snapshot_schema::CustomMetric;
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+
+/// =========================================================================
+/// COLETORES PERSONALIZADOS
+/// =========================================================================
+/// Comandos definidos em `custom_collectors` na configuração, executados a
+/// cada snapshot em paralelo.
+
+/// Caracteres de stdout bruto gravados no snapshot
+const MAX_RAW_CHARS: usize = 4096;
+
+/// Comando externo executado a cada snapshot
+/// O resultado vai para `Snapshot.custom[name]`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CustomCollectorConfig {
+    pub name: String,    // Chave do resultado em `custom`
+    pub command: String, // Executado com /bin/sh -c
+    #[serde(default = "default_collector_timeout")]
+    pub timeout_secs: u64, // Tempo máximo de execução
+    #[serde(default = "default_parse_json")]
+    pub parse_json: bool, // Tenta interpretar a saída como JSON
+}
+
+fn default_collector_timeout() -> u64 {
+    10
+}
+
+fn default_parse_json() -> bool {
+    true
+}
+
+/// Coletores personalizados como um coletor do agendador ("custom")
+/// O valor traz os resultados por nome e as mensagens de falha
+pub struct CustomCollectors {
+    collectors: Vec<CustomCollectorConfig>,
+}
+
+impl CustomCollectors {
+    pub fn new(collectors: Vec<CustomCollectorConfig>) -> Self {
+        Self { collectors }
+    }
+}
+
+impl Collector for CustomCollectors {
+    type Output = (BTreeMap<String, CustomMetric>, Vec<String>);
+
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    fn collect(&mut self) -> Result<Self::Output, String> {
+        Ok(run_collectors(&self.collectors))
+    }
+}
+
+/// Executa todos os coletores em paralelo
+/// Retorna os resultados por nome e as mensagens de falha
+fn run_collectors(
+    collectors: &[CustomCollectorConfig],
+) -> (BTreeMap<String, CustomMetric>, Vec<String>) {
+    let handles: Vec<_> = collectors
+        .iter()
+        .cloned()
+        .map(|collector| thread::spawn(move || (collector.name.clone(), run_collector(&collector))))
+        .collect();
+
+    let mut results = BTreeMap::new();
+    let mut errors = Vec::new();
+    for handle in handles {
+        let (name, (metric, error)) = handle.join().expect("Thread de coletor falhou");
+        if let Some(error) = error {
+            errors.push(error);
+        }
+        results.insert(name, metric);
+    }
+    (results, errors)
+}
+
+/// Executa um coletor, aplicando o timeout configurado
+fn run_collector(collector: &CustomCollectorConfig) -> (CustomMetric, Option<String>) {
+    let mut metric = CustomMetric {
+        exit_code: None,
+        duration_ms: 0,
+        timed_out: false,
+        value: None,
+        stdout: None,
+    };
+
+    let timeout = Duration::from_secs(collector.timeout_secs);
+    let output = match command::run_shell(&collector.command, timeout, true) {
+        Ok(output) => output,
+        Err(e) => return (metric, Some(format!("Coletor {}: {}", collector.name, e))),
+    };
+    metric.exit_code = output.exit_code;
+    metric.duration_ms = output.duration_ms;
+    metric.timed_out = output.timed_out;
+
+    if !output.timed_out {
+        // Saída interpretada como JSON quando possível, senão guardada como texto
+        let parsed = if collector.parse_json {
+            serde_json::from_str(output.stdout.trim()).ok()
+        } else {
+            None
+        };
+        match parsed {
+            Some(value) => metric.value = Some(value),
+            None => {
+                metric.stdout = Some(
+                    output
+                        .stdout
+                        .trim_end()
+                        .chars()
+                        .take(MAX_RAW_CHARS)
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    let error = output
+        .failure()
+        .map(|failure| format!("Coletor {}: {}", collector.name, failure));
+    (metric, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn collector(name: &str, command: &str) -> CustomCollectorConfig {
+        CustomCollectorConfig {
+            name: name.to_string(),
+            command: command.to_string(),
+            timeout_secs: 5,
+            parse_json: true,
+        }
+    }
+
+    #[test]
+    fn json_and_text_outputs() {
+        let mut text = collector("texto", "echo 'não é json'");
+        text.parse_json = false;
+        let mut collectors = CustomCollectors::new(vec![
+            collector("json", r#"echo '{"filas": 3}'"#),
+            collector("invalido", "echo '{quebrado'"),
+            text,
+        ]);
+
+        let (results, errors) = collectors.collect().unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(results["json"].value, Some(json!({"filas": 3})));
+        assert_eq!(results["json"].exit_code, Some(0));
+        // JSON inválido fica como texto
+        assert_eq!(results["invalido"].value, None);
+        assert_eq!(results["invalido"].stdout.as_deref(), Some("{quebrado"));
+        assert_eq!(results["texto"].stdout.as_deref(), Some("não é json"));
+    }
+
+    #[test]
+    fn failures_are_reported_with_the_result() {
+        let mut slow = collector("lento", "sleep 5");
+        slow.timeout_secs = 0;
+        let mut collectors = CustomCollectors::new(vec![
+            collector("falha", "echo 'sem permissão' >&2; exit 3"),
+            slow,
+        ]);
+
+        let (results, mut errors) = collectors.collect().unwrap();
+        errors.sort();
+        assert_eq!(results["falha"].exit_code, Some(3));
+        assert!(results["lento"].timed_out);
+        assert_eq!(results["lento"].exit_code, None);
+        assert_eq!(
+            errors[0],
+            "Coletor falha: comando terminou com código 3: sem permissão"
+        );
+        assert!(errors[1].starts_with("Coletor lento: tempo limite excedido"));
+    }
+
+    #[test]
+    fn raw_output_is_truncated() {
+        let mut collectors = CustomCollectors::new(vec![collector(
+            "grande",
+            "head -c 10000 /dev/zero | tr '\\0' x",
+        )]);
+        let (results, _) = collectors.collect().unwrap();
+        let stdout = results["grande"].stdout.as_ref().unwrap();
+        assert_eq!(stdout.chars().count(), MAX_RAW_CHARS);
+    }
+}
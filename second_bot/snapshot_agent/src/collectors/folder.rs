@@ -0,0 +1,308 @@
+use crate::collector::Collector;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snapshot_schema::FolderListing;
+use std::fs;
+use std::path::PathBuf;
+
+/// =========================================================================
+/// PASTA MONITORADA
+/// =========================================================================
+/// Nomes das entradas da pasta monitorada (home no Linux, C:\Users\Public
+/// no Windows). Pastas alternativas são tentadas em ordem quando a
+/// principal não pode ser lida.
+///
+/// Os nomes vão para o log de monitoramento, então passam antes pelos
+/// filtros de privacidade (`FolderFilter`): padrões de inclusão/exclusão,
+/// arquivos ocultos, hash ou ocultação dos nomes e limite de entradas.
+
+/// Como os nomes aparecem no snapshot
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NameMode {
+    #[default]
+    Plain, // Nome original
+    Hash,   // 16 primeiros dígitos do SHA-256 (com a chave do coletor)
+    Redact, // "<oculto>", mantendo só a extensão
+}
+
+/// Filtros de privacidade aplicados a uma pasta
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct FolderFilter {
+    pub include: Vec<String>, // Padrões com * e ?; vazio aceita todos
+    pub exclude: Vec<String>, // Têm precedência sobre `include`
+    pub hidden: bool,         // Inclui entradas iniciadas por "."
+    pub names: NameMode,
+    pub max_entries: Option<usize>, // Limite de nomes gravados (o total é sempre informado)
+}
+
+impl Default for FolderFilter {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            hidden: true,
+            names: NameMode::Plain,
+            max_entries: None,
+        }
+    }
+}
+
+impl FolderFilter {
+    /// Se a entrada passa pelos filtros (sempre pelo nome original)
+    pub fn accepts(&self, name: &str) -> bool {
+        if !self.hidden && name.starts_with('.') {
+            return false;
+        }
+        if self.exclude.iter().any(|pattern| glob_match(pattern, name)) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|pattern| glob_match(pattern, name))
+    }
+
+    /// Aplica os filtros a uma lista de nomes
+    /// A lista sai ordenada pelo nome original para que o limite corte
+    /// sempre as mesmas entradas
+    pub fn apply(&self, mut names: Vec<String>, hash_key: &str) -> (Vec<String>, u64) {
+        names.retain(|name| self.accepts(name));
+        names.sort();
+        let total = names.len() as u64;
+        if let Some(max) = self.max_entries {
+            names.truncate(max);
+        }
+        let names = match self.names {
+            NameMode::Plain => names,
+            NameMode::Hash => names.iter().map(|name| hash_name(hash_key, name)).collect(),
+            NameMode::Redact => names.iter().map(|name| redact_name(name)).collect(),
+        };
+        (names, total)
+    }
+}
+
+/// Hash do nome com uma chave secreta (o sal de `identity`, nunca gravado no
+/// log), para que a mesma entrada seja reconhecível entre snapshots sem expor
+/// o nome. Quem lê o log não conhece a chave e não consegue testar nomes
+/// comuns contra os hashes, nem compará-los com os de outros hosts
+fn hash_name(key: &str, name: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update([0]);
+    hasher.update(name.as_bytes());
+    hex::encode(&hasher.finalize()[..8])
+}
+
+/// Nome oculto, mantendo a extensão (ex.: "<oculto>.pdf")
+fn redact_name(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => {
+            format!("<oculto>.{}", extension)
+        }
+        _ => "<oculto>".to_string(),
+    }
+}
+
+/// Compara um nome com um padrão em que `*` casa qualquer sequência e `?`
+/// um caractere
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Posição do último `*` e do trecho do nome que ele está cobrindo
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // Recomeça depois do `*`, cobrindo um caractere a mais
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Lista a primeira pasta legível entre `paths`
+pub struct FolderCollector {
+    paths: Vec<PathBuf>,
+    filter: FolderFilter,
+    hash_key: String,
+}
+
+impl FolderCollector {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            paths: vec![path],
+            filter: FolderFilter::default(),
+            hash_key: String::new(),
+        }
+    }
+
+    /// Pasta tentada se as anteriores não puderem ser lidas
+    pub fn with_fallback(mut self, path: PathBuf) -> Self {
+        self.paths.push(path);
+        self
+    }
+
+    /// Filtros de privacidade da pasta
+    pub fn with_filter(mut self, filter: FolderFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Chave secreta misturada aos nomes em `NameMode::Hash`
+    /// Não use um valor que apareça nos registros (como o agent_id)
+    pub fn with_hash_key(mut self, key: &str) -> Self {
+        self.hash_key = key.to_string();
+        self
+    }
+}
+
+impl Collector for FolderCollector {
+    type Output = FolderListing;
+
+    fn name(&self) -> &'static str {
+        "folder"
+    }
+
+    /// O erro informado é o da pasta principal
+    fn collect(&mut self) -> Result<FolderListing, String> {
+        let mut first_error = None;
+        for path in &self.paths {
+            match fs::read_dir(path) {
+                Ok(entries) => {
+                    let names = entries
+                        .filter_map(|entry| {
+                            entry
+                                .ok()
+                                .map(|e| e.file_name().to_string_lossy().into_owned())
+                        })
+                        .collect();
+                    let (files, total) = self.filter.apply(names, &self.hash_key);
+                    return Ok(FolderListing {
+                        path: path.to_string_lossy().into_owned(),
+                        files,
+                        total,
+                    });
+                }
+                Err(e) => {
+                    first_error
+                        .get_or_insert_with(|| format!("Erro ao ler pasta {:?}: {}", path, e));
+                }
+            }
+        }
+        Err(first_error.unwrap_or_else(|| "Nenhuma pasta configurada".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::fixture_root;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn lists_folder_entries() {
+        let mut collector = FolderCollector::new(fixture_root().join("proc/pressure"));
+        let listing = collector.collect().unwrap();
+        assert_eq!(listing.files, vec!["cpu", "io", "memory"]);
+        assert_eq!(listing.total, 3);
+    }
+
+    #[test]
+    fn falls_back_when_folder_is_missing() {
+        let mut collector = FolderCollector::new(fixture_root().join("nao-existe"))
+            .with_fallback(fixture_root().join("proc/net"));
+        let listing = collector.collect().unwrap();
+        assert!(listing.files.contains(&"tcp".to_string()));
+        assert!(listing.path.ends_with("proc/net"), "{}", listing.path);
+    }
+
+    #[test]
+    fn reports_primary_folder_error() {
+        let mut collector = FolderCollector::new(fixture_root().join("nao-existe"))
+            .with_fallback(fixture_root().join("tambem-nao"));
+        let error = collector.collect().unwrap_err();
+        assert!(error.contains("nao-existe"), "{}", error);
+    }
+
+    #[test]
+    fn matches_glob_patterns() {
+        assert!(glob_match("*.pdf", "contrato.pdf"));
+        assert!(!glob_match("*.pdf", "contrato.pdf.bak"));
+        assert!(glob_match("foto-??.jpg", "foto-01.jpg"));
+        assert!(!glob_match("foto-??.jpg", "foto-1.jpg"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("Documentos", "Documentos"));
+        assert!(!glob_match("Doc", "Documentos"));
+    }
+
+    #[test]
+    fn filters_hidden_included_and_excluded_entries() {
+        let filter = FolderFilter {
+            include: names(&["*.txt", "*.pdf", ".*"]),
+            exclude: names(&["privado*"]),
+            hidden: false,
+            ..FolderFilter::default()
+        };
+        let (files, total) = filter.apply(
+            names(&["b.txt", "a.pdf", ".bashrc", "privado.txt", "foto.jpg"]),
+            "",
+        );
+        assert_eq!(files, vec!["a.pdf", "b.txt"]);
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn truncates_but_reports_total() {
+        let filter = FolderFilter {
+            max_entries: Some(2),
+            ..FolderFilter::default()
+        };
+        let (files, total) = filter.apply(names(&["c", "a", "d", "b"]), "");
+        assert_eq!(files, vec!["a", "b"]);
+        assert_eq!(total, 4);
+    }
+
+    #[test]
+    fn hashes_names_with_the_key() {
+        let filter = FolderFilter {
+            names: NameMode::Hash,
+            ..FolderFilter::default()
+        };
+        let (first, _) = filter.apply(names(&["Documentos"]), "sal-1");
+        let (again, _) = filter.apply(names(&["Documentos"]), "sal-1");
+        let (other_host, _) = filter.apply(names(&["Documentos"]), "sal-2");
+        assert_eq!(first, again);
+        assert_ne!(first, other_host);
+        assert_eq!(first[0].len(), 16);
+        assert!(!first[0].contains("Documentos"));
+    }
+
+    #[test]
+    fn redacts_names_keeping_extension() {
+        let filter = FolderFilter {
+            names: NameMode::Redact,
+            ..FolderFilter::default()
+        };
+        let (files, total) = filter.apply(names(&["extrato.pdf", ".bashrc", "Documentos"]), "");
+        assert_eq!(files, vec!["<oculto>", "<oculto>", "<oculto>.pdf"]);
+        assert_eq!(total, 3);
+    }
+}
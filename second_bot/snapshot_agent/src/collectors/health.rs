@@ -0,0 +1,411 @@
+use crate::collector::Collector;
+use snapshot_schema::{DiskIo, Pressure, PressureAverages, PressureStall, SystemHealth};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// =========================================================================
+/// SAÚDE DO KERNEL (SWAP, PSI, DESCRITORES, PROCESSOS E I/O)
+/// =========================================================================
+/// A memória "usada" sozinha não mostra se o sistema está sofrendo. Estes
+/// indicadores vêm direto de /proc:
+///   /proc/meminfo        - SwapTotal/SwapFree
+///   /proc/pressure/*     - médias de pressão (PSI) de cpu, memory e io
+///   /proc/sys/fs/file-nr - descritores alocados e limite do sistema
+///   /proc/diskstats      - taxas de I/O por disco (diferença entre coletas)
+///   /proc/<pid>/stat     - processos, threads e zumbis (ProcessesCollector)
+///
+/// A contagem de processos é um coletor separado porque é a parte cara
+/// (varre todo o /proc) e pode ser omitida pelo orçamento do agente.
+
+/// Tamanho do setor usado pelo /proc/diskstats (fixo, independe do disco)
+const SECTOR_SIZE: f64 = 512.0;
+
+/// Contadores acumulados de um disco
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct DiskCounters {
+    reads: u64,
+    sectors_read: u64,
+    writes: u64,
+    sectors_written: u64,
+    io_ms: u64,
+}
+
+/// Processos, threads e zumbis
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProcessCounts {
+    pub processes: u64,
+    pub threads: u64,
+    pub zombies: u64,
+}
+
+/// Swap, PSI, descritores e I/O por disco
+/// `processes`, `threads` e `zombies` ficam zerados: vêm do ProcessesCollector
+pub struct HealthCollector {
+    root: PathBuf,
+    last_diskstats: Option<(Instant, HashMap<String, DiskCounters>)>, // Para as taxas
+}
+
+impl HealthCollector {
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            last_diskstats: None,
+        }
+    }
+
+    /// Lê /proc e /sys abaixo de `root` em vez de /
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Taxas de I/O por disco desde a coleta anterior (vazio na primeira)
+    fn disk_rates(&mut self) -> BTreeMap<String, DiskIo> {
+        let current = read_diskstats(&self.root);
+        let now = Instant::now();
+        let previous = self.last_diskstats.replace((now, current.clone()));
+
+        let mut rates = BTreeMap::new();
+        let Some((at, previous)) = previous else {
+            return rates;
+        };
+        let elapsed = now.duration_since(at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return rates;
+        }
+        for (name, counters) in &current {
+            // Disco novo ou contadores zerados (remoção e reinserção)
+            let Some(before) = previous.get(name) else {
+                continue;
+            };
+            if counters.reads < before.reads || counters.writes < before.writes {
+                continue;
+            }
+            let per_sec = |after: u64, before: u64| (after - before) as f64 / elapsed;
+            rates.insert(
+                name.clone(),
+                DiskIo {
+                    reads_per_sec: per_sec(counters.reads, before.reads),
+                    writes_per_sec: per_sec(counters.writes, before.writes),
+                    read_bytes_per_sec: per_sec(counters.sectors_read, before.sectors_read)
+                        * SECTOR_SIZE,
+                    write_bytes_per_sec: per_sec(counters.sectors_written, before.sectors_written)
+                        * SECTOR_SIZE,
+                    busy_percent: (counters.io_ms.saturating_sub(before.io_ms) as f64
+                        / (elapsed * 1000.0)
+                        * 100.0)
+                        .min(100.0) as f32,
+                },
+            );
+        }
+        rates
+    }
+}
+
+impl Default for HealthCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector for HealthCollector {
+    type Output = SystemHealth;
+
+    fn name(&self) -> &'static str {
+        "health"
+    }
+
+    /// Fontes indisponíveis ficam zeradas ou ausentes; nunca falha
+    fn collect(&mut self) -> Result<SystemHealth, String> {
+        let (swap_total, swap_used) = read_swap(&self.root);
+        let (open_fds, max_fds) = read_file_nr(&self.root);
+        Ok(SystemHealth {
+            swap_total,
+            swap_used,
+            pressure: read_pressure(&self.root),
+            open_fds,
+            max_fds,
+            processes: 0,
+            threads: 0,
+            zombies: 0,
+            disks: self.disk_rates(),
+        })
+    }
+}
+
+/// Processos, threads e zumbis a partir de /proc/<pid>/stat
+pub struct ProcessesCollector {
+    root: PathBuf,
+}
+
+impl ProcessesCollector {
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+        }
+    }
+
+    /// Lê /proc abaixo de `root` em vez de /
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+}
+
+impl Default for ProcessesCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector for ProcessesCollector {
+    type Output = ProcessCounts;
+
+    fn name(&self) -> &'static str {
+        "processes"
+    }
+
+    fn collect(&mut self) -> Result<ProcessCounts, String> {
+        let proc = self.root.join("proc");
+        let entries = fs::read_dir(&proc).map_err(|e| format!("Erro ao ler {:?}: {}", proc, e))?;
+        let mut counts = ProcessCounts::default();
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name();
+            if !name
+                .to_str()
+                .is_some_and(|n| n.bytes().all(|b| b.is_ascii_digit()))
+            {
+                continue;
+            }
+            // Processos que terminaram durante a leitura são ignorados
+            let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+                continue;
+            };
+            // Campos após o nome (que pode conter espaços): estado é o primeiro,
+            // num_threads o décimo oitavo
+            let Some((_, rest)) = stat.rsplit_once(')') else {
+                continue;
+            };
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            counts.processes += 1;
+            if fields.first() == Some(&"Z") {
+                counts.zombies += 1;
+            }
+            counts.threads += fields
+                .get(17)
+                .and_then(|n| n.parse::<u64>().ok())
+                .unwrap_or(1);
+        }
+        Ok(counts)
+    }
+}
+
+/// Swap total e usado (bytes)
+fn read_swap(root: &Path) -> (u64, u64) {
+    let meminfo = fs::read_to_string(root.join("proc/meminfo")).unwrap_or_default();
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+            .unwrap_or(0)
+            * 1024
+    };
+    let total = field("SwapTotal");
+    (total, total.saturating_sub(field("SwapFree")))
+}
+
+/// Descritores alocados e limite: "alocados livres máximo"
+fn read_file_nr(root: &Path) -> (u64, u64) {
+    let content = fs::read_to_string(root.join("proc/sys/fs/file-nr")).unwrap_or_default();
+    let fields: Vec<u64> = content
+        .split_whitespace()
+        .filter_map(|field| field.parse().ok())
+        .collect();
+    match fields.as_slice() {
+        [allocated, free, max] => (allocated.saturating_sub(*free), *max),
+        _ => (0, 0),
+    }
+}
+
+/// PSI de cpu, memória e I/O; `None` se o kernel não expõe /proc/pressure
+fn read_pressure(root: &Path) -> Option<Pressure> {
+    let read = |resource: &str| {
+        let content = fs::read_to_string(root.join("proc/pressure").join(resource)).ok()?;
+        parse_pressure(&content)
+    };
+    let pressure = Pressure {
+        cpu: read("cpu"),
+        memory: read("memory"),
+        io: read("io"),
+    };
+    if pressure.cpu.is_none() && pressure.memory.is_none() && pressure.io.is_none() {
+        return None;
+    }
+    Some(pressure)
+}
+
+/// Linhas "some avg10=.. avg60=.. avg300=.. total=.." e "full ..."
+fn parse_pressure(content: &str) -> Option<PressureStall> {
+    let averages = |kind: &str| {
+        let line = content.lines().find(|line| line.starts_with(kind))?;
+        let value = |name: &str| {
+            line.split_whitespace()
+                .find_map(|field| field.strip_prefix(name)?.strip_prefix('='))?
+                .parse::<f32>()
+                .ok()
+        };
+        Some(PressureAverages {
+            avg10: value("avg10")?,
+            avg60: value("avg60")?,
+            avg300: value("avg300")?,
+        })
+    };
+    Some(PressureStall {
+        some: averages("some")?,
+        full: averages("full"),
+    })
+}
+
+/// Contadores de /proc/diskstats dos discos inteiros
+///
+/// Partições e dispositivos loop/ram ficam de fora; os discos são os listados
+/// em /sys/block
+fn read_diskstats(root: &Path) -> HashMap<String, DiskCounters> {
+    let disks: Option<HashSet<String>> = fs::read_dir(root.join("sys/block")).ok().map(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect()
+    });
+    let content = fs::read_to_string(root.join("proc/diskstats")).unwrap_or_default();
+
+    content
+        .lines()
+        .filter_map(|line| {
+            // major minor nome reads merged setores ms writes merged setores ms em_andamento ms_io ...
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let name = *fields.get(2)?;
+            if name.starts_with("loop") || name.starts_with("ram") {
+                return None;
+            }
+            if disks.as_ref().is_some_and(|disks| !disks.contains(name)) {
+                return None;
+            }
+            let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+            Some((
+                name.to_string(),
+                DiskCounters {
+                    reads: field(3)?,
+                    sectors_read: field(5)?,
+                    writes: field(7)?,
+                    sectors_written: field(9)?,
+                    io_ms: field(12)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::fixture_root;
+    use std::time::Duration;
+
+    #[test]
+    fn reads_swap_descriptors_and_pressure() {
+        let health = HealthCollector::new()
+            .with_root(fixture_root())
+            .collect()
+            .unwrap();
+        assert_eq!(health.swap_total, 2048 * 1024 * 1024);
+        assert_eq!(health.swap_used, 512 * 1024 * 1024);
+        assert_eq!(health.open_fds, 1344);
+        assert_eq!(health.max_fds, 9223372036854775807);
+
+        let pressure = health.pressure.unwrap();
+        let cpu = pressure.cpu.unwrap();
+        assert_eq!(cpu.some.avg10, 1.5);
+        assert_eq!(cpu.some.avg300, 0.25);
+        assert!(cpu.full.is_none());
+        let memory = pressure.memory.unwrap();
+        assert_eq!(memory.full.unwrap().avg60, 0.1);
+        // Primeira coleta não tem base para as taxas
+        assert!(health.disks.is_empty());
+    }
+
+    #[test]
+    fn ignores_partitions_and_loop_devices() {
+        let disks = read_diskstats(&fixture_root());
+        let mut names: Vec<&String> = disks.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["nvme0n1", "sda"]);
+        assert_eq!(
+            disks["sda"],
+            DiskCounters {
+                reads: 1000,
+                sectors_read: 20000,
+                writes: 2000,
+                sectors_written: 40000,
+                io_ms: 5000,
+            }
+        );
+    }
+
+    #[test]
+    fn computes_disk_rates_since_previous_collection() {
+        let mut collector = HealthCollector::new().with_root(fixture_root());
+        let mut previous = read_diskstats(&fixture_root());
+        let sda = previous.get_mut("sda").unwrap();
+        sda.reads -= 100;
+        sda.sectors_read -= 2000;
+        sda.writes -= 200;
+        sda.io_ms -= 1000;
+        // Contadores menores que os anteriores: disco reinserido, sem taxa
+        previous.get_mut("nvme0n1").unwrap().reads += 1;
+        let ten_seconds_ago = Instant::now() - Duration::from_secs(10);
+        collector.last_diskstats = Some((ten_seconds_ago, previous));
+
+        let disks = collector.collect().unwrap().disks;
+        assert_eq!(disks.len(), 1);
+        let sda = &disks["sda"];
+        assert!((sda.reads_per_sec - 10.0).abs() < 0.1);
+        assert!((sda.writes_per_sec - 20.0).abs() < 0.1);
+        assert!((sda.read_bytes_per_sec - 200.0 * SECTOR_SIZE).abs() < 2.0 * SECTOR_SIZE);
+        assert_eq!(sda.write_bytes_per_sec, 0.0);
+        assert!((sda.busy_percent - 10.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn counts_processes_threads_and_zombies() {
+        let counts = ProcessesCollector::new()
+            .with_root(fixture_root())
+            .collect()
+            .unwrap();
+        assert_eq!(
+            counts,
+            ProcessCounts {
+                processes: 3,
+                threads: 1 + 12 + 1,
+                zombies: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_sources_are_zero_or_absent() {
+        let health = HealthCollector::new()
+            .with_root(fixture_root().join("nao-existe"))
+            .collect()
+            .unwrap();
+        assert_eq!((health.swap_total, health.open_fds), (0, 0));
+        assert!(health.pressure.is_none());
+        assert!(ProcessesCollector::new()
+            .with_root(fixture_root().join("nao-existe"))
+            .collect()
+            .is_err());
+    }
+}
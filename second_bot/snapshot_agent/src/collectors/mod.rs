@@ -0,0 +1,54 @@
+/// =========================================================================
+/// COLETORES DO SNAPSHOT
+/// =========================================================================
+/// Cada coletor implementa `Collector` e preenche uma parte do `Snapshot`:
+///   memory, cpu, disk - sysinfo (todas as plataformas)
+///   folder            - arquivos da pasta monitorada
+///   health            - swap, PSI, descritores e I/O por disco (Linux)
+///   processes         - processos, threads e zumbis (Linux)
+///   ports             - sockets em escuta e processo dono (Linux)
+///   cgroup            - limites e uso do cgroup do agente (Linux)
+///   sessions          - sessões ativas do utmp (Linux); o `SessionTracker`
+///                       acompanha o wtmp para os eventos de login
+///   custom            - comandos definidos pelo usuário (Unix)
+///
+/// Os coletores Linux leem /proc e /sys a partir de uma raiz configurável
+/// (`with_root`), o que permite testá-los com os arquivos de
+/// tests/fixtures/root.
+mod folder;
+mod system;
+
+#[cfg(unix)]
+mod custom;
+
+#[cfg(target_os = "linux")]
+mod cgroup;
+#[cfg(target_os = "linux")]
+mod health;
+#[cfg(target_os = "linux")]
+mod ports;
+#[cfg(target_os = "linux")]
+mod sessions;
+
+pub use folder::{FolderCollector, FolderFilter, NameMode};
+pub use system::{
+    CpuCollector, DiskCollector, DiskUsage, MemoryCollector, MemoryUsage, SystemSource,
+};
+
+#[cfg(unix)]
+pub use custom::{CustomCollectorConfig, CustomCollectors};
+
+#[cfg(target_os = "linux")]
+pub use cgroup::CgroupCollector;
+#[cfg(target_os = "linux")]
+pub use health::{HealthCollector, ProcessCounts, ProcessesCollector};
+#[cfg(target_os = "linux")]
+pub use ports::{parse_proc_net, PortsCollector};
+#[cfg(target_os = "linux")]
+pub use sessions::{read_sessions, SessionTracker, SessionsCollector};
+
+/// Raiz falsa com /proc e /sys de exemplo usada nos testes
+#[cfg(test)]
+fn fixture_root() -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/root")
+}
@@ -0,0 +1,249 @@
+use crate::collector::Collector;
+use snapshot_schema::ListeningSocket;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+/// =========================================================================
+/// PORTAS EM ESCUTA
+/// =========================================================================
+/// Sockets em escuta do namespace de rede do agente, lidos de
+/// /proc/net/{tcp,tcp6,udp,udp6}: TCP no estado LISTEN e UDP sem conexão.
+/// O processo dono vem do inode do socket, procurado nos links
+/// /proc/<pid>/fd/* ("socket:[inode]").
+
+/// Estado TCP_LISTEN em /proc/net/tcp*
+const TCP_LISTEN: &str = "0A";
+
+/// Estado de um socket UDP sem conexão (TCP_CLOSE)
+const UDP_UNCONNECTED: &str = "07";
+
+/// Sockets em escuta e o processo dono de cada um
+pub struct PortsCollector {
+    root: PathBuf,
+}
+
+impl PortsCollector {
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+        }
+    }
+
+    /// Lê /proc abaixo de `root` em vez de /
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+}
+
+impl Default for PortsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collector for PortsCollector {
+    type Output = Vec<ListeningSocket>;
+
+    fn name(&self) -> &'static str {
+        "ports"
+    }
+
+    /// Sockets ordenados por protocolo, porta e endereço
+    /// Um mesmo endereço aberto por vários processos (SO_REUSEPORT) aparece
+    /// uma vez, com o primeiro dono encontrado
+    fn collect(&mut self) -> Result<Vec<ListeningSocket>, String> {
+        let proc = self.root.join("proc");
+        let ephemeral = ephemeral_range(&proc);
+        let mut found = Vec::new();
+        for protocol in ["tcp", "tcp6", "udp", "udp6"] {
+            // Arquivo ausente: protocolo desativado no kernel (ex.: sem IPv6)
+            if let Ok(content) = fs::read_to_string(proc.join("net").join(protocol)) {
+                found.extend(parse_proc_net(&content, protocol, ephemeral));
+            }
+        }
+
+        let inodes: HashSet<u64> = found.iter().map(|(_, inode)| *inode).collect();
+        let owners = socket_owners(&proc, &inodes);
+        let mut sockets: BTreeMap<(String, u16, String), ListeningSocket> = BTreeMap::new();
+        for (mut socket, inode) in found {
+            if let Some((pid, process)) = owners.get(&inode) {
+                socket.pid = Some(*pid);
+                socket.process = Some(process.clone());
+            }
+            sockets
+                .entry((socket.protocol.clone(), socket.port, socket.address.clone()))
+                .or_insert(socket);
+        }
+        Ok(sockets.into_values().collect())
+    }
+}
+
+/// Interpreta o conteúdo de um /proc/net/{tcp,tcp6,udp,udp6}
+///
+/// Sockets UDP em portas efêmeras (`ephemeral`) são descartados: são, em
+/// geral, clientes (consultas DNS, NTP) e gerariam eventos a cada snapshot
+pub fn parse_proc_net(
+    content: &str,
+    protocol: &str,
+    ephemeral: Option<(u16, u16)>,
+) -> Vec<(ListeningSocket, u64)> {
+    let udp = protocol.starts_with("udp");
+    content
+        .lines()
+        .skip(1) // Cabeçalho
+        .filter_map(|line| {
+            // sl local_address rem_address st tx:rx tr:when retrnsmt uid timeout inode
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let state = *fields.get(3)?;
+            if state != if udp { UDP_UNCONNECTED } else { TCP_LISTEN } {
+                return None;
+            }
+            let (address, port) = parse_address(fields.get(1)?)?;
+            if udp && ephemeral.is_some_and(|(low, high)| (low..=high).contains(&port)) {
+                return None;
+            }
+            let inode = fields.get(9)?.parse::<u64>().ok()?;
+            let socket = ListeningSocket {
+                protocol: protocol.to_string(),
+                address,
+                port,
+                uid: fields.get(7)?.parse().ok()?,
+                pid: None,
+                process: None,
+            };
+            Some((socket, inode))
+        })
+        .collect()
+}
+
+/// "0100007F:0016" -> ("127.0.0.1", 22)
+/// O kernel imprime cada palavra de 32 bits do endereço na ordem do host
+fn parse_address(field: &str) -> Option<(String, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let mut bytes = Vec::with_capacity(16);
+    for chunk in address.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let address = match bytes.len() {
+        4 => Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string(),
+        16 => Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string(),
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+/// Faixa de portas efêmeras (/proc/sys/net/ipv4/ip_local_port_range)
+fn ephemeral_range(proc: &Path) -> Option<(u16, u16)> {
+    let content = fs::read_to_string(proc.join("sys/net/ipv4/ip_local_port_range")).ok()?;
+    let mut fields = content.split_whitespace().map(|f| f.parse::<u16>().ok());
+    Some((fields.next()??, fields.next()??))
+}
+
+/// PID e nome do processo dono de cada inode de socket procurado
+/// Processos de outros usuários só são visíveis com privilégios
+fn socket_owners(proc: &Path, inodes: &HashSet<u64>) -> HashMap<u64, (u32, String)> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = fs::read_dir(proc) else {
+        return owners;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        if owners.len() == inodes.len() {
+            break;
+        }
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.filter_map(Result::ok) {
+            let Ok(target) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:[")?.strip_suffix(']'))
+                .and_then(|inode| inode.parse::<u64>().ok());
+            if let Some(inode) = inode.filter(|inode| inodes.contains(inode)) {
+                owners.entry(inode).or_insert_with(|| {
+                    let comm = fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+                    (pid, comm.trim().to_string())
+                });
+            }
+        }
+    }
+    owners
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::fixture_root;
+
+    #[test]
+    fn parses_listening_tcp_sockets() {
+        let content = fs::read_to_string(fixture_root().join("proc/net/tcp")).unwrap();
+        let sockets = parse_proc_net(&content, "tcp", None);
+        // A conexão ESTABLISHED (estado 01) fica de fora
+        assert_eq!(sockets.len(), 2);
+        let (ssh, inode) = &sockets[0];
+        assert_eq!(
+            (ssh.address.as_str(), ssh.port, ssh.uid),
+            ("0.0.0.0", 22, 0)
+        );
+        assert_eq!(*inode, 12345);
+        assert_eq!(sockets[1].0.address, "127.0.0.1");
+        assert_eq!(sockets[1].0.port, 5432);
+    }
+
+    #[test]
+    fn decodes_ipv6_addresses() {
+        let content = fs::read_to_string(fixture_root().join("proc/net/tcp6")).unwrap();
+        let sockets = parse_proc_net(&content, "tcp6", None);
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].0.key(), "tcp6 [::]:80");
+        assert_eq!(sockets[1].0.key(), "tcp6 [::1]:631");
+    }
+
+    #[test]
+    fn skips_udp_sockets_on_ephemeral_ports() {
+        let content = fs::read_to_string(fixture_root().join("proc/net/udp")).unwrap();
+        let all = parse_proc_net(&content, "udp", None);
+        assert_eq!(all.len(), 2);
+        let servers = parse_proc_net(&content, "udp", Some((32768, 60999)));
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].0.port, 53);
+    }
+
+    #[test]
+    fn finds_socket_owners() {
+        let sockets = PortsCollector::new()
+            .with_root(fixture_root())
+            .collect()
+            .unwrap();
+        let keys: Vec<String> = sockets.iter().map(ListeningSocket::key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "tcp 0.0.0.0:22",
+                "tcp 127.0.0.1:5432",
+                "tcp6 [::]:80",
+                "tcp6 [::1]:631",
+                "udp 127.0.0.53:53",
+            ]
+        );
+        // Inode 12345 aparece em /proc/100/fd; os demais ficam sem dono
+        assert_eq!(sockets[0].pid, Some(100));
+        assert_eq!(sockets[0].process.as_deref(), Some("sshd"));
+        assert_eq!(sockets[1].pid, None);
+    }
+}
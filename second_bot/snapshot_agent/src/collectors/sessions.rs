@@ -0,0 +1,339 @@
+use crate::collector::Collector;
+use chrono::{SecondsFormat, Utc};
+use snapshot_schema::{LoginSession, Record, SessionEvent, SCHEMA_VERSION};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// =========================================================================
+/// SESSÕES DE LOGIN (UTMP/WTMP)
+/// =========================================================================
+/// utmp (/var/run/utmp) lista as sessões ativas e entra no snapshot; wtmp
+/// (/var/log/wtmp) é acompanhado como um tail, gerando SESSION_OPENED e
+/// SESSION_CLOSED inclusive para sessões que abriram e fecharam entre dois
+/// snapshots. Ambos usam o `struct utmp` da glibc (Linux x86_64/aarch64).
+
+/// Tamanho de uma entrada `struct utmp`
+const UTMP_SIZE: usize = 384;
+
+/// Valores de `ut_type`
+const USER_PROCESS: i16 = 7;
+const DEAD_PROCESS: i16 = 8;
+
+/// Entrada decodificada do utmp/wtmp
+struct UtmpEntry {
+    kind: i16,
+    pid: i32,
+    line: String,
+    user: String,
+    host: String,
+    time: u64,
+}
+
+impl UtmpEntry {
+    fn to_session(&self) -> LoginSession {
+        LoginSession {
+            user: self.user.clone(),
+            tty: self.line.clone(),
+            host: self.host.clone(),
+            login_time: self.time,
+            pid: self.pid,
+        }
+    }
+}
+
+/// Decodifica uma entrada (ut_type, ut_pid, ut_line, ut_user, ut_host, ut_tv)
+fn parse_entry(buf: &[u8]) -> UtmpEntry {
+    let i16_at = |at: usize| i16::from_le_bytes([buf[at], buf[at + 1]]);
+    let i32_at = |at: usize| i32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]]);
+    UtmpEntry {
+        kind: i16_at(0),
+        pid: i32_at(4),
+        line: c_string(&buf[8..40]),
+        user: c_string(&buf[44..76]),
+        host: c_string(&buf[76..332]),
+        time: i32_at(340).max(0) as u64,
+    }
+}
+
+/// Campo de tamanho fixo terminado em NUL
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn parse_entries(data: &[u8]) -> impl Iterator<Item = UtmpEntry> + '_ {
+    data.chunks_exact(UTMP_SIZE).map(parse_entry)
+}
+
+/// Sessões ativas do snapshot como um coletor do agendador ("sessions")
+pub struct SessionsCollector {
+    utmp: PathBuf,
+}
+
+impl SessionsCollector {
+    pub fn new(utmp: PathBuf) -> Self {
+        Self { utmp }
+    }
+}
+
+impl Collector for SessionsCollector {
+    type Output = Vec<LoginSession>;
+
+    fn name(&self) -> &'static str {
+        "sessions"
+    }
+
+    fn collect(&mut self) -> Result<Vec<LoginSession>, String> {
+        read_sessions(&self.utmp)
+    }
+}
+
+/// Sessões ativas registradas no utmp (nenhuma se o arquivo não existe,
+/// como em containers)
+pub fn read_sessions(utmp: &Path) -> Result<Vec<LoginSession>, String> {
+    let data = match fs::read(utmp) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Erro ao ler {:?}: {}", utmp, e)),
+    };
+    Ok(parse_entries(&data)
+        .filter(|entry| entry.kind == USER_PROCESS && !entry.user.is_empty())
+        .map(|entry| entry.to_session())
+        .collect())
+}
+
+/// Acompanha o wtmp entre intervalos
+pub struct SessionTracker {
+    wtmp: PathBuf,
+    offset: u64,                         // Bytes do wtmp já processados
+    open: HashMap<String, LoginSession>, // Sessões abertas por terminal
+    last_error: Option<String>,
+}
+
+impl SessionTracker {
+    /// Começa no fim do wtmp: o histórico anterior ao agente não é regravado
+    pub fn new(utmp: &Path, wtmp: &Path) -> Self {
+        let open = read_sessions(utmp)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|session| (session.tty.clone(), session))
+            .collect();
+        let offset = fs::metadata(wtmp).map(|m| m.len()).unwrap_or(0);
+        Self {
+            wtmp: wtmp.to_path_buf(),
+            offset: offset - offset % UTMP_SIZE as u64,
+            open,
+            last_error: None,
+        }
+    }
+
+    /// Arquivo wtmp acompanhado
+    pub fn wtmp(&self) -> &Path {
+        &self.wtmp
+    }
+
+    /// Lê as entradas novas do wtmp e retorna os eventos e erros
+    pub fn poll(&mut self, agent_id: &str, hostname: &str) -> (Vec<Record>, Vec<String>) {
+        let mut errors = Vec::new();
+        let data = match self.read_new() {
+            Ok(data) => {
+                self.last_error = None;
+                data
+            }
+            Err(e) => {
+                // O mesmo erro não é repetido a cada intervalo
+                if self.last_error.as_ref() != Some(&e) {
+                    errors.push(e.clone());
+                    self.last_error = Some(e);
+                }
+                return (Vec::new(), errors);
+            }
+        };
+
+        let datetime = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let timestamp = Utc::now().timestamp().max(0) as u64;
+        let event = |session: LoginSession, logout_time| SessionEvent {
+            schema_version: SCHEMA_VERSION,
+            agent_id: agent_id.to_string(),
+            hostname: hostname.to_string(),
+            datetime: datetime.clone(),
+            timestamp,
+            session,
+            logout_time,
+        };
+
+        let mut records = Vec::new();
+        for entry in parse_entries(&data) {
+            match entry.kind {
+                USER_PROCESS if !entry.user.is_empty() => {
+                    let session = entry.to_session();
+                    self.open.insert(session.tty.clone(), session.clone());
+                    records.push(Record::SessionOpened(event(session, None)));
+                }
+                DEAD_PROCESS if !entry.line.is_empty() => {
+                    // O logout no wtmp não traz usuário nem host: vêm da abertura
+                    let session = self.open.remove(&entry.line).unwrap_or(LoginSession {
+                        user: String::new(),
+                        tty: entry.line.clone(),
+                        host: String::new(),
+                        login_time: 0,
+                        pid: entry.pid,
+                    });
+                    records.push(Record::SessionClosed(event(session, Some(entry.time))));
+                }
+                _ => {}
+            }
+        }
+        (records, errors)
+    }
+
+    /// Entradas completas gravadas desde a última leitura
+    fn read_new(&mut self) -> Result<Vec<u8>, String> {
+        let len = match fs::metadata(&self.wtmp) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Erro ao ler {:?}: {}", self.wtmp, e)),
+        };
+        // Arquivo menor que o já lido: foi rotacionado
+        if len < self.offset {
+            self.offset = 0;
+        }
+        let complete = (len - self.offset) / UTMP_SIZE as u64 * UTMP_SIZE as u64;
+        if complete == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut data = vec![0u8; complete as usize];
+        let mut file =
+            File::open(&self.wtmp).map_err(|e| format!("Erro ao abrir {:?}: {}", self.wtmp, e))?;
+        file.seek(SeekFrom::Start(self.offset))
+            .and_then(|_| file.read_exact(&mut data))
+            .map_err(|e| format!("Erro ao ler {:?}: {}", self.wtmp, e))?;
+        self.offset += complete;
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collectors::fixture_root;
+
+    fn fixture_utmp() -> PathBuf {
+        fixture_root().join("var/run/utmp")
+    }
+
+    #[test]
+    fn lists_only_user_processes() {
+        let sessions = SessionsCollector::new(fixture_utmp()).collect().unwrap();
+        // A entrada de boot e a sessão encerrada (DEAD_PROCESS) ficam de fora
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(
+            sessions[0],
+            LoginSession {
+                user: "alice".to_string(),
+                tty: "pts/0".to_string(),
+                host: "10.0.0.5".to_string(),
+                login_time: 1700000100,
+                pid: 4242,
+            }
+        );
+        assert_eq!(
+            (sessions[1].user.as_str(), sessions[1].tty.as_str()),
+            ("root", "tty1")
+        );
+    }
+
+    #[test]
+    fn missing_utmp_means_no_sessions() {
+        let mut collector = SessionsCollector::new(fixture_utmp().with_file_name("nao-existe"));
+        assert!(collector.collect().unwrap().is_empty());
+    }
+
+    /// Entrada `struct utmp` com os campos lidos por `parse_entry`
+    fn utmp_entry(kind: i16, pid: i32, line: &str, user: &str, host: &str, time: i32) -> Vec<u8> {
+        let mut buf = vec![0u8; UTMP_SIZE];
+        buf[0..2].copy_from_slice(&kind.to_le_bytes());
+        buf[4..8].copy_from_slice(&pid.to_le_bytes());
+        buf[8..8 + line.len()].copy_from_slice(line.as_bytes());
+        buf[44..44 + user.len()].copy_from_slice(user.as_bytes());
+        buf[76..76 + host.len()].copy_from_slice(host.as_bytes());
+        buf[340..344].copy_from_slice(&time.to_le_bytes());
+        buf
+    }
+
+    fn append(path: &Path, data: &[u8]) {
+        use std::io::Write;
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(data)
+            .unwrap();
+    }
+
+    #[test]
+    fn wtmp_tail_reports_logins_and_logouts() {
+        let dir = std::env::temp_dir().join(format!("wtmp-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let wtmp = dir.join("wtmp");
+        // Histórico anterior ao agente não gera eventos
+        fs::write(&wtmp, utmp_entry(USER_PROCESS, 1, "pts/9", "antigo", "", 1)).unwrap();
+        let mut tracker = SessionTracker::new(&fixture_utmp(), &wtmp);
+        assert!(tracker.poll("agente", "vm1").0.is_empty());
+
+        append(
+            &wtmp,
+            &utmp_entry(USER_PROCESS, 77, "pts/3", "bob", "10.0.0.9", 1700000500),
+        );
+        append(
+            &wtmp,
+            &utmp_entry(DEAD_PROCESS, 77, "pts/3", "", "", 1700000900),
+        );
+        // Logout de uma sessão aberta antes do agente (vem do utmp)
+        append(
+            &wtmp,
+            &utmp_entry(DEAD_PROCESS, 4242, "pts/0", "", "", 1700001000),
+        );
+        // Entrada incompleta fica para a próxima leitura
+        append(&wtmp, &[0u8; 100]);
+
+        let (records, errors) = tracker.poll("agente", "vm1");
+        assert!(errors.is_empty());
+        let events: Vec<(&str, &str, &str, Option<u64>)> = records
+            .iter()
+            .map(|record| match record {
+                Record::SessionOpened(e) | Record::SessionClosed(e) => (
+                    record.record_type(),
+                    e.session.user.as_str(),
+                    e.session.host.as_str(),
+                    e.logout_time,
+                ),
+                _ => panic!("registro inesperado"),
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                ("SESSION_OPENED", "bob", "10.0.0.9", None),
+                ("SESSION_CLOSED", "bob", "10.0.0.9", Some(1700000900)),
+                ("SESSION_CLOSED", "alice", "10.0.0.5", Some(1700001000)),
+            ]
+        );
+        assert!(tracker.poll("agente", "vm1").0.is_empty());
+
+        // wtmp rotacionado: recomeça do início do arquivo novo
+        fs::write(
+            &wtmp,
+            utmp_entry(USER_PROCESS, 80, "tty2", "carol", "", 1700002000),
+        )
+        .unwrap();
+        let (records, _) = tracker.poll("agente", "vm1");
+        assert_eq!(records.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
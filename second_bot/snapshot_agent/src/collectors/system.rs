@@ -0,0 +1,271 @@
+use crate::collector::Collector;
+use std::thread::sleep;
+use std::time::Duration;
+use sysinfo::{CpuExt, DiskExt, System, SystemExt};
+
+/// =========================================================================
+/// MEMÓRIA, CPU E DISCO (SYSINFO)
+/// =========================================================================
+/// Métricas básicas do snapshot, iguais em todas as plataformas. Cada
+/// coletor mantém o próprio `System`, refrescando só o que precisa.
+
+/// Espera entre as duas leituras da primeira medição de CPU
+const CPU_WARMUP: Duration = Duration::from_millis(200);
+
+/// Memória total e em uso (bytes)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemoryUsage {
+    pub total: u64,
+    pub used: u64,
+}
+
+/// Espaço total e usado somando todos os discos (bytes)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DiskUsage {
+    pub total: u64,
+    pub used: u64,
+}
+
+/// Leituras do sysinfo usadas pelos coletores
+/// Os testes usam valores fixos no lugar dos do host
+pub trait SystemSource: Send {
+    /// Memória total e em uso
+    fn memory(&mut self) -> MemoryUsage;
+
+    /// Uso global de CPU desde a leitura anterior (%)
+    fn cpu_usage(&mut self) -> f32;
+
+    /// Espaço total e disponível de cada volume montado (bytes)
+    fn disks(&mut self) -> Vec<(u64, u64)>;
+}
+
+impl SystemSource for System {
+    fn memory(&mut self) -> MemoryUsage {
+        self.refresh_memory();
+        MemoryUsage {
+            total: self.total_memory(),
+            used: self.used_memory(),
+        }
+    }
+
+    fn cpu_usage(&mut self) -> f32 {
+        self.refresh_cpu();
+        self.global_cpu_info().cpu_usage()
+    }
+
+    /// A lista é relida a cada chamada (volumes montados e desmontados)
+    fn disks(&mut self) -> Vec<(u64, u64)> {
+        self.refresh_disks_list();
+        SystemExt::disks(self)
+            .iter()
+            .map(|disk| (disk.total_space(), disk.available_space()))
+            .collect()
+    }
+}
+
+/// Memória do sistema
+pub struct MemoryCollector<S = System> {
+    system: S,
+}
+
+impl MemoryCollector {
+    pub fn new() -> Self {
+        Self::with_source(System::new())
+    }
+}
+
+impl<S: SystemSource> MemoryCollector<S> {
+    /// Lê de outra fonte (testes)
+    pub fn with_source(system: S) -> Self {
+        Self { system }
+    }
+}
+
+impl Default for MemoryCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: SystemSource> Collector for MemoryCollector<S> {
+    type Output = MemoryUsage;
+
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn collect(&mut self) -> Result<MemoryUsage, String> {
+        Ok(self.system.memory())
+    }
+}
+
+/// Uso global de CPU (%)
+/// O uso é a média desde a coleta anterior; a primeira faz duas leituras
+/// separadas por CPU_WARMUP, já que uma leitura isolada não tem base
+pub struct CpuCollector<S = System> {
+    system: S,
+    primed: bool,
+}
+
+impl CpuCollector {
+    pub fn new() -> Self {
+        Self::with_source(System::new())
+    }
+}
+
+impl<S: SystemSource> CpuCollector<S> {
+    /// Lê de outra fonte (testes)
+    pub fn with_source(system: S) -> Self {
+        Self {
+            system,
+            primed: false,
+        }
+    }
+}
+
+impl Default for CpuCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: SystemSource> Collector for CpuCollector<S> {
+    type Output = f32;
+
+    fn name(&self) -> &'static str {
+        "cpu"
+    }
+
+    fn collect(&mut self) -> Result<f32, String> {
+        if !self.primed {
+            self.system.cpu_usage();
+            sleep(CPU_WARMUP);
+            self.primed = true;
+        }
+        Ok(self.system.cpu_usage())
+    }
+}
+
+/// Espaço em disco de todos os volumes montados
+pub struct DiskCollector<S = System> {
+    system: S,
+}
+
+impl DiskCollector {
+    pub fn new() -> Self {
+        Self::with_source(System::new())
+    }
+}
+
+impl<S: SystemSource> DiskCollector<S> {
+    /// Lê de outra fonte (testes)
+    pub fn with_source(system: S) -> Self {
+        Self { system }
+    }
+}
+
+impl Default for DiskCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: SystemSource> Collector for DiskCollector<S> {
+    type Output = DiskUsage;
+
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn collect(&mut self) -> Result<DiskUsage, String> {
+        let disks = self.system.disks();
+        Ok(DiskUsage {
+            total: disks.iter().map(|(total, _)| total).sum(),
+            used: disks
+                .iter()
+                .map(|(total, available)| total.saturating_sub(*available))
+                .sum(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fonte com valores fixos; as leituras de CPU seguem `cpu` em ordem
+    struct Fixture {
+        memory: MemoryUsage,
+        cpu: Vec<f32>,
+        cpu_reads: usize,
+        disks: Vec<(u64, u64)>,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                memory: MemoryUsage {
+                    total: 8 << 30,
+                    used: 3 << 30,
+                },
+                cpu: vec![0.0, 12.5, 40.0],
+                cpu_reads: 0,
+                disks: vec![(100 << 30, 40 << 30), (20 << 30, 20 << 30)],
+            }
+        }
+    }
+
+    impl SystemSource for Fixture {
+        fn memory(&mut self) -> MemoryUsage {
+            self.memory
+        }
+
+        fn cpu_usage(&mut self) -> f32 {
+            self.cpu_reads += 1;
+            self.cpu[self.cpu_reads - 1]
+        }
+
+        fn disks(&mut self) -> Vec<(u64, u64)> {
+            self.disks.clone()
+        }
+    }
+
+    #[test]
+    fn memory_comes_from_the_source() {
+        let usage = MemoryCollector::with_source(Fixture::new())
+            .collect()
+            .unwrap();
+        assert_eq!(
+            usage,
+            MemoryUsage {
+                total: 8 << 30,
+                used: 3 << 30,
+            }
+        );
+    }
+
+    #[test]
+    fn first_cpu_reading_is_primed() {
+        let mut collector = CpuCollector::with_source(Fixture::new());
+        // A primeira coleta descarta a leitura sem base
+        assert_eq!(collector.collect().unwrap(), 12.5);
+        assert_eq!(collector.system.cpu_reads, 2);
+        assert_eq!(collector.collect().unwrap(), 40.0);
+        assert_eq!(collector.system.cpu_reads, 3);
+    }
+
+    #[test]
+    fn disk_usage_sums_all_volumes() {
+        let mut fixture = Fixture::new();
+        // Volume com mais espaço livre que o total (sistemas de arquivos de rede)
+        fixture.disks.push((1 << 30, 2 << 30));
+        let usage = DiskCollector::with_source(fixture).collect().unwrap();
+        assert_eq!(
+            usage,
+            DiskUsage {
+                total: 121 << 30,
+                used: 60 << 30,
+            }
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// =========================================================================
+/// EXECUÇÃO DE COMANDOS EXTERNOS
+/// =========================================================================
+/// Comandos configurados pelo usuário (coletores personalizados, reinício de
+/// processos) rodam via /bin/sh -c, com timeout, em seu próprio grupo de
+/// processos para que o timeout encerre também os processos filhos.
+
+/// Bytes de stdout/stderr guardados por comando (o restante é descartado)
+const MAX_OUTPUT: u64 = 64 * 1024;
+
+/// Intervalo de verificação do término do comando
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Resultado de um comando executado até o fim ou até o timeout
+pub struct CommandOutput {
+    pub exit_code: Option<i32>, // Ausente se morto por sinal ou timeout
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    /// Descrição da falha (timeout, código diferente de zero ou sinal)
+    pub fn failure(&self) -> Option<String> {
+        if self.timed_out {
+            return Some(format!(
+                "tempo limite excedido após {} ms",
+                self.duration_ms
+            ));
+        }
+        match self.exit_code {
+            Some(0) => None,
+            Some(code) => Some(format!(
+                "comando terminou com código {}{}",
+                code,
+                stderr_suffix(&self.stderr)
+            )),
+            None => Some(format!(
+                "comando encerrado por sinal{}",
+                stderr_suffix(&self.stderr)
+            )),
+        }
+    }
+}
+
+/// Executa `command` com /bin/sh -c, encerrando-o após `timeout`
+/// Com `capture_output`, processos deixados em segundo plano são encerrados
+/// quando o comando termina (manteriam os pipes abertos); sem captura eles
+/// continuam rodando, como esperado de um comando de reinício
+/// Retorna erro apenas se o comando não pôde ser iniciado ou aguardado
+pub fn run_shell(
+    command: &str,
+    timeout: Duration,
+    capture_output: bool,
+) -> Result<CommandOutput, String> {
+    let output = || {
+        if capture_output {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        }
+    };
+    let started = Instant::now();
+    let mut child = Command::new("/bin/sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(output())
+        .stderr(output())
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("erro ao executar comando: {}", e))?;
+
+    // Leitura das saídas em threads próprias para não bloquear o pipe
+    let stdout = read_output(child.stdout.take());
+    let stderr = read_output(child.stderr.take());
+
+    let deadline = started + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if Instant::now() >= deadline => break None,
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                kill_group(&mut child);
+                return Err(format!("erro ao aguardar comando: {}", e));
+            }
+        }
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let Some(status) = status else {
+        kill_group(&mut child);
+        return Ok(CommandOutput {
+            exit_code: None,
+            duration_ms,
+            timed_out: true,
+            stdout: String::new(),
+            stderr: String::new(),
+        });
+    };
+    if capture_output {
+        kill_group(&mut child);
+    }
+    Ok(CommandOutput {
+        exit_code: status.code(),
+        duration_ms,
+        timed_out: false,
+        stdout: join_output(stdout),
+        stderr: join_output(stderr),
+    })
+}
+
+/// Lê um pipe até o fim, guardando no máximo MAX_OUTPUT bytes
+fn read_output<R: Read + Send + 'static>(pipe: Option<R>) -> Option<JoinHandle<String>> {
+    let mut pipe = pipe?;
+    Some(thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = (&mut pipe).take(MAX_OUTPUT).read_to_end(&mut buffer);
+        let _ = io::copy(&mut pipe, &mut io::sink());
+        String::from_utf8_lossy(&buffer).into_owned()
+    }))
+}
+
+fn join_output(handle: Option<JoinHandle<String>>) -> String {
+    handle.and_then(|h| h.join().ok()).unwrap_or_default()
+}
+
+/// Primeira linha do stderr, anexada às mensagens de erro
+fn stderr_suffix(stderr: &str) -> String {
+    match stderr.lines().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) => format!(": {}", line),
+        None => String::new(),
+    }
+}
+
+/// Encerra o comando e todos os processos do seu grupo
+fn kill_group(child: &mut Child) {
+    // SAFETY: kill(2) com o pgid do filho criado com process_group(0)
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.wait();
+}
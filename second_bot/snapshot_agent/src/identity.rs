@@ -0,0 +1,154 @@
+use dirs_next::home_dir;
+use rand::RngCore;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// =========================================================================
+/// IDENTIDADE PERSISTENTE DO AGENTE
+/// =========================================================================
+/// O agent_id é gerado uma única vez e salvo em ~/.snapshot_agent/agent_id,
+/// permitindo correlacionar o histórico do agente entre reinícios. Usado
+/// pelos binários Linux e Windows.
+///
+/// O sal dos nomes de arquivos em hash (`names: "hash"` das pastas) é um
+/// segredo aleatório do agente em ~/.snapshot_agent/name_salt, legível apenas
+/// pelo dono. Diferente do agent_id, que aparece em todo registro, ele nunca
+/// é gravado no log nem enviado, então os hashes não podem ser comparados com
+/// uma lista de nomes comuns.
+
+/// Tamanho do sal, em bytes aleatórios
+const SALT_BYTES: usize = 32;
+
+/// Retorna o caminho do arquivo que guarda o identificador do agente
+/// Localizado em ~/.snapshot_agent/agent_id
+pub fn get_agent_id_path() -> Result<PathBuf, String> {
+    let mut path = home_dir().ok_or("Não foi possível obter a home do usuário")?;
+    path.push(".snapshot_agent");
+    path.push("agent_id");
+    Ok(path)
+}
+
+/// Lê o agent_id persistido ou gera e grava um novo
+/// Um arquivo ausente ou com conteúdo inválido resulta em um novo UUID; falhar
+/// ao gravá-lo é um erro, pois o identificador mudaria a cada reinício
+pub fn load_or_create_agent_id() -> Result<String, String> {
+    load_or_create_agent_id_at(&get_agent_id_path()?)
+}
+
+fn load_or_create_agent_id_at(path: &Path) -> Result<String, String> {
+    if let Ok(content) = fs::read_to_string(path) {
+        if let Ok(id) = Uuid::parse_str(content.trim()) {
+            return Ok(id.to_string());
+        }
+    }
+
+    let agent_id = Uuid::new_v4().to_string();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Erro ao criar diretório do agente {:?}: {}", dir, e))?;
+    }
+    fs::write(path, format!("{}\n", agent_id))
+        .map_err(|e| format!("Erro ao gravar agent_id em {:?}: {}", path, e))?;
+    Ok(agent_id)
+}
+
+/// Retorna o caminho do arquivo que guarda o sal dos nomes em hash
+/// Localizado em ~/.snapshot_agent/name_salt
+pub fn get_name_salt_path() -> Result<PathBuf, String> {
+    Ok(get_agent_id_path()?.with_file_name("name_salt"))
+}
+
+/// Lê o sal persistido ou gera e grava um novo com permissão 0600
+/// Um arquivo vazio ou com conteúdo inválido resulta em um novo sal
+pub fn load_or_create_name_salt() -> Result<String, String> {
+    load_or_create_name_salt_at(&get_name_salt_path()?)
+}
+
+/// Sal aleatório que não é persistido, para quando o arquivo não pode ser
+/// gravado: os nomes continuam protegidos, mas mudam a cada reinício
+pub fn ephemeral_name_salt() -> String {
+    let mut salt = [0u8; SALT_BYTES];
+    rand::thread_rng().fill_bytes(&mut salt);
+    hex::encode(salt)
+}
+
+fn load_or_create_name_salt_at(path: &Path) -> Result<String, String> {
+    if let Ok(content) = fs::read_to_string(path) {
+        let salt = content.trim();
+        if salt.len() == SALT_BYTES * 2 && hex::decode(salt).is_ok() {
+            return Ok(salt.to_string());
+        }
+    }
+
+    let salt = ephemeral_name_salt();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Erro ao criar diretório do agente {:?}: {}", dir, e))?;
+    }
+    // Um arquivo inválido é substituído; o novo já nasce com 0600
+    let _ = fs::remove_file(path);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", salt))
+        .map_err(|e| format!("Erro ao gravar o sal dos nomes em {:?}: {}", path, e))?;
+    Ok(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_id_is_created_once_and_invalid_ids_are_replaced() {
+        let dir = std::env::temp_dir().join(format!("identity-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("state").join("agent_id");
+
+        let created = load_or_create_agent_id_at(&path).unwrap();
+        assert!(Uuid::parse_str(&created).is_ok());
+        assert_eq!(load_or_create_agent_id_at(&path).unwrap(), created);
+
+        fs::write(&path, "não é um uuid\n").unwrap();
+        let replaced = load_or_create_agent_id_at(&path).unwrap();
+        assert_ne!(replaced, created);
+        assert_eq!(fs::read_to_string(&path).unwrap().trim(), replaced);
+
+        // Um diretório no lugar do arquivo impede a gravação
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        assert!(load_or_create_agent_id_at(&path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn name_salt_is_secret_and_stable() {
+        let dir = std::env::temp_dir().join(format!("salt-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("name_salt");
+
+        let salt = load_or_create_name_salt_at(&path).unwrap();
+        assert_eq!(salt.len(), SALT_BYTES * 2);
+        assert_eq!(load_or_create_name_salt_at(&path).unwrap(), salt);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::write(&path, "curto\n").unwrap();
+        let replaced = load_or_create_name_salt_at(&path).unwrap();
+        assert_ne!(replaced, salt);
+        assert_eq!(fs::read_to_string(&path).unwrap().trim(), replaced);
+        assert_ne!(ephemeral_name_salt(), ephemeral_name_salt());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,22 @@
+#![allow(clippy::empty_line_after_doc_comments)]
+
+/// =========================================================================
+/// BIBLIOTECA DO SNAPSHOT AGENT
+/// =========================================================================
+/// Coletores reutilizáveis e o agendador que os executa. Os binários
+/// (`snapshot_agent_linux`, `snapshot_agent_windows`) registram os coletores
+/// da plataforma no `Scheduler` e montam o `Snapshot` com os últimos valores.
+///
+///   collector  - trait `Collector`, `Scheduler` e `Handle`
+///   collectors - memória, CPU, disco e pasta (todas as plataformas);
+///                saúde do kernel, processos, portas, cgroup e sessões
+///                (Linux); comandos do usuário (Unix)
+///   command    - execução de comandos externos com timeout (Unix)
+///   identity   - agent_id persistente, compartilhado pelos binários
+pub mod collector;
+pub mod collectors;
+#[cfg(unix)]
+pub mod command;
+pub mod identity;
+
+pub use collector::{Collector, CollectorError, Handle, Scheduler};
@@ -0,0 +1,205 @@
+// ------------------------------------------------------------------------------
+// SNAPSHOT SCHEMA
+// ------------------------------------------------------------------------------
+// Tipos compartilhados entre o snapshot_agent (que grava o log) e o
+// central_monitor (que o lê):
+// - Estruturas de cada tipo de registro, versionadas por `schema_version`
+// - Enum `Record` com a tag `record_type` usada em cada linha do log
+// - Leitura tolerante a versões antigas (sem tag) e mais novas (campos extras)
+// - Geração do JSON Schema dos registros
+// ------------------------------------------------------------------------------
+
+mod records;
+
+pub use records::*;
+
+use schemars::schema::RootSchema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Qualquer registro do log, identificado pelo campo `record_type`
+// Registros são lidos e gravados um por vez; o Snapshot maior que os
+// eventos não justifica um Box em todos os matches
+#[allow(clippy::large_enum_variant)]
+#[derive(Serialize, JsonSchema, Clone, Debug, PartialEq)]
+#[serde(tag = "record_type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Record {
+    Snapshot(Snapshot),
+    ServiceStatus(ServiceStatus),
+    Error(LogError),
+    Inventory(Inventory),
+    ProcessDown(ProcessEvent),
+    ProcessUp(ProcessEvent),
+    SessionOpened(SessionEvent),
+    SessionClosed(SessionEvent),
+    AuthFailures(AuthFailures),
+    FileChanged(FileChange),
+    PortOpened(PortEvent),
+    PortClosed(PortEvent),
+    PackageInventory(PackageInventory),
+    PackageChanged(PackageChange),
+    UnitChanged(UnitChange),
+}
+
+/// Erros ao interpretar uma linha do log
+#[derive(Debug)]
+pub enum SchemaError {
+    /// A linha não é JSON válido ou não corresponde ao tipo indicado
+    Json(serde_json::Error),
+    /// `record_type` desconhecido (provavelmente de uma versão mais nova)
+    UnknownRecordType(String),
+    /// Registro sem `record_type` que não corresponde a nenhum tipo v1
+    UnrecognizedLegacy,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Json(e) => write!(f, "registro inválido: {}", e),
+            SchemaError::UnknownRecordType(kind) => {
+                write!(f, "tipo de registro desconhecido: {}", kind)
+            }
+            SchemaError::UnrecognizedLegacy => write!(f, "registro legado não reconhecido"),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<serde_json::Error> for SchemaError {
+    fn from(e: serde_json::Error) -> Self {
+        SchemaError::Json(e)
+    }
+}
+
+impl Record {
+    /// Interpreta uma linha JSON do log
+    pub fn from_json(line: &str) -> Result<Record, SchemaError> {
+        Record::from_value(serde_json::from_str(line)?)
+    }
+
+    /// Interpreta um registro já decodificado como `serde_json::Value`
+    ///
+    /// Registros v1 (sem `record_type`) têm o tipo inferido pelos campos
+    /// presentes; campos desconhecidos são ignorados, de modo que registros
+    /// de versões mais novas continuam legíveis.
+    pub fn from_value(value: Value) -> Result<Record, SchemaError> {
+        let kind = match value.get("record_type") {
+            Some(Value::String(kind)) => kind.clone(),
+            Some(other) => return Err(SchemaError::UnknownRecordType(other.to_string())),
+            None => infer_legacy_type(&value)?.to_string(),
+        };
+
+        let record = match kind.as_str() {
+            "SNAPSHOT" => Record::Snapshot(serde_json::from_value(value)?),
+            "SERVICE_STATUS" => Record::ServiceStatus(serde_json::from_value(value)?),
+            "ERROR" => Record::Error(serde_json::from_value(value)?),
+            "INVENTORY" => Record::Inventory(serde_json::from_value(value)?),
+            "PROCESS_DOWN" => Record::ProcessDown(serde_json::from_value(value)?),
+            "PROCESS_UP" => Record::ProcessUp(serde_json::from_value(value)?),
+            "SESSION_OPENED" => Record::SessionOpened(serde_json::from_value(value)?),
+            "SESSION_CLOSED" => Record::SessionClosed(serde_json::from_value(value)?),
+            "AUTH_FAILURES" => Record::AuthFailures(serde_json::from_value(value)?),
+            "FILE_CHANGED" => Record::FileChanged(serde_json::from_value(value)?),
+            "PORT_OPENED" => Record::PortOpened(serde_json::from_value(value)?),
+            "PORT_CLOSED" => Record::PortClosed(serde_json::from_value(value)?),
+            "PACKAGE_INVENTORY" => Record::PackageInventory(serde_json::from_value(value)?),
+            "PACKAGE_CHANGED" => Record::PackageChanged(serde_json::from_value(value)?),
+            "UNIT_CHANGED" => Record::UnitChanged(serde_json::from_value(value)?),
+            _ => return Err(SchemaError::UnknownRecordType(kind)),
+        };
+        Ok(record)
+    }
+
+    /// Valor do campo `record_type` deste registro
+    pub fn record_type(&self) -> &'static str {
+        match self {
+            Record::Snapshot(_) => "SNAPSHOT",
+            Record::ServiceStatus(_) => "SERVICE_STATUS",
+            Record::Error(_) => "ERROR",
+            Record::Inventory(_) => "INVENTORY",
+            Record::ProcessDown(_) => "PROCESS_DOWN",
+            Record::ProcessUp(_) => "PROCESS_UP",
+            Record::SessionOpened(_) => "SESSION_OPENED",
+            Record::SessionClosed(_) => "SESSION_CLOSED",
+            Record::AuthFailures(_) => "AUTH_FAILURES",
+            Record::FileChanged(_) => "FILE_CHANGED",
+            Record::PortOpened(_) => "PORT_OPENED",
+            Record::PortClosed(_) => "PORT_CLOSED",
+            Record::PackageInventory(_) => "PACKAGE_INVENTORY",
+            Record::PackageChanged(_) => "PACKAGE_CHANGED",
+            Record::UnitChanged(_) => "UNIT_CHANGED",
+        }
+    }
+
+    /// Timestamp UNIX (segundos) do registro
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            Record::Snapshot(r) => r.timestamp,
+            Record::ServiceStatus(r) => r.timestamp,
+            Record::Error(r) => r.timestamp,
+            Record::Inventory(r) => r.timestamp,
+            Record::ProcessDown(r) | Record::ProcessUp(r) => r.timestamp,
+            Record::SessionOpened(r) | Record::SessionClosed(r) => r.timestamp,
+            Record::AuthFailures(r) => r.timestamp,
+            Record::FileChanged(r) => r.timestamp,
+            Record::PortOpened(r) | Record::PortClosed(r) => r.timestamp,
+            Record::PackageInventory(r) => r.timestamp,
+            Record::PackageChanged(r) => r.timestamp,
+            Record::UnitChanged(r) => r.timestamp,
+        }
+    }
+
+    /// Identificador do agente que gravou o registro
+    pub fn agent_id(&self) -> &str {
+        match self {
+            Record::Snapshot(r) => &r.agent_id,
+            Record::ServiceStatus(r) => &r.agent_id,
+            Record::Error(r) => &r.agent_id,
+            Record::Inventory(r) => &r.agent_id,
+            Record::ProcessDown(r) | Record::ProcessUp(r) => &r.agent_id,
+            Record::SessionOpened(r) | Record::SessionClosed(r) => &r.agent_id,
+            Record::AuthFailures(r) => &r.agent_id,
+            Record::FileChanged(r) => &r.agent_id,
+            Record::PortOpened(r) | Record::PortClosed(r) => &r.agent_id,
+            Record::PackageInventory(r) => &r.agent_id,
+            Record::PackageChanged(r) => &r.agent_id,
+            Record::UnitChanged(r) => &r.agent_id,
+        }
+    }
+
+    /// Serializa o registro como uma linha JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Erro ao serializar registro")
+    }
+}
+
+/// Infere o tipo de um registro v1 a partir dos campos presentes
+fn infer_legacy_type(value: &Value) -> Result<&'static str, SchemaError> {
+    let has = |key: &str| value.get(key).is_some();
+    if has("status") {
+        Ok("SERVICE_STATUS")
+    } else if has("error") {
+        Ok("ERROR")
+    } else if has("inventory") {
+        Ok("INVENTORY")
+    } else if has("total_memory") {
+        Ok("SNAPSHOT")
+    } else {
+        Err(SchemaError::UnrecognizedLegacy)
+    }
+}
+
+impl<'de> Deserialize<'de> for Record {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        Record::from_value(value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// JSON Schema de uma linha do log (qualquer `Record`)
+pub fn json_schema() -> RootSchema {
+    schemars::schema_for!(Record)
+}
@@ -0,0 +1,10 @@
+// Imprime o JSON Schema dos registros do snapshot.log
+// Uso: cargo run -p snapshot_schema > snapshot_schema/records.schema.json
+
+fn main() {
+    let schema = snapshot_schema::json_schema();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema).expect("Erro ao serializar schema")
+    );
+}
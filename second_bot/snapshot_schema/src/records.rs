@@ -0,0 +1,623 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// ------------------------------------------------------------------------------
+// TIPOS DE REGISTRO DO snapshot.log
+// ------------------------------------------------------------------------------
+// Cada linha do log é um destes registros, identificado pelo campo
+// `record_type` e versionado por `schema_version`. Registros gravados antes
+// do versionamento não têm nenhum dos dois campos e são tratados como v1.
+// ------------------------------------------------------------------------------
+
+/// Versão atual do schema gravada em todos os registros
+///
+/// Histórico:
+/// * 1 - registros sem `record_type`/`schema_version` (formato original)
+/// * 2 - `record_type` + `schema_version`; Windows passa a gravar `agent_id`/`hostname`
+/// * 3 - `Snapshot.custom` com os resultados dos coletores personalizados
+/// * 4 - registros `PROCESS_DOWN`/`PROCESS_UP` da lista de processos monitorados
+/// * 5 - `Snapshot.sessions`, registros `SESSION_OPENED`/`SESSION_CLOSED` e `AUTH_FAILURES`
+/// * 6 - registros `FILE_CHANGED` do monitoramento de integridade de arquivos
+/// * 7 - `Snapshot.cgroup` com os limites e o uso do cgroup do agente
+/// * 8 - `Snapshot.health` com swap, PSI, descritores, processos e I/O por disco
+/// * 9 - `Snapshot.overhead` com o consumo do próprio agente e coletas reduzidas
+/// * 10 - `Snapshot.listening` e registros `PORT_OPENED`/`PORT_CLOSED`
+/// * 11 - registros `PACKAGE_INVENTORY`, `PACKAGE_CHANGED` e `UNIT_CHANGED`
+/// * 12 - filtros de privacidade nas pastas: `Snapshot.folder_total` e `Snapshot.folders`
+pub const SCHEMA_VERSION: u32 = 12;
+
+/// Versão assumida quando o registro não informa `schema_version`
+fn legacy_version() -> u32 {
+    1
+}
+
+/// Snapshot periódico dos recursos do sistema
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    /// Identificador persistente do agente (vazio em snapshots Windows v1)
+    #[serde(default)]
+    pub agent_id: String,
+    /// Host onde o agente está rodando (vazio em snapshots Windows v1)
+    #[serde(default)]
+    pub hostname: String,
+    /// Timestamp UNIX em segundos
+    pub timestamp: u64,
+    /// Data/hora ISO8601 (UTC)
+    pub datetime: String,
+    /// Memória total (bytes)
+    pub total_memory: u64,
+    /// Memória em uso (bytes)
+    pub used_memory: u64,
+    /// Uso de CPU (0-100%)
+    pub cpu_usage_percent: f32,
+    /// Espaço total em disco (bytes)
+    pub total_disk: u64,
+    /// Espaço usado em disco (bytes)
+    pub used_disk: u64,
+    /// Arquivos da pasta monitorada (filtrados, com os nomes em hash ou
+    /// ocultos conforme a configuração a partir da v12)
+    #[serde(default)]
+    pub folder_files: Vec<String>,
+    /// Entradas da pasta monitorada que passaram nos filtros; maior que
+    /// `folder_files.len()` quando a lista foi truncada (v12)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder_total: Option<u64>,
+    /// Demais pastas monitoradas, cada uma com os próprios filtros (v12)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folders: Vec<FolderListing>,
+    /// Resultados dos coletores personalizados, por nome (v3)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, CustomMetric>,
+    /// Sessões de login ativas (utmp) (v5)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<LoginSession>,
+    /// Limites e uso do cgroup do agente (container ou slice do systemd) (v7)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<CgroupStats>,
+    /// Indicadores de saúde do kernel: swap, pressão, descritores, processos
+    /// e I/O por disco (v8)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub health: Option<SystemHealth>,
+    /// Consumo do próprio agente e coletores omitidos pelo orçamento (v9)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overhead: Option<AgentOverhead>,
+    /// Sockets TCP/UDP em escuta com o processo dono (v10)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listening: Vec<ListeningSocket>,
+}
+
+impl Snapshot {
+    /// Memória usada e total no escopo que se aplica ao agente: os valores
+    /// do cgroup quando há limite de memória, senão os do host
+    pub fn effective_memory(&self) -> (u64, u64) {
+        match &self.cgroup {
+            Some(CgroupStats {
+                memory_scope: ResourceScope::Cgroup,
+                memory_limit: Some(limit),
+                memory_usage,
+                ..
+            }) => (*memory_usage, *limit),
+            _ => (self.used_memory, self.total_memory),
+        }
+    }
+
+    /// Uso de CPU no escopo que se aplica ao agente (percentual da cota do
+    /// cgroup quando há limite de CPU, senão o do host)
+    pub fn effective_cpu_percent(&self) -> f32 {
+        match &self.cgroup {
+            Some(CgroupStats {
+                cpu_scope: ResourceScope::Cgroup,
+                cpu_usage_percent: Some(usage),
+                ..
+            }) => *usage,
+            _ => self.cpu_usage_percent,
+        }
+    }
+}
+
+/// Entradas de uma pasta monitorada, após os filtros de privacidade
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Default)]
+pub struct FolderListing {
+    /// Pasta lida
+    pub path: String,
+    /// Nomes das entradas (no máximo `max_entries`)
+    pub files: Vec<String>,
+    /// Entradas que passaram nos filtros, antes do limite
+    pub total: u64,
+}
+
+/// Socket em escuta (TCP em LISTEN ou UDP sem conexão)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct ListeningSocket {
+    /// `tcp`, `tcp6`, `udp` ou `udp6`
+    pub protocol: String,
+    /// Endereço local (`0.0.0.0` e `::` escutam em todas as interfaces)
+    pub address: String,
+    pub port: u16,
+    /// UID dono do socket
+    pub uid: u32,
+    /// Processo dono (ausente sem permissão para ler /proc/<pid>/fd)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+}
+
+impl ListeningSocket {
+    /// Identificação do socket nos eventos: protocolo, endereço e porta
+    /// (o processo dono pode mudar sem que o serviço mude)
+    pub fn key(&self) -> String {
+        if self.address.contains(':') {
+            format!("{} [{}]:{}", self.protocol, self.address, self.port)
+        } else {
+            format!("{} {}:{}", self.protocol, self.address, self.port)
+        }
+    }
+}
+
+/// Consumo de recursos do próprio agente
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct AgentOverhead {
+    /// CPU do agente desde o snapshot anterior (% de um núcleo)
+    pub cpu_percent: f32,
+    /// Tempo de CPU do ciclo anterior: coleta, watchlist, segurança e FIM (ms)
+    pub cycle_cpu_ms: u64,
+    /// Memória residente do agente (bytes)
+    pub rss_bytes: u64,
+    /// Orçamento excedido: coletores caros omitidos neste snapshot
+    pub throttled: bool,
+    /// Coletores omitidos ou reduzidos (`custom`, `fim`, `processes`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+/// Indicadores de saúde do kernel coletados de /proc
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct SystemHealth {
+    /// Swap total (bytes)
+    pub swap_total: u64,
+    /// Swap em uso (bytes)
+    pub swap_used: u64,
+    /// Pressure stall information (/proc/pressure); ausente em kernels sem PSI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pressure: Option<Pressure>,
+    /// Descritores de arquivo alocados no sistema (/proc/sys/fs/file-nr)
+    pub open_fds: u64,
+    /// Limite de descritores do sistema (fs.file-max)
+    pub max_fds: u64,
+    /// Processos em execução (zero quando `processes` foi omitido pelo
+    /// orçamento do agente, junto com `threads` e `zombies`)
+    pub processes: u64,
+    /// Threads de todos os processos
+    pub threads: u64,
+    /// Processos zumbis (estado Z)
+    pub zombies: u64,
+    /// Taxas de I/O por dispositivo desde o snapshot anterior (/proc/diskstats);
+    /// vazio no primeiro snapshot
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub disks: BTreeMap<String, DiskIo>,
+}
+
+/// Médias de pressão (PSI) de CPU, memória e I/O
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct Pressure {
+    pub cpu: Option<PressureStall>,
+    pub memory: Option<PressureStall>,
+    pub io: Option<PressureStall>,
+}
+
+/// Linhas `some` e `full` de um arquivo de /proc/pressure
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct PressureStall {
+    /// Tempo em que ao menos uma tarefa ficou parada esperando o recurso
+    pub some: PressureAverages,
+    /// Tempo em que todas as tarefas ficaram paradas (ausente em cpu nos
+    /// kernels antigos)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full: Option<PressureAverages>,
+}
+
+/// Percentual do tempo parado nas janelas de 10s, 60s e 300s
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct PressureAverages {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+}
+
+/// Taxas de I/O de um dispositivo de bloco
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct DiskIo {
+    /// Leituras concluídas por segundo
+    pub reads_per_sec: f64,
+    /// Escritas concluídas por segundo
+    pub writes_per_sec: f64,
+    /// Bytes lidos por segundo
+    pub read_bytes_per_sec: f64,
+    /// Bytes escritos por segundo
+    pub write_bytes_per_sec: f64,
+    /// Percentual do tempo com I/O em andamento
+    pub busy_percent: f32,
+}
+
+/// Escopo em que um recurso é limitado
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceScope {
+    /// Sem limite no cgroup (ou limite acima do host): valem os valores do host
+    Host,
+    /// O cgroup limita o recurso abaixo do host: valem os valores do cgroup
+    Cgroup,
+}
+
+/// Limites e uso do cgroup em que o agente está rodando
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct CgroupStats {
+    /// Versão do cgroup (1 ou 2)
+    pub version: u8,
+    /// Caminho do cgroup (ex.: `/system.slice/snapshot_agent.service`)
+    pub path: String,
+    /// Runtime de container detectado (`docker`, `podman`, `kubernetes`, `lxc`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// Escopo dos valores de memória que se aplicam ao agente
+    pub memory_scope: ResourceScope,
+    /// Limite de memória (bytes); ausente se ilimitado
+    pub memory_limit: Option<u64>,
+    /// Memória em uso no cgroup, sem o cache de arquivos inativo (bytes)
+    pub memory_usage: u64,
+    /// Escopo do uso de CPU que se aplica ao agente
+    pub cpu_scope: ResourceScope,
+    /// Cota de CPU em núcleos (cpu.max); ausente se ilimitada
+    pub cpu_limit_cores: Option<f64>,
+    /// Uso de CPU do cgroup desde o snapshot anterior, em percentual da cota
+    /// (ou de todos os núcleos do host, sem cota); ausente no primeiro snapshot
+    pub cpu_usage_percent: Option<f32>,
+    /// Processos/threads no cgroup
+    pub pids_current: Option<u64>,
+    /// Limite de processos/threads; ausente se ilimitado
+    pub pids_max: Option<u64>,
+}
+
+/// Sessão de login (entrada USER_PROCESS do utmp/wtmp)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct LoginSession {
+    pub user: String,
+    /// Terminal (ex.: `pts/0`)
+    pub tty: String,
+    /// Host de origem (vazio em logins locais)
+    #[serde(default)]
+    pub host: String,
+    /// Timestamp UNIX do login
+    pub login_time: u64,
+    pub pid: i32,
+}
+
+/// Resultado de um coletor personalizado (comando definido na configuração)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct CustomMetric {
+    /// Código de saída; ausente se o comando não iniciou ou foi morto
+    pub exit_code: Option<i32>,
+    /// Duração da execução (milissegundos)
+    pub duration_ms: u64,
+    /// O comando excedeu o timeout e foi encerrado
+    #[serde(default)]
+    pub timed_out: bool,
+    /// Saída padrão interpretada como JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// Saída padrão bruta, quando não é JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdout: Option<String>,
+}
+
+/// Estados do ciclo de vida do agente
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AgentStatus {
+    Started,
+    Stopped,
+    Reloaded,
+    /// Estado gravado por uma versão mais nova do agente
+    #[serde(other)]
+    Unknown,
+}
+
+impl AgentStatus {
+    /// Nome do estado como aparece no log
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AgentStatus::Started => "STARTED",
+            AgentStatus::Stopped => "STOPPED",
+            AgentStatus::Reloaded => "RELOADED",
+            AgentStatus::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// Mudança de estado do agente (iniciado/parado/recarregado)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct ServiceStatus {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    pub status: AgentStatus,
+    /// Motivo da mudança (ex.: sinal recebido)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Porta que passou a escutar (PORT_OPENED) ou deixou de escutar (PORT_CLOSED)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct PortEvent {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    /// Socket aberto ou, em PORT_CLOSED, o último estado conhecido
+    pub socket: ListeningSocket,
+}
+
+/// Pacote instalado (dpkg ou rpm)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct Package {
+    pub name: String,
+    /// Versão completa (`epoch:versão-revisão`; epoch omitido quando zero)
+    pub version: String,
+    pub architecture: String,
+}
+
+/// Estado de uma unidade systemd (`systemctl list-units --all`)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct UnitState {
+    pub name: String,
+    /// `loaded`, `not-found`, `masked`...
+    pub load: String,
+    /// `active`, `inactive`, `failed`...
+    pub active: String,
+    /// Subestado (`running`, `exited`, `dead`...)
+    pub sub: String,
+}
+
+/// Lista completa de pacotes e unidades (gravada com baixa frequência)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct PackageInventory {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    /// Gerenciador de pacotes (`dpkg` ou `rpm`); ausente se nenhum foi encontrado
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manager: Option<String>,
+    #[serde(default)]
+    pub packages: Vec<Package>,
+    #[serde(default)]
+    pub units: Vec<UnitState>,
+}
+
+/// Pacote instalado, removido ou com a versão alterada entre inventários
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct PackageChange {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    pub name: String,
+    pub architecture: String,
+    /// `installed`, `removed`, `upgraded` ou `downgraded`
+    pub change: String,
+    /// Versão anterior (ausente em `installed`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_version: Option<String>,
+    /// Versão atual (ausente em `removed`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_version: Option<String>,
+}
+
+/// Unidade systemd que entrou em falha ou se recuperou
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct UnitChange {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    /// `failed` ou `recovered`
+    pub change: String,
+    /// Estado no inventário anterior (ausente se a unidade é nova)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old: Option<UnitState>,
+    /// Estado atual (ausente se a unidade deixou de existir)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new: Option<UnitState>,
+}
+
+/// Erro encontrado pelo agente
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct LogError {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    /// Mensagem de erro detalhada
+    pub error: String,
+}
+
+/// Descrição estática do host
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct HostInventory {
+    /// Distribuição (PRETTY_NAME de /etc/os-release)
+    pub os_release: String,
+    pub kernel: String,
+    /// Arquitetura da CPU (ex.: x86_64)
+    pub architecture: String,
+    pub cpu_model: String,
+    /// Núcleos físicos
+    pub cpu_cores: usize,
+    /// CPUs lógicas
+    pub cpu_threads: usize,
+    /// Memória total (bytes)
+    pub total_memory: u64,
+    /// Timestamp UNIX do boot
+    pub boot_time: u64,
+    pub boot_datetime: String,
+    pub uptime_secs: u64,
+    pub agent_version: String,
+}
+
+/// Inventário do host, gravado junto ao registro STARTED
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct Inventory {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    pub inventory: HostInventory,
+}
+
+/// Mudança de estado de um item da lista de processos monitorados
+/// Gravado como `PROCESS_DOWN` ou `PROCESS_UP`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct ProcessEvent {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    /// Nome do item na configuração
+    pub name: String,
+    /// O que foi verificado (ex.: `process:nginx`, `unit:nginx.service`)
+    pub target: String,
+    /// PIDs encontrados (UP) ou os últimos vistos rodando (DOWN)
+    #[serde(default)]
+    pub pids: Vec<u32>,
+    /// Timestamp UNIX de início do processo mais antigo, quando conhecido
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    /// Última verificação em que o item estava rodando (DOWN)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<u64>,
+    /// Resultado do comando de reinício configurado (DOWN)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<RestartResult>,
+}
+
+/// Resultado do comando de reinício de um processo monitorado
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct RestartResult {
+    /// Código de saída; ausente se o comando não iniciou ou foi morto
+    pub exit_code: Option<i32>,
+    /// Duração da execução (milissegundos)
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub timed_out: bool,
+}
+
+/// Abertura ou encerramento de uma sessão de login (wtmp)
+/// Gravado como `SESSION_OPENED` ou `SESSION_CLOSED`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct SessionEvent {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    pub session: LoginSession,
+    /// Timestamp UNIX do logout (SESSION_CLOSED)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logout_time: Option<u64>,
+}
+
+/// Falhas de autenticação encontradas nos logs de autenticação desde o
+/// registro anterior
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct AuthFailures {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    /// Tentativas de login SSH que falharam
+    pub ssh_failures: u64,
+    /// Senhas incorretas no sudo
+    pub sudo_failures: u64,
+    /// Falhas por usuário (alvo do login SSH ou quem executou o sudo)
+    #[serde(default)]
+    pub by_user: BTreeMap<String, u64>,
+    /// Falhas SSH por endereço de origem
+    #[serde(default)]
+    pub by_source: BTreeMap<String, u64>,
+}
+
+/// Estado de um arquivo monitorado (baseline do FIM)
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct FileState {
+    /// SHA-256 do conteúdo; ausente em diretórios, arquivos grandes demais
+    /// ou sem permissão de leitura
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    pub size: u64,
+    /// Permissões em octal (ex.: `0644`)
+    pub mode: String,
+    pub uid: u32,
+    pub gid: u32,
+    /// Nome do dono (ou o uid, se desconhecido)
+    pub owner: String,
+    /// Nome do grupo (ou o gid, se desconhecido)
+    pub group: String,
+    /// Timestamp UNIX da última modificação
+    pub mtime: u64,
+}
+
+/// Alteração detectada pelo monitoramento de integridade de arquivos
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+pub struct FileChange {
+    #[serde(default = "legacy_version")]
+    pub schema_version: u32,
+    pub agent_id: String,
+    #[serde(default)]
+    pub hostname: String,
+    pub datetime: String,
+    pub timestamp: u64,
+    pub path: String,
+    /// O que mudou: `created`, `deleted`, `content`, `mode`, `owner`, `group`
+    pub changed: Vec<String>,
+    /// Estado no baseline (ausente se o arquivo foi criado)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old: Option<FileState>,
+    /// Estado atual (ausente se o arquivo foi removido)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new: Option<FileState>,
+}